@@ -0,0 +1,86 @@
+//! Builds a Polyglot opening book from PGN files.
+//!
+//! ```text
+//! cargo run --release --example polyglot_book -- [options] -o book.bin games.pgn...
+//!
+//! options:
+//!     --min-rating N       only use games where both players are rated at least N
+//!     --max-ply N          only record the first N half-moves of each game
+//!     --min-count N        only keep moves played at least N times
+//!     --weights W D L      score of a win, draw and loss (default 2 1 0)
+//! ```
+
+extern crate pleco;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::process;
+
+use pleco::tools::polyglot::BookBuilder;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: polyglot_book [--min-rating N] [--max-ply N] [--min-count N] \
+         [--weights W D L] -o OUTPUT PGN..."
+    );
+    process::exit(1);
+}
+
+fn number<T: std::str::FromStr>(arg: Option<String>) -> T {
+    arg.and_then(|s| s.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() {
+    let mut builder = BookBuilder::new();
+    let mut output = None;
+    let mut inputs = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--min-rating" => builder = builder.min_rating(number(args.next())),
+            "--max-ply" => builder = builder.max_ply(number(args.next())),
+            "--min-count" => builder = builder.min_count(number(args.next())),
+            "--weights" => {
+                let win = number(args.next());
+                let draw = number(args.next());
+                let loss = number(args.next());
+                builder = builder.result_weights(win, draw, loss);
+            }
+            "-o" | "--output" => output = Some(args.next().unwrap_or_else(|| usage())),
+            _ if arg.starts_with('-') => usage(),
+            _ => inputs.push(arg),
+        }
+    }
+
+    let output = output.unwrap_or_else(|| usage());
+    if inputs.is_empty() {
+        usage();
+    }
+
+    for input in inputs.iter() {
+        let file = File::open(input).unwrap_or_else(|e| {
+            eprintln!("unable to open {}: {}", input, e);
+            process::exit(1);
+        });
+        match builder.add_pgn(BufReader::new(file)) {
+            Ok(games) => println!("{}: {} games", input, games),
+            Err(e) => {
+                eprintln!("{}: {}", input, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let entries = builder.entries().len();
+    let file = File::create(&output).unwrap_or_else(|e| {
+        eprintln!("unable to create {}: {}", output, e);
+        process::exit(1);
+    });
+    if let Err(e) = builder.write(BufWriter::new(file)) {
+        eprintln!("unable to write {}: {}", output, e);
+        process::exit(1);
+    }
+    println!("wrote {} entries to {}", entries, output);
+}
@@ -0,0 +1,405 @@
+//! Whole-board views of the attacks in a position, such as which squares each player controls,
+//! which pieces are hanging, and which are pinned.
+//!
+//! These build on the same lookup tables as [`Board::attackers_to`] and [`Board::attacks_from`],
+//! but look at every piece at once, which is what GUIs and analysis tools tend to need.
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::{Board,Player,SQ};
+//!
+//! // The pawn on e5 is attacked by the knight on f3, and the bishop on b5 pins the knight on c6
+//! // to the king
+//! let board = Board::from_fen("r1bqkb1r/ppp2ppp/2np1n2/1B2p3/4P3/2N2N2/PPPP1PPP/R1BQK2R w KQkq - 0 5").unwrap();
+//! assert!((board.attacked_squares(Player::White) & SQ::E5.to_bb()).is_not_empty());
+//! assert_eq!(board.control_map().attackers(SQ::E5), 1);
+//! assert_eq!(board.control_map().defenders(SQ::E5), 2);
+//!
+//! let pins = board.pins(Player::Black);
+//! assert_eq!(pins.len(), 1);
+//! assert_eq!(pins[0].pinned, SQ::C6);
+//! assert_eq!(pins[0].pinner, SQ::B5);
+//! assert_eq!(pins[0].pinned_to, SQ::E8);
+//! ```
+//!
+//! [`Board::attackers_to`]: ../struct.Board.html#method.attackers_to
+//! [`Board::attacks_from`]: ../struct.Board.html#method.attacks_from
+
+use super::Board;
+use core::bitboard::BitBoard;
+use core::masks::{PLAYER_CNT, SQ_CNT};
+use core::sq::SQ;
+use core::{Piece, PieceType, Player, Rank, ALL_PLAYERS};
+use helper::prelude::*;
+
+/// The number of pieces of each player attacking each square of a `Board`.
+///
+/// Only direct attacks are counted, so a rook behind another rook on the same file only counts
+/// for the squares up to the first rook.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ControlMap {
+    counts: [[u8; SQ_CNT]; PLAYER_CNT],
+    occupied: [BitBoard; PLAYER_CNT],
+}
+
+impl ControlMap {
+    /// Returns the number of a player's pieces attacking a square.
+    #[inline]
+    pub fn count(&self, player: Player, sq: SQ) -> u8 {
+        self.counts[player as usize][sq.0 as usize]
+    }
+
+    /// Returns the number of pieces attacking the piece on a square, meaning pieces of the other
+    /// player than the piece's. Empty squares have no attackers.
+    pub fn attackers(&self, sq: SQ) -> u8 {
+        match self.owner(sq) {
+            Some(player) => self.count(!player, sq),
+            None => 0,
+        }
+    }
+
+    /// Returns the number of pieces defending the piece on a square, meaning other pieces of the
+    /// same player as the piece. Empty squares have no defenders.
+    pub fn defenders(&self, sq: SQ) -> u8 {
+        match self.owner(sq) {
+            Some(player) => self.count(player, sq),
+            None => 0,
+        }
+    }
+
+    /// Returns the player attacking a square with more pieces, or `None` if both attack it
+    /// with as many.
+    pub fn controller(&self, sq: SQ) -> Option<Player> {
+        let white = self.count(Player::White, sq);
+        let black = self.count(Player::Black, sq);
+        if white > black {
+            Some(Player::White)
+        } else if black > white {
+            Some(Player::Black)
+        } else {
+            None
+        }
+    }
+
+    fn owner(&self, sq: SQ) -> Option<Player> {
+        ALL_PLAYERS
+            .iter()
+            .cloned()
+            .find(|player| (self.occupied[*player as usize] & sq.to_bb()).is_not_empty())
+    }
+}
+
+/// A piece that can't move off a line without exposing a more valuable piece behind it, or
+/// its king, to an attack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Pin {
+    /// The square of the pinned piece.
+    pub pinned: SQ,
+    /// The square of the sliding piece pinning it.
+    pub pinner: SQ,
+    /// The square of the piece it is pinned to.
+    pub pinned_to: SQ,
+}
+
+impl Board {
+    /// Returns the squares attacked by a player's pieces.
+    ///
+    /// A square is attacked even if it's occupied by one of the player's own pieces.
+    pub fn attacked_squares(&self, player: Player) -> BitBoard {
+        self.attacked_squares_through(player, self.occupied())
+    }
+
+    /// Returns a `ControlMap` of the number of pieces of each player attacking each square.
+    pub fn control_map(&self) -> ControlMap {
+        let mut counts = [[0; SQ_CNT]; PLAYER_CNT];
+        for player in ALL_PLAYERS.iter() {
+            let mut pieces = self.get_occupied_player(*player);
+            while let Some(sq) = pieces.pop_some_lsb() {
+                let piece = self.piece_at_sq(sq).type_of();
+                for attacked in self.attacks_from(piece, sq, *player) {
+                    counts[*player as usize][attacked.0 as usize] += 1;
+                }
+            }
+        }
+        ControlMap {
+            counts,
+            occupied: [
+                self.get_occupied_player(Player::White),
+                self.get_occupied_player(Player::Black),
+            ],
+        }
+    }
+
+    /// Returns a player's hanging pieces, the ones the other player wins material by capturing.
+    ///
+    /// A piece is hanging if it is attacked and either undefended or not defended well enough,
+    /// as decided by [`Board::see_square`]. Kings are never hanging.
+    ///
+    /// [`Board::see_square`]: #method.see_square
+    pub fn hanging_pieces(&self, player: Player) -> BitBoard {
+        let mut attacked = self.attacked_squares(!player)
+            & self.get_occupied_player(player)
+            & !self.piece_bb(player, PieceType::K);
+        let mut hanging = BitBoard(0);
+        while let Some(sq) = attacked.pop_some_lsb() {
+            if self.see_square(sq, !player) > 0 {
+                hanging |= sq.to_bb();
+            }
+        }
+        hanging
+    }
+
+    /// Returns a player's pieces that are attacked by a piece of lower value.
+    ///
+    /// Knights and bishops are counted as having the same value, so a bishop attacked by a
+    /// knight isn't included. Kings are never included.
+    pub fn attacked_by_lower(&self, player: Player) -> BitBoard {
+        let them = !player;
+        let by_pawns = self.piece_attacks(them, PieceType::P);
+        let by_minors = by_pawns
+            | self.piece_attacks(them, PieceType::N)
+            | self.piece_attacks(them, PieceType::B);
+        let by_rooks = by_minors | self.piece_attacks(them, PieceType::R);
+
+        (by_pawns & self.piece_two_bb(PieceType::N, PieceType::B, player))
+            | (by_minors & self.piece_bb(player, PieceType::R))
+            | (by_rooks & self.piece_bb(player, PieceType::Q))
+    }
+
+    /// Returns the pins against a player's pieces.
+    ///
+    /// A piece is pinned when it stands between a sliding piece of the other player and either
+    /// the player's king or a piece of higher value than itself. Knights and bishops are counted
+    /// as having the same value.
+    pub fn pins(&self, player: Player) -> Vec<Pin> {
+        let occupied = self.occupied();
+        let ours = self.get_occupied_player(player);
+        let mut pins = Vec::new();
+
+        let mut targets = ours;
+        while let Some(target) = targets.pop_some_lsb() {
+            let target_class = material_class(self.piece_at_sq(target).type_of());
+            let mut snipers = self.get_occupied_player(!player)
+                & ((rook_moves(BitBoard(0), target)
+                    & self.piece_two_bb_both_players(PieceType::R, PieceType::Q))
+                    | (bishop_moves(BitBoard(0), target)
+                        & self.piece_two_bb_both_players(PieceType::B, PieceType::Q)));
+
+            while let Some(pinner) = snipers.pop_some_lsb() {
+                let between = between_bb(target, pinner) & occupied;
+                if between.is_empty() || between.more_than_one() || (between & ours).is_empty() {
+                    continue;
+                }
+                let pinned = between.to_sq();
+                if material_class(self.piece_at_sq(pinned).type_of()) < target_class {
+                    pins.push(Pin {
+                        pinned,
+                        pinner,
+                        pinned_to: target,
+                    });
+                }
+            }
+        }
+        pins
+    }
+
+    /// Returns the number of squares the piece on a square can move to, or zero for an empty
+    /// square.
+    ///
+    /// This counts the squares the piece attacks that aren't occupied by its own player's
+    /// pieces, or a pawn's pushes and captures. A piece pinned to its king can only move along
+    /// the pin, and a king can't move onto squares the other player attacks, including those
+    /// behind it on the line of a sliding piece. Checks and castling aren't taken into account.
+    pub fn mobility(&self, sq: SQ) -> u8 {
+        let piece = self.piece_at_sq(sq);
+        if piece == Piece::None {
+            return 0;
+        }
+        let player = piece.player_lossy();
+        let piece_type = piece.type_of();
+
+        let mut moves = if piece_type == PieceType::P {
+            self.pawn_pushes(sq, player)
+                | (pawn_attacks_from(sq, player) & self.get_occupied_player(!player))
+        } else {
+            self.attacks_from(piece_type, sq, player) & !self.get_occupied_player(player)
+        };
+
+        if piece_type == PieceType::K {
+            // The king doesn't block attacks on the squares behind it
+            moves &= !self.attacked_squares_through(!player, self.occupied() ^ sq.to_bb());
+        } else if (self.pinned_pieces(player) & sq.to_bb()).is_not_empty() {
+            moves &= line_bb(sq, self.king_sq(player));
+        }
+        moves.count_bits()
+    }
+
+    // Returns the squares attacked by a player's pieces, with the sliding pieces blocked only
+    // by the given occupied squares.
+    fn attacked_squares_through(&self, player: Player, occupied: BitBoard) -> BitBoard {
+        let mut attacked = BitBoard(0);
+        let mut pieces = self.get_occupied_player(player);
+        while let Some(sq) = pieces.pop_some_lsb() {
+            attacked |= match self.piece_at_sq(sq).type_of() {
+                PieceType::B => bishop_moves(occupied, sq),
+                PieceType::R => rook_moves(occupied, sq),
+                PieceType::Q => queen_moves(occupied, sq),
+                piece => self.attacks_from(piece, sq, player),
+            };
+        }
+        attacked
+    }
+
+    // Returns the squares attacked by all of a player's pieces of a type.
+    fn piece_attacks(&self, player: Player, piece: PieceType) -> BitBoard {
+        let mut attacked = BitBoard(0);
+        let mut pieces = self.piece_bb(player, piece);
+        while let Some(sq) = pieces.pop_some_lsb() {
+            attacked |= self.attacks_from(piece, sq, player);
+        }
+        attacked
+    }
+
+    // Returns the squares a pawn can push to.
+    fn pawn_pushes(&self, sq: SQ, player: Player) -> BitBoard {
+        let mut pushes = BitBoard(0);
+        let mut from = sq;
+        for _ in 0..2 {
+            if player.relative_rank_of_sq(from) == Rank::R8 {
+                break;
+            }
+            let to = SQ((from.0 as i8 + player.pawn_push()) as u8);
+            if self.piece_at_sq(to) != Piece::None {
+                break;
+            }
+            pushes |= to.to_bb();
+            if player.relative_rank_of_sq(sq) != Rank::R2 {
+                break;
+            }
+            from = to;
+        }
+        pushes
+    }
+}
+
+// Orders the piece types by value, with knights and bishops counted as equal.
+fn material_class(piece: PieceType) -> u8 {
+    match piece {
+        PieceType::P => 1,
+        PieceType::N | PieceType::B => 3,
+        PieceType::R => 5,
+        PieceType::Q => 9,
+        _ => u8::MAX,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attacked_squares_and_control() {
+        let board = Board::start_pos();
+        let white = board.attacked_squares(Player::White);
+        assert_eq!(white.count_bits(), 22);
+        assert!((white & SQ::F3.to_bb()).is_not_empty());
+        assert!((white & SQ::E4.to_bb()).is_empty());
+
+        let control = board.control_map();
+        assert_eq!(control.count(Player::White, SQ::F3), 3);
+        assert_eq!(control.count(Player::Black, SQ::F3), 0);
+        assert_eq!(control.controller(SQ::F3), Some(Player::White));
+        assert_eq!(control.controller(SQ::E4), None);
+        assert_eq!(control.defenders(SQ::E2), 4);
+        assert_eq!(control.attackers(SQ::E2), 0);
+        assert_eq!(control.defenders(SQ::E4), 0);
+
+        let board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let control = board.control_map();
+        assert_eq!(control.attackers(SQ::D5), 1);
+        assert_eq!(control.attackers(SQ::E4), 1);
+        assert_eq!(control.defenders(SQ::E4), 0);
+    }
+
+    #[test]
+    fn hanging_and_threats() {
+        // The knight on d5 is defended by a pawn but attacked by another, while the rook on a4
+        // and the queen on h5 are undefended but not attacked
+        let board = Board::from_fen("4k3/8/2p5/3n3q/r3P3/8/8/4K3 w - - 0 1").unwrap();
+        let hanging = board.hanging_pieces(Player::Black);
+        assert!((hanging & SQ::D5.to_bb()).is_not_empty());
+        assert!((hanging & SQ::A4.to_bb()).is_empty());
+        assert!((hanging & SQ::H5.to_bb()).is_empty());
+        assert_eq!(board.attacked_by_lower(Player::Black), SQ::D5.to_bb());
+        assert!((board.hanging_pieces(Player::White) & SQ::E4.to_bb()).is_not_empty());
+
+        // A bishop attacked by a knight is not attacked by a lower piece
+        let board = Board::from_fen("4k3/8/8/3b4/8/4N3/8/4K3 b - - 0 1").unwrap();
+        assert!(board.attacked_by_lower(Player::Black).is_empty());
+        assert_eq!(board.hanging_pieces(Player::Black), SQ::D5.to_bb());
+
+        // A queen attacked by a rook
+        let board = Board::from_fen("3qk3/8/8/8/8/8/8/3RK3 b - - 0 1").unwrap();
+        assert_eq!(board.attacked_by_lower(Player::Black), SQ::D8.to_bb());
+    }
+
+    #[test]
+    fn pins() {
+        // Rook and knight pinned to the queen
+        let board = Board::from_fen("4k3/8/8/8/1b6/2N5/8/q2RQ1K1 w - - 0 1").unwrap();
+        assert_eq!(
+            board.pins(Player::White),
+            vec![
+                Pin {
+                    pinned: SQ::D1,
+                    pinner: SQ::A1,
+                    pinned_to: SQ::E1,
+                },
+                Pin {
+                    pinned: SQ::C3,
+                    pinner: SQ::B4,
+                    pinned_to: SQ::E1,
+                },
+            ]
+        );
+
+        // Rook pinned to the king
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/q2R1K2 w - - 0 1").unwrap();
+        assert_eq!(
+            board.pins(Player::White),
+            vec![Pin {
+                pinned: SQ::D1,
+                pinner: SQ::A1,
+                pinned_to: SQ::F1,
+            }]
+        );
+        assert!(board.pins(Player::Black).is_empty());
+
+        // A knight in front of a bishop isn't pinned
+        let board = Board::from_fen("4k3/8/8/8/8/2B5/1N6/b3K3 w - - 0 1").unwrap();
+        assert!(board.pins(Player::White).is_empty());
+    }
+
+    #[test]
+    fn mobility() {
+        let board = Board::start_pos();
+        assert_eq!(board.mobility(SQ::G1), 2);
+        assert_eq!(board.mobility(SQ::E2), 2);
+        assert_eq!(board.mobility(SQ::D1), 0);
+        assert_eq!(board.mobility(SQ::E4), 0);
+
+        // The pinned rook can only move along the file, the king can't step onto a square
+        // the pawn attacks
+        let board = Board::from_fen("4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.mobility(SQ::E2), 6);
+        assert_eq!(board.mobility(SQ::E1), 4);
+        let board = Board::from_fen("6k1/8/8/8/8/3p4/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.mobility(SQ::E1), 4);
+        assert_eq!(board.mobility(SQ::D3), 1);
+
+        // The king can't step back along the rank of the rook checking it
+        let board = Board::from_fen("6k1/8/8/8/8/8/8/r3K3 w - - 0 1").unwrap();
+        assert_eq!(board.mobility(SQ::E1), 3);
+    }
+}
@@ -10,7 +10,10 @@
 //! [`BoardState`]: struct.BoardState.html
 //! [`Board`]: ../struct.Board.html
 
+use std::ops::Deref;
+
 use super::castle_rights::Castling;
+use super::variant::Variant;
 use super::Board;
 
 use core::bitboard::BitBoard;
@@ -21,16 +24,15 @@ use core::sq::{NO_SQ, SQ};
 use core::*;
 
 use helper::prelude::*;
-use tools::pleco_arc::Arc;
+
+/// The number of `BoardState`s a new `StateStack` has room for before it needs to reallocate.
+const STATE_STACK_RESERVE: usize = 16;
 
 /// Holds useful information concerning the current state of the [`Board`].
 ///
 /// This is information that is computed upon making a move, and requires expensive computation to do so as well.
-/// It is stored in the Heap by [`Board`] as an `Arc<BoardState>`, as cloning the board can lead to multiple
-/// references to the same `BoardState`.
-///
-/// Allows for easy undo-ing of moves as these keep track of their previous board state, forming a
-/// Tree-like persistent Stack.
+/// The [`Board`] keeps the `BoardState` of every position it has reached in a preallocated stack,
+/// which allows for easy undo-ing of moves by popping the current state.
 ///
 /// [`Board`]: ../struct.Board.html
 #[derive(Clone)]
@@ -47,6 +49,13 @@ pub struct BoardState {
     /// the push. So, `ep_square = abs(sq_to - sq_from) / 2`. If the last move was not
     /// a double pawn push, then `ep_square = NO_SQ`.
     pub ep_square: SQ,
+    /// The number of checks given by each player. Only counted in Three-check.
+    pub checks: [u8; PLAYER_CNT],
+    /// The number of each piece type held in each player's pocket. Only used in Crazyhouse.
+    pub pockets: [[u8; PIECE_TYPE_CNT]; PLAYER_CNT],
+    /// The pieces that were promoted from pawns, which return to a pocket as pawns when
+    /// captured. Only used in Crazyhouse.
+    pub promoted: BitBoard,
 
     /// The positional score of the board.
     pub psq: Score,
@@ -62,6 +71,9 @@ pub struct BoardState {
     pub nonpawn_material: [Value; PLAYER_CNT],
     /// The last captured Piece, if any.
     pub captured_piece: PieceType,
+    /// The pieces exploded by the last capture, in order of the squares around and including
+    /// the capture square. Only used in Atomic.
+    pub exploded: [Piece; 9],
     /// A `BitBoard` of the current pieces giving check.
     pub checkers_bb: BitBoard,
     /// Per each player, `BitBoard` of pieces blocking an attack on a that player's king.
@@ -77,8 +89,6 @@ pub struct BoardState {
     /// The previous move, if any, that was played. Returns `BitMove::NULL` if there was no
     /// previous move played.
     pub prev_move: BitMove,
-    /// Previous State of the board (from one move ago).
-    pub prev: Option<Arc<BoardState>>,
 }
 
 impl BoardState {
@@ -89,24 +99,27 @@ impl BoardState {
             rule_50: 0,
             ply: 0,
             ep_square: NO_SQ,
+            checks: [0; PLAYER_CNT],
+            pockets: [[0; PIECE_TYPE_CNT]; PLAYER_CNT],
+            promoted: BitBoard(0),
             psq: Score::ZERO,
             zobrist: 0,
             pawn_key: 0,
             material_key: 0,
             nonpawn_material: [0; PLAYER_CNT],
             captured_piece: PieceType::None,
+            exploded: [Piece::None; 9],
             checkers_bb: BitBoard(0),
             blockers_king: [BitBoard(0); PLAYER_CNT],
             pinners_king: [BitBoard(0); PLAYER_CNT],
             check_sqs: [BitBoard(0); PIECE_TYPE_CNT],
             prev_move: BitMove::null(),
-            prev: None,
         }
     }
 
     /// Constructs a partial clone of a `BoardState`.
     ///
-    /// Castling, rule_50, ply, and ep_square are copied. The copied fields need to be
+    /// Castling, rule_50, ply, ep_square, checks, pockets and promoted pieces are copied. The copied fields need to be
     /// modified accordingly, and the remaining fields need to be generated.
     pub fn partial_clone(&self) -> BoardState {
         BoardState {
@@ -114,18 +127,21 @@ impl BoardState {
             rule_50: self.rule_50,
             ply: self.ply,
             ep_square: self.ep_square,
+            checks: self.checks,
+            pockets: self.pockets,
+            promoted: self.promoted,
             psq: self.psq,
             zobrist: self.zobrist,
             pawn_key: self.pawn_key,
             material_key: self.material_key,
             nonpawn_material: self.nonpawn_material,
             captured_piece: self.captured_piece,
+            exploded: [Piece::None; 9],
             checkers_bb: BitBoard(0),
             blockers_king: [BitBoard(0); PLAYER_CNT],
             pinners_king: [BitBoard(0); PLAYER_CNT],
             check_sqs: [BitBoard(0); PIECE_TYPE_CNT],
             prev_move: BitMove::null(),
-            prev: self.get_prev(),
         }
     }
 
@@ -137,12 +153,7 @@ impl BoardState {
         self.pawn_key = z_no_pawns();
         self.nonpawn_material = [0; 2];
 
-        let us = board.turn;
-        let them = !us;
-        let ksq = board.king_sq(us);
-
-        self.checkers_bb =
-            board.attackers_to(ksq, board.occupied()) & board.bbs_player[them as usize];
+        self.checkers_bb = board.king_attackers(board.turn, board.occupied());
 
         self.set_check_info(board);
         self.set_zob_hash(board);
@@ -155,28 +166,54 @@ impl BoardState {
     /// Specifically, sets Blockers, Pinners, and Check Squares for each piece.
     ///
     /// The `checkers_bb` must beset before this method can be used.
+    ///
+    /// A player without a king, such as White in Horde, has no blockers or pinners, and no
+    /// check squares. Neither do the players of Antichess, where kings are ordinary pieces.
     pub(crate) fn set_check_info(&mut self, board: &Board) {
-        let mut white_pinners: BitBoard = BitBoard(0);
-
-        self.blockers_king[Player::White as usize] = board.slider_blockers(
-            board.occupied_black(),
-            board.king_sq(Player::White),
-            &mut white_pinners,
-        );
-
-        self.pinners_king[Player::White as usize] = white_pinners;
-
-        let mut black_pinners: BitBoard = BitBoard(0);
-
-        self.blockers_king[Player::Black as usize] = board.slider_blockers(
-            board.occupied_white(),
-            board.king_sq(Player::Black),
-            &mut black_pinners,
-        );
+        if board.variant == Variant::Standard {
+            for player in ALL_PLAYERS.iter() {
+                let mut pinners: BitBoard = BitBoard(0);
+                self.blockers_king[*player as usize] = board.slider_blockers(
+                    board.get_occupied_player(!*player),
+                    board.king_sq(*player),
+                    &mut pinners,
+                );
+                self.pinners_king[*player as usize] = pinners;
+            }
+            self.set_check_sqs(board, board.king_sq(board.turn.other_player()));
+            return;
+        }
+        if board.variant == Variant::Antichess {
+            self.blockers_king = [BitBoard(0); PLAYER_CNT];
+            self.pinners_king = [BitBoard(0); PLAYER_CNT];
+            self.check_sqs = [BitBoard(0); PIECE_TYPE_CNT];
+            return;
+        }
+        for player in ALL_PLAYERS.iter() {
+            let mut pinners: BitBoard = BitBoard(0);
+            let king = board.piece_bb(*player, PieceType::K);
+            self.blockers_king[*player as usize] = if king.is_empty() {
+                BitBoard(0)
+            } else {
+                board.slider_blockers(
+                    board.get_occupied_player(!*player),
+                    king.to_sq(),
+                    &mut pinners,
+                )
+            };
+            self.pinners_king[*player as usize] = pinners;
+        }
 
-        self.pinners_king[Player::Black as usize] = black_pinners;
+        let them_king = board.piece_bb(board.turn.other_player(), PieceType::K);
+        if them_king.is_empty() {
+            self.check_sqs = [BitBoard(0); PIECE_TYPE_CNT];
+            return;
+        }
+        self.set_check_sqs(board, them_king.to_sq());
+    }
 
-        let ksq: SQ = board.king_sq(board.turn.other_player());
+    // Sets the squares where each piece would give check to the king on `ksq`
+    fn set_check_sqs(&mut self, board: &Board, ksq: SQ) {
         let occupied = board.occupied();
 
         self.check_sqs[PieceType::P as usize] = pawn_attacks_from(ksq, board.turn.other_player());
@@ -202,6 +239,17 @@ impl BoardState {
         }
 
         self.zobrist ^= z_castle(self.castling.bits());
+        self.zobrist ^= z_checks(Player::White, self.checks[Player::White as usize])
+            ^ z_checks(Player::Black, self.checks[Player::Black as usize]);
+        for player in ALL_PLAYERS.iter() {
+            for piece in ALL_PIECE_TYPES.iter() {
+                self.zobrist ^= z_pocket(
+                    *player,
+                    *piece,
+                    self.pockets[*player as usize][*piece as usize],
+                );
+            }
+        }
 
         let ep = self.ep_square;
         if ep != NO_SQ {
@@ -236,24 +284,6 @@ impl BoardState {
         }
     }
 
-    /// Return the previous BoardState from one move ago.
-    ///
-    /// If there was no previous state, returns `None`.
-    #[inline]
-    pub fn get_prev(&self) -> Option<Arc<BoardState>> {
-        self.prev.as_ref().cloned()
-    }
-
-    /// Iterates through all previous `BoardStates` and prints debug information for each.
-    ///
-    /// Used primarily for debugging.
-    pub fn backtrace(&self) {
-        self.print_info();
-        if let Some(ref prev) = self.prev {
-            prev.backtrace();
-        }
-    }
-
     /// Prints information about the current `BoardState`.
     pub fn print_info(&self) {
         print!("ply: {}, move played: {} ", self.ply, self.prev_move);
@@ -269,11 +299,104 @@ impl PartialEq for BoardState {
         self.castling == other.castling
             && self.rule_50 == other.rule_50
             && self.ep_square == other.ep_square
+            && self.checks == other.checks
+            && self.pockets == other.pockets
+            && self.promoted == other.promoted
             && self.zobrist == other.zobrist
             && self.captured_piece == other.captured_piece
+            && self.exploded == other.exploded
             && self.checkers_bb == other.checkers_bb
             && self.blockers_king == other.blockers_king
             && self.pinners_king == other.pinners_king
             && self.check_sqs == other.check_sqs
     }
 }
+
+/// The stack of `BoardState`s held by a [`Board`], one for each position reached, with the
+/// state of the current position on top.
+///
+/// A new stack reserves room for a few states, and grows as more moves are applied.
+/// Dereferences to the current `BoardState`.
+///
+/// [`Board`]: ../struct.Board.html
+#[derive(Clone)]
+pub(crate) struct StateStack {
+    states: Vec<BoardState>,
+}
+
+impl StateStack {
+    /// Creates a stack holding only the given state.
+    pub fn new(state: BoardState) -> StateStack {
+        let mut states = Vec::with_capacity(STATE_STACK_RESERVE);
+        states.push(state);
+        StateStack { states }
+    }
+
+    /// Pushes the state of a newly reached position.
+    #[inline(always)]
+    pub fn push(&mut self, state: BoardState) {
+        self.states.push(state);
+    }
+
+    /// Pops the current state, returning to the state of the previous position. There must be
+    /// a previous state to return to.
+    #[inline(always)]
+    pub fn pop(&mut self) {
+        debug_assert!(self.has_prev());
+        self.states.pop();
+    }
+
+    /// Returns if there is a state to return to.
+    #[inline(always)]
+    pub fn has_prev(&self) -> bool {
+        self.states.len() > 1
+    }
+
+    /// Returns every state in the stack, from the oldest to the current one.
+    #[inline(always)]
+    pub fn history(&self) -> &[BoardState] {
+        &self.states
+    }
+
+    /// Returns the number of states before the current one that were reached since the last
+    /// capture or pawn move. No earlier position can repeat.
+    #[inline]
+    fn reversible(&self) -> usize {
+        (self.rule_50.max(0) as usize).min(self.states.len() - 1)
+    }
+
+    /// Returns the number of times the current position has occurred before.
+    ///
+    /// As each state has the opposite player to move of the one before, only every other state
+    /// needs to be looked at.
+    pub fn repetitions(&self) -> usize {
+        let current = self.states.len() - 1;
+        let zobrist = self.zobrist;
+        self.states[current - self.reversible()..current]
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|state| state.zobrist == zobrist)
+            .count()
+    }
+
+    /// Clones only the states reached since the last capture or pawn move, which are all that's
+    /// needed to find repetitions.
+    pub fn clone_reversible(&self) -> StateStack {
+        let start = self.states.len() - 1 - self.reversible();
+        StateStack {
+            states: self.states[start..].to_vec(),
+        }
+    }
+}
+
+impl Deref for StateStack {
+    type Target = BoardState;
+
+    #[inline(always)]
+    fn deref(&self) -> &BoardState {
+        // The stack is never empty, as it's created with a state and the last state can't be popped.
+        unsafe { self.states.get_unchecked(self.states.len() - 1) }
+    }
+}
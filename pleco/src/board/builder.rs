@@ -0,0 +1,427 @@
+//! Contains the `BoardBuilder`, for constructing a [`Board`] piece by piece.
+//!
+//! Unlike [`Board::from_fen`], a `BoardBuilder` can be edited one square at a time, which makes
+//! it a good fit for position editors. Calling [`BoardBuilder::build`] computes all of the
+//! [`BoardState`] information for the position, and checks that the position is legal.
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::{Board, Piece, Player, SQ};
+//! use pleco::board::builder::BoardBuilder;
+//! use pleco::core::CastleType;
+//!
+//! let board: Board = BoardBuilder::new()
+//!     .place(SQ::E1, Piece::WhiteKing)
+//!     .place(SQ::H1, Piece::WhiteRook)
+//!     .place(SQ::E8, Piece::BlackKing)
+//!     .turn(Player::Black)
+//!     .castle(Player::White, CastleType::KingSide)
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/4K2R b K - 0 1");
+//! ```
+//!
+//! [`Board`]: ../struct.Board.html
+//! [`Board::from_fen`]: ../struct.Board.html#method.from_fen
+//! [`BoardBuilder::build`]: struct.BoardBuilder.html#method.build
+//! [`BoardState`]: ../board_state/struct.BoardState.html
+
+use super::board_state::{BoardState, StateStack};
+use super::castle_rights::{Castling, CastlingSquares};
+use super::{Board, BoardError};
+
+use core::masks::*;
+use core::sq::{NO_SQ, SQ};
+use core::*;
+
+/// The rook a castling right was given for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CastlingRook {
+    /// The outermost rook on the back rank, as in standard FEN and X-FEN.
+    Outermost,
+    /// The rook on a specific file, as in Shredder-FEN.
+    File(File),
+}
+
+/// Builder for a [`Board`], allowing for the position to be set up piece by piece.
+///
+/// A new builder starts from an empty board, with White to move, no castling rights, no
+/// en-passant square, and both move clocks at their starting values.
+///
+/// [`Board`]: ../struct.Board.html
+#[derive(Clone)]
+pub struct BoardBuilder {
+    pieces: [Piece; SQ_CNT],
+    turn: Player,
+    castling: [[Option<CastlingRook>; CASTLING_SIDES]; PLAYER_CNT],
+    ep_square: SQ,
+    rule_50: i16,
+    full_moves: u16,
+    chess960: bool,
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        BoardBuilder::new()
+    }
+}
+
+impl BoardBuilder {
+    /// Creates a `BoardBuilder` with an empty board.
+    pub fn new() -> Self {
+        BoardBuilder {
+            pieces: [Piece::None; SQ_CNT],
+            turn: Player::White,
+            castling: [[None; CASTLING_SIDES]; PLAYER_CNT],
+            ep_square: NO_SQ,
+            rule_50: 0,
+            full_moves: 1,
+            chess960: false,
+        }
+    }
+
+    /// Creates a `BoardBuilder` holding the current position of a `Board`.
+    ///
+    /// The move history of the `Board` is not kept.
+    pub fn from_board(board: &Board) -> Self {
+        let mut builder = BoardBuilder::new();
+        for sq in 0..SQ_CNT as u8 {
+            builder.pieces[sq as usize] = board.piece_at_sq(SQ(sq));
+        }
+        let castling = Castling::from_bits_truncate(board.castling_bits());
+        for player in ALL_PLAYERS.iter() {
+            for side in [CastleType::KingSide, CastleType::QueenSide].iter() {
+                if castling.castle_rights(*player, *side) {
+                    let rook = board.castling_squares().rook_start(*player, *side);
+                    builder.castling[*player as usize][*side as usize] =
+                        Some(CastlingRook::File(rook.file()));
+                }
+            }
+        }
+        builder.turn = board.turn();
+        builder.ep_square = board.ep_square();
+        builder.rule_50 = board.rule_50();
+        builder.full_moves = board.moves_played() / 2 + 1;
+        builder.chess960 = board.is_chess960();
+        builder
+    }
+
+    /// Returns the piece placed at a square, if any.
+    pub fn piece_at(&self, sq: SQ) -> Piece {
+        self.pieces[sq.0 as usize]
+    }
+
+    /// Places a piece at a square, replacing any piece already there.
+    pub fn place(mut self, sq: SQ, piece: Piece) -> Self {
+        self.pieces[sq.0 as usize] = piece;
+        self
+    }
+
+    /// Removes the piece at a square, if any.
+    pub fn remove(mut self, sq: SQ) -> Self {
+        self.pieces[sq.0 as usize] = Piece::None;
+        self
+    }
+
+    /// Sets the player to move.
+    pub fn turn(mut self, player: Player) -> Self {
+        self.turn = player;
+        self
+    }
+
+    /// Gives a player the right to castle on a side, using the outermost rook on that side of
+    /// the king.
+    pub fn castle(mut self, player: Player, side: CastleType) -> Self {
+        self.castling[player as usize][side as usize] = Some(CastlingRook::Outermost);
+        self
+    }
+
+    /// Gives a player the right to castle on a side, using the rook on the given file of their
+    /// back rank. Needed for Chess960 positions with two rooks on the same side of the king.
+    pub fn castle_with_rook(mut self, player: Player, side: CastleType, file: File) -> Self {
+        self.castling[player as usize][side as usize] = Some(CastlingRook::File(file));
+        self
+    }
+
+    /// Removes a player's right to castle on a side.
+    pub fn remove_castle(mut self, player: Player, side: CastleType) -> Self {
+        self.castling[player as usize][side as usize] = None;
+        self
+    }
+
+    /// Sets the en-passant square, the square behind a pawn that has just been double
+    /// pushed. `NO_SQ` removes the en-passant square.
+    pub fn ep_square(mut self, sq: SQ) -> Self {
+        self.ep_square = sq;
+        self
+    }
+
+    /// Sets the number of half-moves since a pawn move or capture.
+    pub fn rule_50(mut self, half_moves: i16) -> Self {
+        self.rule_50 = half_moves;
+        self
+    }
+
+    /// Sets the full move number, which starts at 1 and is increased after each Black move.
+    pub fn full_moves(mut self, moves: u16) -> Self {
+        self.full_moves = moves;
+        self
+    }
+
+    /// Sets whether the `Board` uses Chess960 castling. Positions where the kings or castling
+    /// rooks aren't on their standard squares always use Chess960 castling.
+    pub fn chess960(mut self, chess960: bool) -> Self {
+        self.chess960 = chess960;
+        self
+    }
+
+    /// Builds the `Board`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BoardError` if the position is illegal, such as when a player doesn't have
+    /// exactly one king, the side not to move is in check, a pawn is on a back rank, the
+    /// en-passant square doesn't follow a double pawn push, a castling right has no king or
+    /// rook to castle with, or the full move number is too large to count the moves played.
+    pub fn build(&self) -> Result<Board, BoardError> {
+        let mut board = Board::blank();
+        for (sq, piece) in self.pieces.iter().enumerate() {
+            if *piece != Piece::None {
+                board.put_piece_c(*piece, SQ(sq as u8));
+            }
+        }
+        board.turn = self.turn;
+        board.check_king()?;
+        board.check_piece_counts()?;
+
+        let (castling, squares) = self.castling_squares(&board)?;
+        board.castling_squares = squares;
+        board.chess960 = self.chess960 || !squares.is_standard();
+
+        if self.ep_square != NO_SQ && !self.ep_square.is_okay() {
+            return Err(BoardError::BadEPSquare { sq: self.ep_square });
+        }
+        board.half_moves = (self.full_moves.max(1) - 1)
+            .checked_mul(2)
+            .and_then(|moves| moves.checked_add(self.turn as u16))
+            .ok_or(BoardError::FullMovesOutOfRange {
+                full_moves: self.full_moves,
+            })?;
+
+        let mut state = BoardState::blank();
+        state.castling = castling;
+        state.rule_50 = self.rule_50;
+        state.ep_square = self.ep_square;
+        state.set(&board);
+        board.state = StateStack::new(state);
+
+        board.is_okay()?;
+        Ok(board)
+    }
+
+    // Finds the king and rook for each castling right.
+    fn castling_squares(&self, board: &Board) -> Result<(Castling, CastlingSquares), BoardError> {
+        let mut castling = Castling::empty();
+        let mut squares = CastlingSquares::standard();
+
+        for player in ALL_PLAYERS.iter() {
+            let player = *player;
+            let back_rank = player.relative_rank(Rank::R1);
+            let ksq = board.king_sq(player);
+            let rook = Piece::make_lossy(player, PieceType::R);
+
+            for side in [CastleType::KingSide, CastleType::QueenSide].iter() {
+                let side = *side;
+                let castling_rook = match self.castling[player as usize][side as usize] {
+                    Some(castling_rook) => castling_rook,
+                    None => continue,
+                };
+                if ksq.rank() != back_rank {
+                    return Err(BoardError::CastlingWithoutKing { player, side });
+                }
+
+                let on_side = |sq: &SQ| match side {
+                    CastleType::KingSide => *sq > ksq,
+                    CastleType::QueenSide => *sq < ksq,
+                };
+                let mut rooks = ALL_FILES
+                    .iter()
+                    .map(|file| SQ::make(*file, back_rank))
+                    .filter(|sq| board.piece_at_sq(*sq) == rook && on_side(sq));
+                let rook_sq = match castling_rook {
+                    CastlingRook::Outermost => match side {
+                        CastleType::KingSide => rooks.next_back(),
+                        CastleType::QueenSide => rooks.next(),
+                    },
+                    CastlingRook::File(file) => rooks.find(|sq| sq.file() == file),
+                };
+
+                match rook_sq {
+                    Some(rook_sq) => squares.set(player, side, ksq, rook_sq),
+                    None => return Err(BoardError::CastlingWithoutRook { player, side }),
+                }
+                castling |= Castling::single_right(player, side);
+            }
+        }
+        Ok((castling, squares))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kings() -> BoardBuilder {
+        BoardBuilder::new()
+            .place(SQ::E1, Piece::WhiteKing)
+            .place(SQ::E8, Piece::BlackKing)
+    }
+
+    #[test]
+    fn build_matches_fen() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+            "1r2k1r1/8/8/8/8/8/8/1R3KR1 w GBgb - 0 1",
+        ];
+        for fen in fens.iter() {
+            let board = Board::from_fen(fen).unwrap();
+            let built = BoardBuilder::from_board(&board).build().unwrap();
+            assert_eq!(built, board, "{}", fen);
+            assert_eq!(built.fen(), board.fen());
+            assert_eq!(built.zobrist(), board.zobrist());
+            assert_eq!(built.psq(), board.psq());
+            assert_eq!(built.is_chess960(), board.is_chess960());
+        }
+    }
+
+    #[test]
+    fn build_castling() {
+        let board = kings()
+            .place(SQ::A1, Piece::WhiteRook)
+            .place(SQ::B1, Piece::WhiteRook)
+            .place(SQ::H1, Piece::WhiteRook)
+            .castle(Player::White, CastleType::KingSide)
+            .castle_with_rook(Player::White, CastleType::QueenSide, File::B)
+            .build()
+            .unwrap();
+        assert_eq!(board.shredder_fen(), "4k3/8/8/8/8/8/8/RR2K2R w HB - 0 1");
+        assert!(board.is_chess960());
+
+        let err = kings()
+            .castle(Player::White, CastleType::QueenSide)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            BoardError::CastlingWithoutRook {
+                player: Player::White,
+                side: CastleType::QueenSide,
+            }
+        );
+
+        let err = kings()
+            .remove(SQ::E8)
+            .place(SQ::E7, Piece::BlackKing)
+            .place(SQ::H8, Piece::BlackRook)
+            .castle(Player::Black, CastleType::KingSide)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            BoardError::CastlingWithoutKing {
+                player: Player::Black,
+                side: CastleType::KingSide,
+            }
+        );
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            BoardBuilder::new()
+                .place(SQ::E1, Piece::WhiteKing)
+                .build()
+                .unwrap_err(),
+            BoardError::IncorrectKingNum {
+                player: Player::Black,
+                num: 0,
+            }
+        );
+        assert_eq!(
+            kings().place(SQ::D1, Piece::WhiteKing).build().unwrap_err(),
+            BoardError::IncorrectKingNum {
+                player: Player::White,
+                num: 2,
+            }
+        );
+        assert_eq!(
+            kings().place(SQ::C8, Piece::WhitePawn).build().unwrap_err(),
+            BoardError::PawnOnBackRank { sq: SQ::C8 }
+        );
+        assert_eq!(
+            kings()
+                .place(SQ::E4, Piece::WhiteRook)
+                .turn(Player::White)
+                .build()
+                .unwrap_err(),
+            BoardError::OpponentInCheck {
+                player: Player::Black,
+            }
+        );
+        assert!(kings()
+            .place(SQ::E4, Piece::WhiteRook)
+            .turn(Player::Black)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn build_ep_square() {
+        let builder = kings()
+            .place(SQ::D5, Piece::BlackPawn)
+            .place(SQ::E5, Piece::WhitePawn);
+        let board = builder.clone().ep_square(SQ::D6).build().unwrap();
+        assert_eq!(board.ep_square(), SQ::D6);
+        assert!(board.generate_moves().iter().any(|m| m.is_en_passant()));
+
+        for sq in [SQ::E6, SQ::D3, SQ::C6].iter() {
+            assert_eq!(
+                builder.clone().ep_square(*sq).build().unwrap_err(),
+                BoardError::BadEPSquare { sq: *sq }
+            );
+        }
+        assert_eq!(
+            builder
+                .clone()
+                .place(SQ::D7, Piece::BlackBishop)
+                .ep_square(SQ::D6)
+                .build()
+                .unwrap_err(),
+            BoardError::BadEPSquare { sq: SQ::D6 }
+        );
+    }
+
+    #[test]
+    fn build_full_moves() {
+        let board = kings().full_moves(40).build().unwrap();
+        assert_eq!(board.moves_played(), 78);
+        let board = kings()
+            .turn(Player::Black)
+            .full_moves(32_768)
+            .build()
+            .unwrap();
+        assert_eq!(board.moves_played(), u16::MAX);
+
+        for moves in [32_769, u16::MAX].iter() {
+            assert_eq!(
+                kings().full_moves(*moves).build().unwrap_err(),
+                BoardError::FullMovesOutOfRange { full_moves: *moves }
+            );
+        }
+    }
+}
@@ -10,8 +10,10 @@
 
 use core::masks::*;
 use core::*;
+use helper::prelude::{between_bb, init_statics};
 use std::fmt;
 
+use core::bitboard::BitBoard;
 use core::sq::SQ;
 
 const ALL_CASTLING: u8 = 0b0000_1111;
@@ -114,12 +116,28 @@ impl Castling {
 
     #[inline]
     pub fn update_castling(&mut self, to: SQ, from: SQ) -> u8 {
-        let mask_change: u8 = to.castle_rights_mask() | from.castle_rights_mask();
+        self.update_castling_mask(to.castle_rights_mask() | from.castle_rights_mask())
+    }
+
+    /// Removes the castling rights given by a mask, returning the rights that were removed.
+    #[inline]
+    pub fn update_castling_mask(&mut self, mask_change: u8) -> u8 {
         let to_return: u8 = self.bits & mask_change;
         self.bits &= !mask_change;
         to_return
     }
 
+    /// Returns the `Castling` containing only the right of a player to castle on a given side.
+    #[inline]
+    pub fn single_right(player: Player, side: CastleType) -> Castling {
+        match (player, side) {
+            (Player::White, CastleType::KingSide) => Castling::WHITE_K,
+            (Player::White, CastleType::QueenSide) => Castling::WHITE_Q,
+            (Player::Black, CastleType::KingSide) => Castling::BLACK_K,
+            (Player::Black, CastleType::QueenSide) => Castling::BLACK_Q,
+        }
+    }
+
     /// Adds the Right to castle based on an `char`.
     ///
     /// ```md
@@ -180,6 +198,146 @@ impl fmt::Display for Castling {
     }
 }
 
+/// Serializes as the bits of the castling rights.
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Castling {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Castling {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Castling, D::Error> {
+        let bits = u8::deserialize(deserializer)?;
+        Castling::from_bits(bits)
+            .ok_or_else(|| ::serde::de::Error::custom(format!("invalid castling bits: {}", bits)))
+    }
+}
+
+/// The starting squares of the kings and rooks taking part in each castling, alongside the
+/// squares that must be empty for each castling to happen.
+///
+/// In standard chess, the kings start on the e-file and the rooks in the corners. In Chess960
+/// (Fischer Random Chess) the king starts anywhere between the two rooks, so the `Board` keeps
+/// a `CastlingSquares` describing the current game. No matter the starting squares, castling
+/// king-side always ends with the king on the g-file and the rook on the f-file, and castling
+/// queen-side with the king on the c-file and the rook on the d-file.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct CastlingSquares {
+    king_start: [SQ; PLAYER_CNT],
+    rook_start: [[SQ; CASTLING_SIDES]; PLAYER_CNT],
+    paths: [[BitBoard; CASTLING_SIDES]; PLAYER_CNT],
+    rights_masks: [u8; SQ_CNT],
+}
+
+impl CastlingSquares {
+    /// Returns the castling squares of standard chess.
+    pub fn standard() -> CastlingSquares {
+        let mut squares = CastlingSquares {
+            king_start: [SQ(WHITE_KING_START), SQ(BLACK_KING_START)],
+            rook_start: [
+                [SQ(ROOK_WHITE_KSIDE_START), SQ(ROOK_WHITE_QSIDE_START)],
+                [SQ(ROOK_BLACK_KSIDE_START), SQ(ROOK_BLACK_QSIDE_START)],
+            ],
+            paths: [[BitBoard(0); CASTLING_SIDES]; PLAYER_CNT],
+            rights_masks: [0; SQ_CNT],
+        };
+        for player in [Player::White, Player::Black].iter() {
+            for side in [CastleType::KingSide, CastleType::QueenSide].iter() {
+                let rook = SQ(CASTLING_ROOK_START[*player as usize][*side as usize]);
+                squares.set(*player, *side, squares.king_start(*player), rook);
+            }
+        }
+        squares
+    }
+
+    /// Sets the starting squares of the king and rook for a player's castling on a given side.
+    pub fn set(&mut self, player: Player, side: CastleType, king: SQ, rook: SQ) {
+        init_statics();
+        let (king_to, rook_to) = CastlingSquares::destinations(player, side);
+
+        self.king_start[player as usize] = king;
+        self.rook_start[player as usize][side as usize] = rook;
+        self.paths[player as usize][side as usize] = (between_bb(rook, rook_to)
+            | between_bb(king, king_to)
+            | rook_to.to_bb()
+            | king_to.to_bb())
+            & !(king.to_bb() | rook.to_bb());
+
+        // Recompute the masks of the player, as the king may have moved
+        let k_right: u8 = Castling::single_right(player, CastleType::KingSide).bits;
+        let q_right: u8 = Castling::single_right(player, CastleType::QueenSide).bits;
+        for mask in self.rights_masks.iter_mut() {
+            *mask &= !(k_right | q_right);
+        }
+        let rooks = self.rook_start[player as usize];
+        self.rights_masks[king.0 as usize] |= k_right | q_right;
+        self.rights_masks[rooks[CastleType::KingSide as usize].0 as usize] |= k_right;
+        self.rights_masks[rooks[CastleType::QueenSide as usize].0 as usize] |= q_right;
+    }
+
+    /// Returns the squares a king and rook end up on after castling.
+    #[inline]
+    pub fn destinations(player: Player, side: CastleType) -> (SQ, SQ) {
+        match side {
+            CastleType::KingSide => (
+                player.relative_square(SQ::G1),
+                player.relative_square(SQ::F1),
+            ),
+            CastleType::QueenSide => (
+                player.relative_square(SQ::C1),
+                player.relative_square(SQ::D1),
+            ),
+        }
+    }
+
+    /// Returns the square the king of a player starts on.
+    #[inline(always)]
+    pub fn king_start(&self, player: Player) -> SQ {
+        self.king_start[player as usize]
+    }
+
+    /// Returns the square the rook castling on a given side starts on.
+    #[inline(always)]
+    pub fn rook_start(&self, player: Player, side: CastleType) -> SQ {
+        self.rook_start[player as usize][side as usize]
+    }
+
+    /// Returns the squares that must be empty, ignoring the castling king and rook, for the
+    /// castle to be possible.
+    #[inline(always)]
+    pub fn path(&self, player: Player, side: CastleType) -> BitBoard {
+        self.paths[player as usize][side as usize]
+    }
+
+    /// Returns the castling rights lost when a piece moves to or from the given square.
+    #[inline(always)]
+    pub fn rights_mask(&self, sq: SQ) -> u8 {
+        self.rights_masks[sq.0 as usize]
+    }
+
+    /// Returns if these are the castling squares of standard chess.
+    pub fn is_standard(&self) -> bool {
+        *self == CastlingSquares::standard()
+    }
+}
+
+impl Default for CastlingSquares {
+    fn default() -> Self {
+        CastlingSquares::standard()
+    }
+}
+
+impl fmt::Debug for CastlingSquares {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CastlingSquares")
+            .field("king_start", &self.king_start)
+            .field("rook_start", &self.rook_start)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,4 +347,38 @@ mod tests {
         let c_const = Castling::all_castling();
         assert_eq!(c, c_const);
     }
+
+    #[test]
+    fn standard_squares() {
+        let squares = CastlingSquares::standard();
+        for player in [Player::White, Player::Black].iter() {
+            for side in [CastleType::KingSide, CastleType::QueenSide].iter() {
+                let path = CASTLING_PATH[*player as usize][*side as usize];
+                assert_eq!(squares.path(*player, *side), BitBoard(path));
+            }
+        }
+        for sq in 0..SQ_CNT as u8 {
+            assert_eq!(squares.rights_mask(SQ(sq)), SQ(sq).castle_rights_mask());
+        }
+    }
+
+    #[test]
+    fn chess960_squares() {
+        // King on b1, queen-side rook on a1 and king-side rook on c1
+        let mut squares = CastlingSquares::standard();
+        squares.set(Player::White, CastleType::QueenSide, SQ::B1, SQ::A1);
+        squares.set(Player::White, CastleType::KingSide, SQ::B1, SQ::C1);
+        assert_eq!(
+            squares.path(Player::White, CastleType::QueenSide),
+            SQ::C1.to_bb() | SQ::D1.to_bb()
+        );
+        assert_eq!(
+            squares.path(Player::White, CastleType::KingSide),
+            SQ::D1.to_bb() | SQ::E1.to_bb() | SQ::F1.to_bb() | SQ::G1.to_bb()
+        );
+        assert_eq!(squares.rights_mask(SQ::E1), 0);
+        assert_eq!(squares.rights_mask(SQ::H1), 0);
+        assert_eq!(squares.rights_mask(SQ::C1), C_WHITE_K_MASK);
+        assert_eq!(squares.rights_mask(SQ::B1), C_WHITE_K_MASK | C_WHITE_Q_MASK);
+    }
 }
@@ -0,0 +1,625 @@
+//! Module for reading and writing positions in Extended Position Description (EPD).
+//!
+//! An EPD record is made of the first four fields of a FEN string (piece placement, side to
+//! move, castling rights and en-passant square), followed by any number of operations. Each
+//! operation is an opcode and its operands, ended with a semicolon, such as `bm Nf3;` or
+//! `id "WAC.001";`. EPD is the format used by most test suites, e.g. WAC, STS and Arasan.
+//!
+//! Parsing an [`EPD`] resolves the SAN operands of the `bm`, `am` and `pv` opcodes against its
+//! [`Board`], and reads the other recognised opcodes into typed [`Operation`]s. Unrecognised
+//! opcodes are kept as they were written. Writing an [`EPD`] through its `Display`
+//! implementation produces the record again, with moves in normalized SAN. EPD has no way of
+//! escaping a double quote inside a string operand, so as in PGN, quotes and backslashes are
+//! escaped with a backslash.
+//!
+//! See the [EPD specification](https://www.chessclub.com/user/help/PGN-spec) (Section 16.2)
+//! for more information.
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::board::epd::EPD;
+//!
+//! let record = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";";
+//! let epd = EPD::parse(record).unwrap();
+//!
+//! assert_eq!(epd.id(), Some("WAC.001"));
+//! let best = epd.best_moves()[0];
+//! assert_eq!(epd.board().move_to_san(best), "Qg6");
+//! assert_eq!(epd.to_string(), record);
+//! ```
+//!
+//! [`EPD`]: struct.EPD.html
+//! [`Operation`]: enum.Operation.html
+//! [`Board`]: ../struct.Board.html
+
+use std::fmt::{self, Display, Formatter};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::san::SanError;
+use super::Board;
+use core::piece_move::BitMove;
+
+/// A single operation of an EPD record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// `bm`: The best moves of the position.
+    BestMoves(Vec<BitMove>),
+    /// `am`: Moves that should be avoided in the position.
+    AvoidMoves(Vec<BitMove>),
+    /// `id`: An identifier for the position.
+    Id(String),
+    /// `c0` to `c9`: A comment, and its number.
+    Comment(u8, String),
+    /// `acd`: The depth the position was analyzed to, in plies.
+    AnalysisDepth(u32),
+    /// `ce`: The evaluation of the position in centipawns, from the side to move's point of view.
+    CentipawnEval(i32),
+    /// `pv`: The principal variation, starting from the position.
+    PrincipalVariation(Vec<BitMove>),
+    /// `hmvc`: The number of half-moves since the last capture or pawn move.
+    HalfMoveClock(u16),
+    /// `fmvn`: The full move number.
+    FullMoveNumber(u16),
+    /// Any other opcode, with its operands.
+    Other {
+        opcode: String,
+        operands: Vec<String>,
+    },
+}
+
+impl Operation {
+    /// Returns the opcode of the operation.
+    pub fn opcode(&self) -> String {
+        match *self {
+            Operation::BestMoves(_) => "bm".to_owned(),
+            Operation::AvoidMoves(_) => "am".to_owned(),
+            Operation::Id(_) => "id".to_owned(),
+            Operation::Comment(n, _) => format!("c{}", n),
+            Operation::AnalysisDepth(_) => "acd".to_owned(),
+            Operation::CentipawnEval(_) => "ce".to_owned(),
+            Operation::PrincipalVariation(_) => "pv".to_owned(),
+            Operation::HalfMoveClock(_) => "hmvc".to_owned(),
+            Operation::FullMoveNumber(_) => "fmvn".to_owned(),
+            Operation::Other { ref opcode, .. } => opcode.clone(),
+        }
+    }
+}
+
+/// Errors encountered while reading an EPD record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EPDError {
+    /// The record has fewer than the four position fields.
+    MissingFields { fields: usize },
+    /// The position fields don't describe a valid position.
+    InvalidFen { fen: String },
+    /// An opcode is not made of letters, digits and underscores, starting with a letter.
+    InvalidOpcode { opcode: String },
+    /// A semicolon was found without an opcode before it.
+    MissingOpcode,
+    /// The last operation of the record isn't ended with a semicolon.
+    MissingSemicolon { opcode: String },
+    /// A string operand isn't closed by a double quote.
+    UnterminatedString { opcode: String },
+    /// An operand can't be read for its opcode.
+    InvalidOperand { opcode: String, operand: String },
+    /// A move operand doesn't match exactly one legal move.
+    IllegalMove {
+        opcode: String,
+        san: String,
+        error: SanError,
+    },
+}
+
+impl Display for EPDError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            EPDError::MissingFields { fields } => {
+                write!(f, "expected four position fields, found {}", fields)
+            }
+            EPDError::InvalidFen { ref fen } => write!(f, "invalid position: {}", fen),
+            EPDError::InvalidOpcode { ref opcode } => write!(f, "invalid opcode: {}", opcode),
+            EPDError::MissingOpcode => write!(f, "operation has no opcode"),
+            EPDError::MissingSemicolon { ref opcode } => {
+                write!(f, "operation {} is not ended by a semicolon", opcode)
+            }
+            EPDError::UnterminatedString { ref opcode } => {
+                write!(f, "unterminated string operand for {}", opcode)
+            }
+            EPDError::InvalidOperand {
+                ref opcode,
+                ref operand,
+            } => write!(f, "invalid operand for {}: {}", opcode, operand),
+            EPDError::IllegalMove {
+                ref opcode,
+                ref error,
+                ..
+            } => write!(f, "{}: {}", opcode, error),
+        }
+    }
+}
+
+/// A position in Extended Position Description, along with its operations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EPD {
+    board: Board,
+    operations: Vec<Operation>,
+}
+
+impl EPD {
+    /// Creates an EPD record of a position, without any operations.
+    pub fn new(board: Board) -> EPD {
+        EPD {
+            board,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Parses a single EPD record.
+    ///
+    /// The `hmvc` and `fmvn` operations, if present, set the move clocks of the `Board`.
+    pub fn parse(input: &str) -> Result<EPD, EPDError> {
+        let mut rest = input;
+        let mut fields = Vec::with_capacity(4);
+        while fields.len() < 4 {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                return Err(EPDError::MissingFields {
+                    fields: fields.len(),
+                });
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+
+        let raw = raw_operations(rest)?;
+        let mut half_moves: i16 = 0;
+        let mut full_moves: u16 = 1;
+        for (opcode, operands) in raw.iter() {
+            match opcode.as_str() {
+                "hmvc" => half_moves = number_operand(opcode, operands)?,
+                "fmvn" => full_moves = number_operand(opcode, operands)?,
+                _ => {}
+            }
+        }
+
+        let position = fields.join(" ");
+        let fen = format!("{} {} {}", position, half_moves, full_moves.max(1));
+        let board = Board::from_fen(&fen).map_err(|_| EPDError::InvalidFen { fen: position })?;
+
+        let mut operations = Vec::with_capacity(raw.len());
+        for (opcode, operands) in raw {
+            operations.push(operation(&board, opcode, operands)?);
+        }
+        Ok(EPD { board, operations })
+    }
+
+    /// Returns the position of the record.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the operations of the record, in the order they were written.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Returns the operation with the given opcode, if any.
+    pub fn operation(&self, opcode: &str) -> Option<&Operation> {
+        self.operations.iter().find(|op| op.opcode() == opcode)
+    }
+
+    /// Adds an operation to the record, replacing any operation with the same opcode.
+    ///
+    /// The moves of an operation are expected to be legal in the record's position.
+    pub fn set_operation(&mut self, operation: Operation) {
+        let opcode = operation.opcode();
+        match self.operations.iter().position(|op| op.opcode() == opcode) {
+            Some(i) => self.operations[i] = operation,
+            None => self.operations.push(operation),
+        }
+    }
+
+    /// Removes the operation with the given opcode, returning it if there was one.
+    pub fn remove_operation(&mut self, opcode: &str) -> Option<Operation> {
+        let i = self
+            .operations
+            .iter()
+            .position(|op| op.opcode() == opcode)?;
+        Some(self.operations.remove(i))
+    }
+
+    /// Returns the moves of the `bm` operation, or an empty slice if there is none.
+    pub fn best_moves(&self) -> &[BitMove] {
+        match self.operation("bm") {
+            Some(Operation::BestMoves(moves)) => moves,
+            _ => &[],
+        }
+    }
+
+    /// Returns the moves of the `am` operation, or an empty slice if there is none.
+    pub fn avoid_moves(&self) -> &[BitMove] {
+        match self.operation("am") {
+            Some(Operation::AvoidMoves(moves)) => moves,
+            _ => &[],
+        }
+    }
+
+    /// Returns the `id` of the record, if any.
+    pub fn id(&self) -> Option<&str> {
+        match self.operation("id") {
+            Some(Operation::Id(id)) => Some(id),
+            _ => None,
+        }
+    }
+
+    fn write_operation(&self, f: &mut Formatter, operation: &Operation) -> fmt::Result {
+        write!(f, "{}", operation.opcode())?;
+        match *operation {
+            Operation::BestMoves(ref moves) | Operation::AvoidMoves(ref moves) => {
+                for mov in moves.iter() {
+                    write!(f, " {}", self.board.move_to_san(*mov))?;
+                }
+            }
+            Operation::PrincipalVariation(ref moves) => {
+                let mut board = self.board.shallow_clone();
+                for mov in moves.iter() {
+                    write!(f, " {}", board.move_to_san(*mov))?;
+                    board.apply_move(*mov);
+                }
+            }
+            Operation::Id(ref s) | Operation::Comment(_, ref s) => write_string(f, s)?,
+            Operation::AnalysisDepth(n) => write!(f, " {}", n)?,
+            Operation::CentipawnEval(n) => write!(f, " {}", n)?,
+            Operation::HalfMoveClock(n) | Operation::FullMoveNumber(n) => write!(f, " {}", n)?,
+            Operation::Other { ref operands, .. } => {
+                for operand in operands.iter() {
+                    let plain = !operand.is_empty()
+                        && !operand.contains(|c: char| c.is_whitespace() || c == ';' || c == '"');
+                    if plain {
+                        write!(f, " {}", operand)?;
+                    } else {
+                        write_string(f, operand)?;
+                    }
+                }
+            }
+        }
+        write!(f, ";")
+    }
+}
+
+impl Display for EPD {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let fen = self.board.fen();
+        let fields: Vec<&str> = fen.split_whitespace().take(4).collect();
+        write!(f, "{}", fields.join(" "))?;
+        for operation in self.operations.iter() {
+            write!(f, " ")?;
+            self.write_operation(f, operation)?;
+        }
+        Ok(())
+    }
+}
+
+// Writes a string operand in double quotes. EPD has no escapes of its own, so quotes and
+// backslashes inside the string are escaped with a backslash, as in PGN.
+fn write_string(f: &mut Formatter, s: &str) -> fmt::Result {
+    write!(f, " \"")?;
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            write!(f, "\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    write!(f, "\"")
+}
+
+type RawOperation = (String, Vec<String>);
+
+// Splits the operations of a record into opcodes and their operands.
+fn raw_operations(input: &str) -> Result<Vec<RawOperation>, EPDError> {
+    let mut operations = Vec::new();
+    let mut current: Option<RawOperation> = None;
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while let Some(c) = chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            chars.next();
+        }
+        match chars.peek().cloned() {
+            None => break,
+            Some(';') => {
+                chars.next();
+                operations.push(current.take().ok_or(EPDError::MissingOpcode)?);
+            }
+            Some('"') => {
+                chars.next();
+                let (opcode, operands) = current.as_mut().ok_or(EPDError::MissingOpcode)?;
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // A backslash only escapes a quote or another backslash
+                        Some('\\') => match chars.peek() {
+                            Some(&c) if c == '"' || c == '\\' => {
+                                s.push(c);
+                                chars.next();
+                            }
+                            _ => s.push('\\'),
+                        },
+                        Some(c) => s.push(c),
+                        None => {
+                            return Err(EPDError::UnterminatedString {
+                                opcode: opcode.clone(),
+                            })
+                        }
+                    }
+                }
+                operands.push(s);
+            }
+            Some(_) => {
+                let word = read_word(&mut chars);
+                match current {
+                    Some((_, ref mut operands)) => operands.push(word),
+                    None => {
+                        let valid = word.starts_with(|c: char| c.is_ascii_alphabetic())
+                            && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                        if !valid {
+                            return Err(EPDError::InvalidOpcode { opcode: word });
+                        }
+                        current = Some((word, Vec::new()));
+                    }
+                }
+            }
+        }
+    }
+
+    match current {
+        Some((opcode, _)) => Err(EPDError::MissingSemicolon { opcode }),
+        None => Ok(operations),
+    }
+}
+
+fn read_word(chars: &mut Peekable<Chars>) -> String {
+    let mut word = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == ';' {
+            break;
+        }
+        word.push(c);
+        chars.next();
+    }
+    word
+}
+
+// Reads the single number operand of an operation.
+fn number_operand<T: ::std::str::FromStr>(
+    opcode: &str,
+    operands: &[String],
+) -> Result<T, EPDError> {
+    let error = || EPDError::InvalidOperand {
+        opcode: opcode.to_owned(),
+        operand: operands.join(" "),
+    };
+    if operands.len() != 1 {
+        return Err(error());
+    }
+    operands[0].parse().map_err(|_| error())
+}
+
+// Resolves a move operand against a board.
+fn san_operand(board: &Board, opcode: &str, san: &str) -> Result<BitMove, EPDError> {
+    board.parse_san(san).map_err(|error| EPDError::IllegalMove {
+        opcode: opcode.to_owned(),
+        san: san.to_owned(),
+        error,
+    })
+}
+
+// Reads a raw operation into its typed form.
+fn operation(board: &Board, opcode: String, operands: Vec<String>) -> Result<Operation, EPDError> {
+    let comment = {
+        let mut chars = opcode.chars();
+        match (
+            chars.next(),
+            chars.next().and_then(|c| c.to_digit(10)),
+            chars.next(),
+        ) {
+            (Some('c'), Some(n), None) => Some(n as u8),
+            _ => None,
+        }
+    };
+    if let Some(n) = comment {
+        return Ok(Operation::Comment(n, operands.join(" ")));
+    }
+
+    Ok(match opcode.as_str() {
+        "bm" | "am" => {
+            let mut moves = Vec::with_capacity(operands.len());
+            for san in operands.iter() {
+                moves.push(san_operand(board, &opcode, san)?);
+            }
+            if opcode == "bm" {
+                Operation::BestMoves(moves)
+            } else {
+                Operation::AvoidMoves(moves)
+            }
+        }
+        "pv" => {
+            let mut board = board.shallow_clone();
+            let mut moves = Vec::with_capacity(operands.len());
+            for san in operands.iter() {
+                let mov = san_operand(&board, &opcode, san)?;
+                board.apply_move(mov);
+                moves.push(mov);
+            }
+            Operation::PrincipalVariation(moves)
+        }
+        "id" => Operation::Id(operands.join(" ")),
+        "acd" => Operation::AnalysisDepth(number_operand(&opcode, &operands)?),
+        "ce" => Operation::CentipawnEval(number_operand(&opcode, &operands)?),
+        "hmvc" => Operation::HalfMoveClock(number_operand(&opcode, &operands)?),
+        "fmvn" => Operation::FullMoveNumber(number_operand(&opcode, &operands)?),
+        _ => Operation::Other { opcode, operands },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_operations() {
+        let record = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - \
+                      bm Bb5 Bc4; am Qe2; id \"Ruy Lopez\"; c0 \"main line; popular\"; \
+                      acd 24; ce -15; pv Bb5 a6 Ba4; hmvc 2; fmvn 3; sm Bb5; noop;";
+        let epd = EPD::parse(record).unwrap();
+        let board = epd.board();
+
+        assert_eq!(board.rule_50(), 2);
+        assert_eq!(board.moves_played(), 4);
+        let sans: Vec<String> = epd
+            .best_moves()
+            .iter()
+            .map(|m| board.move_to_san(*m))
+            .collect();
+        assert_eq!(sans, vec!["Bb5", "Bc4"]);
+        assert_eq!(epd.avoid_moves().len(), 1);
+        assert_eq!(epd.id(), Some("Ruy Lopez"));
+        assert_eq!(
+            epd.operation("c0"),
+            Some(&Operation::Comment(0, "main line; popular".to_owned()))
+        );
+        assert_eq!(epd.operation("acd"), Some(&Operation::AnalysisDepth(24)));
+        assert_eq!(epd.operation("ce"), Some(&Operation::CentipawnEval(-15)));
+        match epd.operation("pv") {
+            Some(Operation::PrincipalVariation(moves)) => assert_eq!(moves.len(), 3),
+            op => panic!("{:?}", op),
+        }
+        assert_eq!(
+            epd.operation("sm"),
+            Some(&Operation::Other {
+                opcode: "sm".to_owned(),
+                operands: vec!["Bb5".to_owned()],
+            })
+        );
+        assert_eq!(
+            epd.operation("noop"),
+            Some(&Operation::Other {
+                opcode: "noop".to_owned(),
+                operands: vec![],
+            })
+        );
+
+        assert_eq!(epd.to_string(), record);
+        assert_eq!(EPD::parse(&epd.to_string()).unwrap(), epd);
+    }
+
+    #[test]
+    fn epd_edit() {
+        let mut epd = EPD::new(Board::start_pos());
+        assert_eq!(
+            epd.to_string(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"
+        );
+
+        let e4 = epd.board().parse_san("e4").unwrap();
+        epd.set_operation(Operation::Id("start".to_owned()));
+        epd.set_operation(Operation::BestMoves(vec![e4]));
+        epd.set_operation(Operation::Id("initial".to_owned()));
+        assert_eq!(
+            epd.to_string(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id \"initial\"; bm e4;"
+        );
+        assert_eq!(
+            epd.remove_operation("id"),
+            Some(Operation::Id("initial".to_owned()))
+        );
+        assert_eq!(epd.remove_operation("id"), None);
+        assert_eq!(epd.best_moves(), &[e4]);
+    }
+
+    #[test]
+    fn epd_escaped_strings() {
+        let mut epd = EPD::new(Board::start_pos());
+        epd.set_operation(Operation::Id("The \"Big\" One".to_owned()));
+        epd.set_operation(Operation::Comment(1, "C:\\ends with \\".to_owned()));
+        epd.set_operation(Operation::Other {
+            opcode: "x".to_owned(),
+            operands: vec![
+                "\"quoted\"".to_owned(),
+                "a\"b".to_owned(),
+                "plain".to_owned(),
+            ],
+        });
+        let record = epd.to_string();
+        assert_eq!(
+            record,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id \"The \\\"Big\\\" One\"; \
+             c1 \"C:\\\\ends with \\\\\"; x \"\\\"quoted\\\"\" \"a\\\"b\" plain;"
+        );
+        assert_eq!(EPD::parse(&record).unwrap(), epd);
+
+        // Backslashes not escaping anything are read as they are
+        let epd = EPD::parse("8/8/8/8/8/8/8/K6k w - - c0 \"a\\b\";").unwrap();
+        assert_eq!(
+            epd.operation("c0"),
+            Some(&Operation::Comment(0, "a\\b".to_owned()))
+        );
+    }
+
+    #[test]
+    fn epd_errors() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        let parse = |ops: &str| EPD::parse(&format!("{} {}", start, ops)).unwrap_err();
+
+        assert_eq!(
+            EPD::parse("8/8/8/8/8/8/8/8 w").unwrap_err(),
+            EPDError::MissingFields { fields: 2 }
+        );
+        assert_eq!(
+            EPD::parse("8/8/8/8 w - -").unwrap_err(),
+            EPDError::InvalidFen {
+                fen: "8/8/8/8 w - -".to_owned(),
+            }
+        );
+        assert_eq!(
+            parse("bm e4"),
+            EPDError::MissingSemicolon {
+                opcode: "bm".to_owned()
+            }
+        );
+        assert_eq!(parse("; bm e4;"), EPDError::MissingOpcode);
+        assert_eq!(
+            parse("4x e4;"),
+            EPDError::InvalidOpcode {
+                opcode: "4x".to_owned()
+            }
+        );
+        assert_eq!(
+            parse("id \"open;"),
+            EPDError::UnterminatedString {
+                opcode: "id".to_owned()
+            }
+        );
+        assert_eq!(
+            parse("acd deep;"),
+            EPDError::InvalidOperand {
+                opcode: "acd".to_owned(),
+                operand: "deep".to_owned(),
+            }
+        );
+        match parse("pv e4 e4;") {
+            EPDError::IllegalMove { opcode, san, .. } => {
+                assert_eq!(opcode, "pv");
+                assert_eq!(san, "e4");
+            }
+            err => panic!("{:?}", err),
+        }
+    }
+}
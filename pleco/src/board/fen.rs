@@ -7,10 +7,31 @@
 //!
 //! See [this Wikipedia article](https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation)
 //! for more information.
+//!
+//! # Chess960
+//!
+//! Chess960 (Fischer Random Chess) positions can't always describe their castling rights with
+//! `KQkq`, as a player may have more than one rook on their back rank. Two extensions of FEN
+//! are supported for these positions:
+//!
+//! - Shredder-FEN, which gives the file of each castling rook, e.g. `HAha`.
+//! - X-FEN, which uses `KQkq` for the outermost rooks, and the file of the rook otherwise.
+//!
+//! Both are read by [`Board::from_fen`]. [`Board::fen`] writes X-FEN, which is the same as
+//! standard FEN for standard positions, and [`Board::shredder_fen`] writes Shredder-FEN.
+//!
+//! [`Board::from_fen`]: ../struct.Board.html#method.from_fen
+//! [`Board::fen`]: ../struct.Board.html#method.fen
+//! [`Board::shredder_fen`]: ../struct.Board.html#method.shredder_fen
 
+use super::super::core::masks::{FILE_DISPLAYS, PIECE_TYPE_CNT, PLAYER_CNT};
 use super::super::core::sq::NO_SQ;
+use super::castle_rights::{Castling, CastlingSquares};
+use super::variant::Variant;
 use super::{Board, FenBuildError};
-use {BitBoard, PieceType, Player, Rank, SQ};
+use core::{CastleType, ALL_FILES};
+use helper::zobrist::MAX_POCKET;
+use {BitBoard, Piece, PieceType, Player, Rank, SQ};
 
 /// The fen string for the start position.
 pub const OPENING_POS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
@@ -101,7 +122,12 @@ lazy_static! {
 /// [`Board`]: ../struct.Board.html
 /// [`Board::new_from_fen`]: ../struct.Board.html#method.new_from_fen
 pub fn is_valid_fen(board: Board) -> Result<Board, FenBuildError> {
-    let checks = board.checkers();
+    // Explosions can reveal any number of checks in Atomic
+    let checks = if board.variant() == Variant::Atomic {
+        BitBoard(0)
+    } else {
+        board.checkers()
+    };
     let num_checks = checks.count_bits();
     // Can't be more than 2 checking pieces at a time
     if num_checks > 2 {
@@ -126,25 +152,31 @@ pub fn is_valid_fen(board: Board) -> Result<Board, FenBuildError> {
         }
     }
 
-    let all_pawns: BitBoard =
+    // The White horde may have pawns on the first rank
+    let horde = board.variant() == Variant::Horde;
+    let mut all_pawns: BitBoard =
         board.piece_bb_both_players(PieceType::P) & (BitBoard::RANK_1 | BitBoard::RANK_8);
+    if horde {
+        all_pawns &= !(board.piece_bb(Player::White, PieceType::P) & BitBoard::RANK_1);
+    }
 
     // No pawns on Rank 1 or 8
     if all_pawns.is_not_empty() {
         return Err(FenBuildError::PawnOnLastRow);
     }
 
-    // Check for more pawns than possible
+    // Check for more pawns than possible. Crazyhouse pawns can be dropped back on the board.
+    let crazyhouse = board.variant() == Variant::Crazyhouse;
     let white_pawns = board.count_piece(Player::White, PieceType::P);
     let black_pawns = board.count_piece(Player::Black, PieceType::P);
-    if white_pawns > 8 {
+    if white_pawns > 8 && !horde && !crazyhouse {
         return Err(FenBuildError::TooManyPawns {
             player: Player::White,
             num: white_pawns,
         });
     }
 
-    if black_pawns > 8 {
+    if black_pawns > 8 && !crazyhouse {
         return Err(FenBuildError::TooManyPawns {
             player: Player::Black,
             num: black_pawns,
@@ -207,8 +239,318 @@ pub fn is_valid_fen(board: Board) -> Result<Board, FenBuildError> {
     Ok(board)
 }
 
+/// Parses the check counter of a Three-check FEN, returning the number of checks each player
+/// has given.
+///
+/// The counter is either the checks each player has left, like `3+3`, or the checks each
+/// player has given, like `+0+0`.
+pub(crate) fn parse_check_count(field: &str) -> Result<[u8; 2], FenBuildError> {
+    let error = || FenBuildError::UnreadableCheckCount {
+        checks: field.to_string(),
+    };
+    let (given, counts) = match field.strip_prefix('+') {
+        Some(counts) => (true, counts),
+        None => (false, field),
+    };
+
+    let mut checks = [0; 2];
+    let mut split = counts.split('+');
+    for player in checks.iter_mut() {
+        let count = split
+            .next()
+            .and_then(|c| c.parse::<u8>().ok())
+            .filter(|c| *c <= 3)
+            .ok_or_else(error)?;
+        *player = if given { count } else { 3 - count };
+    }
+    if split.next().is_some() {
+        return Err(error());
+    }
+    Ok(checks)
+}
+
+/// Parses the pocket of a Crazyhouse FEN, such as `QNpp`, returning the number of each piece
+/// type each player holds. Kings can't be held in a pocket.
+pub(crate) fn parse_pocket(
+    pocket: &str,
+) -> Result<[[u8; PIECE_TYPE_CNT]; PLAYER_CNT], FenBuildError> {
+    let error = || FenBuildError::UnrecognizedPocket {
+        pocket: pocket.to_string(),
+    };
+    let mut pockets = [[0; PIECE_TYPE_CNT]; PLAYER_CNT];
+    for ch in pocket.chars() {
+        let piece = match ch.to_ascii_lowercase() {
+            'p' => PieceType::P,
+            'n' => PieceType::N,
+            'b' => PieceType::B,
+            'r' => PieceType::R,
+            'q' => PieceType::Q,
+            _ => return Err(error()),
+        };
+        let player = if ch.is_lowercase() {
+            Player::Black
+        } else {
+            Player::White
+        };
+        let count = &mut pockets[player as usize][piece as usize];
+        if *count as usize >= MAX_POCKET {
+            return Err(error());
+        }
+        *count += 1;
+    }
+    Ok(pockets)
+}
+
+/// Removes the `~` markers of promoted pieces from the ranks of a Crazyhouse FEN, returning the
+/// ranks without them and the squares that were marked.
+pub(crate) fn strip_promoted(ranks: &[&str]) -> (Vec<String>, BitBoard) {
+    let mut promoted = BitBoard(0);
+    let stripped = ranks
+        .iter()
+        .enumerate()
+        .map(|(i, rank)| {
+            let mut file = 0;
+            let mut stripped = String::with_capacity(rank.len());
+            for ch in rank.chars() {
+                if ch == '~' {
+                    if (1..=8).contains(&file) {
+                        promoted |= SQ(((7 - i) * 8 + file - 1) as u8).to_bb();
+                    }
+                    continue;
+                }
+                file += ch.to_digit(10).unwrap_or(1) as usize;
+                stripped.push(ch);
+            }
+            stripped
+        })
+        .collect();
+    (stripped, promoted)
+}
+
+/// Writes the pockets of a Crazyhouse board, such as `[QNpp]`, with White's pieces first.
+pub(crate) fn pocket_string(board: &Board) -> String {
+    let mut s = String::from("[");
+    for player in [Player::White, Player::Black].iter() {
+        for piece in [
+            PieceType::Q,
+            PieceType::R,
+            PieceType::B,
+            PieceType::N,
+            PieceType::P,
+        ]
+        .iter()
+        {
+            for _ in 0..board.pocket(*player, *piece) {
+                s.push(match *player {
+                    Player::White => piece.char_upper(),
+                    Player::Black => piece.char_lower(),
+                });
+            }
+        }
+    }
+    s.push(']');
+    s
+}
+
+/// Returns the squares of the rooks on a player's back rank, from the a-file to the h-file.
+fn back_rank_rooks(board: &Board, player: Player) -> Vec<SQ> {
+    let rank = player.relative_rank(Rank::R1);
+    let rook = Piece::make_lossy(player, PieceType::R);
+    ALL_FILES
+        .iter()
+        .map(|file| SQ::make(*file, rank))
+        .filter(|sq| board.piece_at_sq(*sq) == rook)
+        .collect()
+}
+
+/// Parses the castling rights field of a FEN string for a board with its pieces already placed,
+/// returning the castling rights, the castling squares, and whether the position needs
+/// Chess960 castling.
+///
+/// Accepts standard, X-FEN and Shredder-FEN castling rights.
+pub(crate) fn parse_castling(
+    board: &Board,
+    field: &str,
+) -> Result<(Castling, CastlingSquares, bool), FenBuildError> {
+    let mut castling = Castling::empty();
+    let mut squares = CastlingSquares::standard();
+    let mut shredder = false;
+    let error = || FenBuildError::UnrecognizedCastling {
+        castling: field.to_string(),
+    };
+
+    if field == "-" {
+        return Ok((castling, squares, false));
+    }
+
+    for c in field.chars() {
+        let player = if c.is_ascii_uppercase() {
+            Player::White
+        } else {
+            Player::Black
+        };
+        let back_rank = player.relative_rank(Rank::R1);
+        let kings: BitBoard = board.piece_bb(player, PieceType::K);
+        let king_sq = if kings.is_empty() {
+            NO_SQ
+        } else {
+            kings.to_sq()
+        };
+        let king_on_back_rank = king_sq != NO_SQ && king_sq.rank() == back_rank;
+        let rooks = back_rank_rooks(board, player);
+
+        let (side, rook) = match c.to_ascii_lowercase() {
+            'k' => {
+                let outer = rooks
+                    .iter()
+                    .rev()
+                    .find(|r| king_on_back_rank && **r > king_sq);
+                (CastleType::KingSide, outer.cloned())
+            }
+            'q' => {
+                let outer = rooks.iter().find(|r| king_on_back_rank && **r < king_sq);
+                (CastleType::QueenSide, outer.cloned())
+            }
+            f @ 'a'..='h' => {
+                shredder = true;
+                let rook = SQ::make(ALL_FILES[(f as u8 - b'a') as usize], back_rank);
+                if !king_on_back_rank || !rooks.contains(&rook) {
+                    return Err(error());
+                }
+                let side = if rook > king_sq {
+                    CastleType::KingSide
+                } else {
+                    CastleType::QueenSide
+                };
+                (side, Some(rook))
+            }
+            _ => return Err(error()),
+        };
+
+        let right = Castling::single_right(player, side);
+        if castling.contains(right) {
+            return Err(error());
+        }
+        castling |= right;
+
+        // Rights without a matching rook are kept with the standard squares, and never
+        // lead to a legal castle
+        if let Some(rook) = rook {
+            squares.set(player, side, king_sq, rook);
+        }
+    }
+
+    let chess960 = shredder || !squares.is_standard();
+    Ok((castling, squares, chess960))
+}
+
+/// Returns the castling rights field of a board's FEN string. Writes Shredder-FEN if `shredder`
+/// is set, and X-FEN otherwise.
+pub(crate) fn castling_string(board: &Board, shredder: bool) -> String {
+    let castling: Castling = Castling::from_bits_truncate(board.castling_bits());
+    if castling.no_castling() {
+        return "-".to_owned();
+    }
+
+    let mut s = String::new();
+    for player in [Player::White, Player::Black].iter() {
+        let rooks = back_rank_rooks(board, *player);
+        for side in [CastleType::KingSide, CastleType::QueenSide].iter() {
+            if !castling.castle_rights(*player, *side) {
+                continue;
+            }
+            let rook = board.castling_squares().rook_start(*player, *side);
+            let outermost = match *side {
+                CastleType::KingSide => rooks.iter().all(|r| *r <= rook),
+                CastleType::QueenSide => rooks.iter().all(|r| *r >= rook),
+            };
+            let c = if !shredder && outermost {
+                match *side {
+                    CastleType::KingSide => 'k',
+                    CastleType::QueenSide => 'q',
+                }
+            } else {
+                FILE_DISPLAYS[rook.file() as usize]
+            };
+            s.push(match *player {
+                Player::White => c.to_ascii_uppercase(),
+                Player::Black => c,
+            });
+        }
+    }
+    s
+}
+
+/// Returns the FEN string of a Chess960 starting position from its Scharnagl number (SP-ID),
+/// or `None` if the number isn't between 0 and 959.
+///
+/// Position 518 is the standard starting position.
+///
+/// # Examples
+///
+/// ```
+/// use pleco::board::fen::chess960_fen;
+///
+/// assert_eq!(chess960_fen(518).unwrap(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+/// assert_eq!(chess960_fen(0).unwrap(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+/// assert!(chess960_fen(960).is_none());
+/// ```
+pub fn chess960_fen(sp_id: u16) -> Option<String> {
+    // Possible placements of the knights among the five squares left after the bishops and queen
+    const KNIGHTS: [(usize, usize); 10] = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ];
+
+    if sp_id > 959 {
+        return None;
+    }
+    let mut n = sp_id as usize;
+    let mut rank: [Option<char>; 8] = [None; 8];
+
+    // Light-squared bishop on b, d, f or h, then the dark-squared bishop on a, c, e or g
+    rank[(n % 4) * 2 + 1] = Some('b');
+    n /= 4;
+    rank[(n % 4) * 2] = Some('b');
+    n /= 4;
+
+    let empty = |rank: &[Option<char>; 8]| -> Vec<usize> {
+        (0..8).filter(|i| rank[*i].is_none()).collect()
+    };
+
+    let queen = empty(&rank)[n % 6];
+    rank[queen] = Some('q');
+    n /= 6;
+
+    let remaining = empty(&rank);
+    let (n1, n2) = KNIGHTS[n];
+    rank[remaining[n1]] = Some('n');
+    rank[remaining[n2]] = Some('n');
+
+    // The king always goes between the two rooks
+    for (i, file) in empty(&rank).into_iter().enumerate() {
+        rank[file] = Some(if i == 1 { 'k' } else { 'r' });
+    }
+
+    let black: String = rank.iter().map(|c| c.unwrap()).collect();
+    Some(format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+        black,
+        black.to_ascii_uppercase()
+    ))
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use Board;
 
     const EXTRA_PAWNS: &str = "rnbqkbnr/pppppppp/8/8/8/7P/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
@@ -217,4 +559,63 @@ mod tests {
     fn fen_extra_pawns() {
         assert!(Board::from_fen(EXTRA_PAWNS).is_err());
     }
+
+    #[test]
+    fn fen_castling_notations() {
+        let board =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+        assert!(board.is_chess960());
+        assert_eq!(board.castling_bits(), Castling::all().bits());
+        assert_eq!(board.fen(), OPENING_POS_FEN);
+        assert_eq!(
+            board.shredder_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+
+        let board = Board::start_pos();
+        assert!(!board.is_chess960());
+        assert_eq!(
+            board.shredder_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+
+        // Two rooks on the king-side, castling with the inner one
+        let fen = "4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        assert!(board.is_chess960());
+        assert_eq!(
+            board
+                .castling_squares()
+                .rook_start(Player::White, CastleType::KingSide),
+            SQ::G1
+        );
+        assert_eq!(board.fen(), fen);
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/1R2K1RR w KQ - 0 1").unwrap();
+        assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/1R2K1RR w KQ - 0 1");
+        assert_eq!(board.shredder_fen(), "4k3/8/8/8/8/8/8/1R2K1RR w HB - 0 1");
+
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/1R2K1RR w C - 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/1R2K1RR w X - 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/1R2K1RR w KK - 0 1").is_err());
+    }
+
+    #[test]
+    fn chess960_start_positions() {
+        let mut fens = Vec::new();
+        for sp_id in 0..960 {
+            let fen = chess960_fen(sp_id).unwrap();
+            let board = Board::start_pos_chess960(sp_id).unwrap();
+            assert!(board.is_chess960());
+            assert_eq!(board.fen(), fen);
+            fens.push(fen);
+        }
+        fens.sort();
+        fens.dedup();
+        assert_eq!(fens.len(), 960);
+
+        assert_eq!(
+            chess960_fen(959).unwrap(),
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1"
+        );
+    }
 }
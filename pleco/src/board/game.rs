@@ -0,0 +1,907 @@
+//! Module for recording a game as it is played, with navigation through its moves.
+//!
+//! A [`Game`] owns the position the game started from and a tree of the moves played since.
+//! Each move carries its SAN alongside optional comments, clock time and evaluation. The
+//! game can be stepped through with [`undo`], [`redo`] and [`goto_ply`], and playing a
+//! different move at an earlier point starts a variation rather than discarding the moves
+//! that followed.
+//!
+//! A `Game` can be exported as a [`PGN`], or as the `position` command sent to a UCI engine.
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::board::game::Game;
+//!
+//! let mut game = Game::new();
+//! for mov in ["e4", "e5", "Nf3", "Nc6"].iter() {
+//!     game.play_san(mov).unwrap();
+//! }
+//!
+//! // Go back a move, and play something else instead
+//! assert!(game.undo());
+//! game.play_san("Nf6").unwrap();
+//!
+//! assert_eq!(game.ply(), 4);
+//! assert_eq!(game.uci_position(), "position startpos moves e2e4 e7e5 g1f3 g8f6");
+//! assert_eq!(
+//!     game.to_pgn().to_string().lines().last().unwrap(),
+//!     "1. e4 e5 2. Nf3 Nc6 (2... Nf6) *"
+//! );
+//! ```
+//!
+//! [`Game`]: struct.Game.html
+//! [`undo`]: struct.Game.html#method.undo
+//! [`redo`]: struct.Game.html#method.redo
+//! [`goto_ply`]: struct.Game.html#method.goto_ply
+//! [`PGN`]: ../pgn/struct.PGN.html
+
+use std::fmt;
+use std::time::Duration;
+
+use super::fen::OPENING_POS_FEN;
+use super::pgn::{GameResult, PGNError, PGNMove, PGNTags, PGNVariation, PGN};
+use super::san::SanError;
+use super::variant::Variant;
+use super::Board;
+use core::piece_move::BitMove;
+
+/// An engine's evaluation of a position, from White's point of view.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Evaluation {
+    /// An advantage measured in hundredths of a pawn.
+    Centipawns(i32),
+    /// A forced mate in the given number of moves. Negative if Black is mating.
+    Mate(i32),
+}
+
+impl fmt::Display for Evaluation {
+    /// Displays the evaluation as used by the `[%eval]` comment command, e.g. `0.25` or `#-3`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Evaluation::Centipawns(cp) => {
+                let sign = if cp < 0 { "-" } else { "" };
+                write!(f, "{}{}.{:02}", sign, cp.abs() / 100, cp.abs() % 100)
+            }
+            Evaluation::Mate(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+/// A move of a [`Game`], alongside its annotations.
+///
+/// [`Game`]: struct.Game.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameMove {
+    /// The move played.
+    pub bit_move: BitMove,
+    /// The move in Standard Algebraic Notation.
+    pub san: String,
+    /// Numeric Annotation Glyphs of the move.
+    pub nags: Vec<u8>,
+    /// Comments before the move. These are only exported when the move starts the game or a
+    /// variation.
+    pub starting_comments: Vec<String>,
+    /// Comments following the move.
+    pub comments: Vec<String>,
+    /// The time left on the mover's clock after the move.
+    pub clock: Option<Duration>,
+    /// The evaluation of the position after the move.
+    pub eval: Option<Evaluation>,
+}
+
+impl GameMove {
+    /// Creates a `GameMove` with no annotations from a move legal on the given `Board`.
+    pub fn new(board: &Board, bit_move: BitMove) -> GameMove {
+        GameMove {
+            bit_move,
+            san: board.move_to_san(bit_move),
+            nags: Vec::new(),
+            starting_comments: Vec::new(),
+            comments: Vec::new(),
+            clock: None,
+            eval: None,
+        }
+    }
+}
+
+/// A position in the tree of moves.
+#[derive(Clone, Debug)]
+struct Node {
+    parent: usize,
+    // `None` only for the root, the starting position
+    game_move: Option<GameMove>,
+    // The first child continues the mainline, the rest are variations
+    children: Vec<usize>,
+    // Index into `children` of the move `redo` follows
+    selected: usize,
+}
+
+/// Index of the node for the starting position.
+const ROOT: usize = 0;
+
+/// A game of chess, consisting of its starting position and every move played since.
+///
+/// Moves are stored as a tree: the moves following a position are its mainline continuation,
+/// followed by any variations. The game's current position is a node of that tree, and the
+/// *current line* is the path from the start to the current position, continued by the moves
+/// most recently visited after it. [`redo`] and [`goto_ply`] move along the current line.
+///
+/// [`redo`]: #method.redo
+/// [`goto_ply`]: #method.goto_ply
+#[derive(Debug)]
+pub struct Game {
+    start: Board,
+    board: Board,
+    nodes: Vec<Node>,
+    current: usize,
+    ply: usize,
+    tags: PGNTags,
+    result: GameResult,
+}
+
+impl Game {
+    /// Creates a game starting from the standard starting position.
+    pub fn new() -> Game {
+        Game::from_board(&Board::start_pos())
+    }
+
+    /// Creates a game starting from the position of the given `Board`. Any moves previously
+    /// applied to the `Board` are not part of the game.
+    pub fn from_board(board: &Board) -> Game {
+        let mut start = Board::from_fen_variant(&board.fen(), board.variant())
+            .expect("the FEN of a Board is always valid");
+        start.set_chess960(board.is_chess960());
+        Game {
+            board: Board::clone(&start),
+            start,
+            nodes: vec![Node {
+                parent: ROOT,
+                game_move: None,
+                children: Vec::new(),
+                selected: 0,
+            }],
+            current: ROOT,
+            ply: 0,
+            tags: PGNTags::default(),
+            result: GameResult::Other,
+        }
+    }
+
+    /// Creates a game from a [`PGN`], including its tags, result, annotations and variations.
+    ///
+    /// `[%clk]` and `[%eval]` commands are read out of the comments into the clock and
+    /// evaluation of each move. The game's current position is its starting position.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `FEN` tag of the PGN isn't a valid position.
+    ///
+    /// [`PGN`]: ../pgn/struct.PGN.html
+    pub fn from_pgn(pgn: &PGN) -> Result<Game, PGNError> {
+        let mut game = Game::from_board(&pgn.initial_board()?);
+        game.tags = pgn.tags().clone();
+        game.tags.remove("Result");
+        game.result = pgn.result();
+        game.add_pgn_variation(pgn.moves());
+        game.goto_start();
+        Ok(game)
+    }
+
+    /// Returns the current position.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the position the game started from.
+    pub fn start_board(&self) -> &Board {
+        &self.start
+    }
+
+    /// Returns the tags exported with the game.
+    pub fn tags(&self) -> &PGNTags {
+        &self.tags
+    }
+
+    /// Returns a mutable reference to the tags exported with the game.
+    pub fn tags_mut(&mut self) -> &mut PGNTags {
+        &mut self.tags
+    }
+
+    /// Returns the result of the game.
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    /// Sets the result of the game.
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+    }
+
+    /// Returns the number of moves played to reach the current position.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Returns the ply at the end of the current line.
+    pub fn last_ply(&self) -> usize {
+        let mut ply = self.ply;
+        let mut node = self.current;
+        while let Some(child) = self.selected_child(node) {
+            node = child;
+            ply += 1;
+        }
+        ply
+    }
+
+    /// Returns the move leading to the current position, or `None` at the start of the game.
+    pub fn current_move(&self) -> Option<&GameMove> {
+        self.nodes[self.current].game_move.as_ref()
+    }
+
+    /// Returns a mutable reference to the move leading to the current position, allowing its
+    /// annotations to be changed.
+    pub fn current_move_mut(&mut self) -> Option<&mut GameMove> {
+        self.nodes[self.current].game_move.as_mut()
+    }
+
+    /// Returns the moves played to reach the current position.
+    pub fn moves(&self) -> Vec<BitMove> {
+        let mut moves = Vec::with_capacity(self.ply);
+        let mut node = self.current;
+        while node != ROOT {
+            moves.push(self.bit_move(node));
+            node = self.nodes[node].parent;
+        }
+        moves.reverse();
+        moves
+    }
+
+    /// Returns the moves of the current line, including those after the current position.
+    pub fn line(&self) -> Vec<BitMove> {
+        let mut moves = self.moves();
+        let mut node = self.current;
+        while let Some(child) = self.selected_child(node) {
+            moves.push(self.bit_move(child));
+            node = child;
+        }
+        moves
+    }
+
+    /// Returns the moves of the mainline.
+    pub fn mainline(&self) -> Vec<BitMove> {
+        let mut moves = Vec::new();
+        let mut node = ROOT;
+        while let Some(&child) = self.nodes[node].children.first() {
+            moves.push(self.bit_move(child));
+            node = child;
+        }
+        moves
+    }
+
+    /// Returns the moves that have been played from the current position. The first is the
+    /// mainline continuation, and the rest are variations.
+    pub fn variations(&self) -> Vec<BitMove> {
+        self.nodes[self.current]
+            .children
+            .iter()
+            .map(|&child| self.bit_move(child))
+            .collect()
+    }
+
+    /// Plays a move from the current position, returning `false` if the move isn't legal.
+    ///
+    /// If the move has already been played from this position, the game moves along it as
+    /// with [`redo`]. Otherwise, the move becomes the mainline continuation if there is none,
+    /// or a new variation if there is.
+    ///
+    /// [`redo`]: #method.redo
+    pub fn play(&mut self, mov: BitMove) -> bool {
+        let existing = self.nodes[self.current]
+            .children
+            .iter()
+            .position(|&child| self.bit_move(child) == mov);
+        if let Some(index) = existing {
+            self.nodes[self.current].selected = index;
+            return self.redo();
+        }
+
+        if mov.is_null() || !self.board.pseudo_legal_move(mov) || !self.board.legal_move(mov) {
+            return false;
+        }
+        let game_move = GameMove::new(&self.board, mov);
+        self.push(game_move);
+        true
+    }
+
+    /// Plays a move given in Standard Algebraic Notation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the move doesn't match exactly one legal move of the current
+    /// position.
+    pub fn play_san(&mut self, san: &str) -> Result<(), SanError> {
+        let mov = self.board.parse_san(san)?;
+        self.play(mov);
+        Ok(())
+    }
+
+    /// Plays a move given in UCI notation, returning `false` if it isn't a legal move.
+    pub fn play_uci(&mut self, uci_move: &str) -> bool {
+        let mov = self.board.with_legal_moves(|moves| {
+            moves
+                .iter()
+                .find(|m| self.board.move_to_uci(**m) == uci_move)
+                .cloned()
+        });
+        match mov {
+            Some(mov) => self.play(mov),
+            None => false,
+        }
+    }
+
+    /// Takes back the move leading to the current position, returning `false` at the start of
+    /// the game. The move can be played again with [`redo`].
+    ///
+    /// [`redo`]: #method.redo
+    pub fn undo(&mut self) -> bool {
+        if self.current == ROOT {
+            return false;
+        }
+        let parent = self.nodes[self.current].parent;
+        let index = self.nodes[parent]
+            .children
+            .iter()
+            .position(|&child| child == self.current)
+            .unwrap();
+        self.nodes[parent].selected = index;
+        self.board.undo_move();
+        self.current = parent;
+        self.ply -= 1;
+        true
+    }
+
+    /// Plays the next move of the current line, returning `false` at the end of the line.
+    pub fn redo(&mut self) -> bool {
+        match self.selected_child(self.current) {
+            Some(child) => {
+                self.board.apply_move(self.bit_move(child));
+                self.current = child;
+                self.ply += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves along the current line to the position after `ply` moves, returning `false` and
+    /// leaving the position unchanged if the line is shorter than that.
+    pub fn goto_ply(&mut self, ply: usize) -> bool {
+        if ply > self.last_ply() {
+            return false;
+        }
+        while self.ply > ply {
+            self.undo();
+        }
+        while self.ply < ply {
+            self.redo();
+        }
+        true
+    }
+
+    /// Moves to the starting position.
+    pub fn goto_start(&mut self) {
+        while self.undo() {}
+    }
+
+    /// Moves to the end of the current line.
+    pub fn goto_end(&mut self) {
+        while self.redo() {}
+    }
+
+    /// Makes the given move, played from the current position, the mainline continuation.
+    /// The previous mainline continuation becomes the first variation. Returns `false` if
+    /// the move hasn't been played from the current position.
+    pub fn promote_variation(&mut self, mov: BitMove) -> bool {
+        let index = match self.nodes[self.current]
+            .children
+            .iter()
+            .position(|&child| self.bit_move(child) == mov)
+        {
+            Some(index) => index,
+            None => return false,
+        };
+        let node = &mut self.nodes[self.current];
+        let child = node.children.remove(index);
+        node.children.insert(0, child);
+        node.selected = 0;
+        true
+    }
+
+    /// Exports the game as a [`PGN`].
+    ///
+    /// If the game doesn't start from the standard starting position, the `SetUp` and `FEN`
+    /// tags are added. Clock times and evaluations are written as `[%clk]` and `[%eval]`
+    /// comment commands.
+    ///
+    /// [`PGN`]: ../pgn/struct.PGN.html
+    pub fn to_pgn(&self) -> PGN {
+        let mut tags = self.tags.clone();
+        let fen = self.start.fen();
+        if self.start.variant() != Variant::Standard {
+            tags.set("Variant", self.start.variant().name());
+        } else if self.start.is_chess960() {
+            tags.set("Variant", "Chess960");
+        }
+        if fen != OPENING_POS_FEN || self.start.is_chess960() {
+            tags.set("SetUp", "1");
+            tags.set("FEN", &fen);
+        }
+
+        let mut mainline = PGNVariation::default();
+        if let Some(&first) = self.nodes[ROOT].children.first() {
+            mainline.comments = self.game_move(first).starting_comments.clone();
+        }
+        self.add_pgn_moves(ROOT, &mut mainline.moves);
+        PGN::new(tags, mainline, self.result)
+    }
+
+    /// Returns the UCI `position` command for the current position, listing the moves played
+    /// from the starting position.
+    pub fn uci_position(&self) -> String {
+        let mut position = if self.start.fen() == OPENING_POS_FEN
+            && self.start.variant() == Variant::Standard
+            && !self.start.is_chess960()
+        {
+            "position startpos".to_string()
+        } else {
+            format!("position fen {}", self.start.fen())
+        };
+        let moves = self.moves();
+        if !moves.is_empty() {
+            position.push_str(" moves");
+            for mov in moves {
+                position.push(' ');
+                position.push_str(&self.start.move_to_uci(mov));
+            }
+        }
+        position
+    }
+
+    fn game_move(&self, node: usize) -> &GameMove {
+        self.nodes[node].game_move.as_ref().unwrap()
+    }
+
+    fn bit_move(&self, node: usize) -> BitMove {
+        self.game_move(node).bit_move
+    }
+
+    fn selected_child(&self, node: usize) -> Option<usize> {
+        let node = &self.nodes[node];
+        node.children.get(node.selected).cloned()
+    }
+
+    /// Adds a legal move after the current position, and plays it.
+    fn push(&mut self, game_move: GameMove) {
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            parent: self.current,
+            game_move: Some(game_move),
+            children: Vec::new(),
+            selected: 0,
+        });
+        let node = &mut self.nodes[self.current];
+        node.children.push(index);
+        node.selected = node.children.len() - 1;
+        self.redo();
+    }
+
+    /// Adds the moves of a variation after the current position, moving to its end.
+    fn add_pgn_variation(&mut self, variation: &PGNVariation) {
+        for (i, pgn_move) in variation.moves.iter().enumerate() {
+            let mut game_move = GameMove {
+                bit_move: pgn_move.bit_move,
+                san: pgn_move.san.clone(),
+                nags: pgn_move.nags.clone(),
+                starting_comments: Vec::new(),
+                comments: Vec::new(),
+                clock: None,
+                eval: None,
+            };
+            if i == 0 {
+                game_move.starting_comments = variation.comments.clone();
+            }
+            for comment in pgn_move.comments.iter() {
+                let comment = read_commands(comment, &mut game_move);
+                if !comment.is_empty() {
+                    game_move.comments.push(comment);
+                }
+            }
+
+            self.push(game_move);
+
+            // Variations start from the position before the move they're an alternative to
+            if !pgn_move.variations.is_empty() {
+                let main = self.current;
+                self.undo();
+                for sub_variation in pgn_move.variations.iter() {
+                    let ply = self.ply;
+                    self.add_pgn_variation(sub_variation);
+                    self.goto_ply(ply);
+                }
+                let current = self.current;
+                let index = self.nodes[current]
+                    .children
+                    .iter()
+                    .position(|&child| child == main)
+                    .unwrap();
+                self.nodes[current].selected = index;
+                self.redo();
+            }
+        }
+    }
+
+    /// Adds the moves following `node` to a PGN line, with any variations.
+    fn add_pgn_moves(&self, mut node: usize, moves: &mut Vec<PGNMove>) {
+        while let Some((&main, alternatives)) = self.nodes[node].children.split_first() {
+            let mut pgn_move = self.pgn_move(main);
+            for &alternative in alternatives {
+                let mut variation = PGNVariation {
+                    comments: self.game_move(alternative).starting_comments.clone(),
+                    moves: vec![self.pgn_move(alternative)],
+                };
+                self.add_pgn_moves(alternative, &mut variation.moves);
+                pgn_move.variations.push(variation);
+            }
+            moves.push(pgn_move);
+            node = main;
+        }
+    }
+
+    fn pgn_move(&self, node: usize) -> PGNMove {
+        let game_move = self.game_move(node);
+        let mut comments = Vec::with_capacity(game_move.comments.len() + 1);
+        let mut commands = Vec::new();
+        if let Some(eval) = game_move.eval {
+            commands.push(format!("[%eval {}]", eval));
+        }
+        if let Some(clock) = game_move.clock {
+            commands.push(format!("[%clk {}]", format_clock(clock)));
+        }
+        if !commands.is_empty() {
+            comments.push(commands.join(" "));
+        }
+        comments.extend(game_move.comments.iter().cloned());
+        PGNMove {
+            bit_move: game_move.bit_move,
+            san: game_move.san.clone(),
+            nags: game_move.nags.clone(),
+            comments,
+            variations: Vec::new(),
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
+impl Clone for Game {
+    /// Clones the game. As cloning a `Board` drops its history, the current position is
+    /// recreated by replaying the moves leading to it.
+    fn clone(&self) -> Game {
+        let mut board = Board::clone(&self.start);
+        for mov in self.moves() {
+            board.apply_move(mov);
+        }
+        Game {
+            start: Board::clone(&self.start),
+            board,
+            nodes: self.nodes.clone(),
+            current: self.current,
+            ply: self.ply,
+            tags: self.tags.clone(),
+            result: self.result,
+        }
+    }
+}
+
+/// Formats a clock time as `H:MM:SS`, with tenths of a second if there are any.
+fn format_clock(clock: Duration) -> String {
+    let secs = clock.as_secs();
+    let mut time = format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    let tenths = clock.subsec_millis() / 100;
+    if tenths != 0 {
+        time.push_str(&format!(".{}", tenths));
+    }
+    time
+}
+
+/// Parses a clock time of the form `H:MM:SS`, where the seconds may have a fraction.
+fn parse_clock(time: &str) -> Option<Duration> {
+    let mut parts = time.split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || minutes >= 60 || !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    let millis = (seconds * 1000.0).round() as u64;
+    Some(Duration::from_millis(
+        (hours * 60 + minutes) * 60_000 + millis,
+    ))
+}
+
+fn parse_eval(eval: &str) -> Option<Evaluation> {
+    if let Some(mate) = eval.strip_prefix('#') {
+        return mate.parse().ok().map(Evaluation::Mate);
+    }
+    let pawns: f64 = eval.parse().ok()?;
+    if !pawns.is_finite() {
+        return None;
+    }
+    Some(Evaluation::Centipawns((pawns * 100.0).round() as i32))
+}
+
+/// Reads any `[%clk]` and `[%eval]` commands out of a comment into the move, returning the
+/// rest of the comment.
+fn read_commands(comment: &str, game_move: &mut GameMove) -> String {
+    let mut rest = String::new();
+    let mut text = comment;
+    while let Some(start) = text.find("[%") {
+        let end = match text[start..].find(']') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let mut command = text[start + 2..end].split_whitespace();
+        let read = match (command.next(), command.next(), command.next()) {
+            (Some("clk"), Some(time), None) => match parse_clock(time) {
+                Some(clock) => {
+                    game_move.clock = Some(clock);
+                    true
+                }
+                None => false,
+            },
+            (Some("eval"), Some(eval), None) => match parse_eval(eval) {
+                Some(eval) => {
+                    game_move.eval = Some(eval);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        };
+        rest.push_str(&text[..start]);
+        if !read {
+            rest.push_str(&text[start..=end]);
+        }
+        text = &text[end + 1..];
+    }
+    rest.push_str(text);
+    rest.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for mov in moves {
+            game.play_san(mov).unwrap();
+        }
+    }
+
+    fn san_line(game: &Game) -> Vec<String> {
+        let mut board = Board::clone(game.start_board());
+        game.line()
+            .into_iter()
+            .map(|mov| {
+                let san = board.move_to_san(mov);
+                board.apply_move(mov);
+                san
+            })
+            .collect()
+    }
+
+    #[test]
+    fn navigation() {
+        let mut game = Game::new();
+        play(&mut game, &["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        let fen = game.board().fen();
+        assert_eq!(game.ply(), 5);
+        assert_eq!(game.last_ply(), 5);
+        assert_eq!(game.current_move().unwrap().san, "Bb5");
+        assert!(!game.redo());
+
+        assert!(game.goto_ply(2));
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.board().moves_played(), 2);
+        assert_eq!(game.moves().len(), 2);
+        assert_eq!(game.line().len(), 5);
+        assert!(!game.goto_ply(6));
+        assert_eq!(game.ply(), 2);
+
+        assert!(game.redo());
+        assert_eq!(game.current_move().unwrap().san, "Nf3");
+        game.goto_end();
+        assert_eq!(game.board().fen(), fen);
+
+        game.goto_start();
+        assert!(game.current_move().is_none());
+        assert!(!game.undo());
+        assert_eq!(game.board().fen(), OPENING_POS_FEN);
+        assert_eq!(game.last_ply(), 5);
+
+        // Cloning a Board drops its history, so a cloned game replays its moves to rebuild it,
+        // and navigation still works
+        game.goto_ply(3);
+        let mut clone = game.clone();
+        assert!(clone.undo());
+        assert!(clone.undo());
+        let mut board = Board::start_pos();
+        board.apply_uci_move("e2e4");
+        assert_eq!(clone.board().fen(), board.fen());
+        assert_eq!(clone.ply(), 1);
+        assert_eq!(clone.line(), game.line());
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let mut game = Game::new();
+        assert!(!game.play(BitMove::null()));
+        assert!(!game.play_uci("e2e5"));
+        assert!(game.play_san("Ke2").is_err());
+        assert!(game.play_uci("e2e4"));
+        assert_eq!(game.ply(), 1);
+        assert_eq!(game.variations(), Vec::new());
+    }
+
+    #[test]
+    fn variations() {
+        let mut game = Game::new();
+        play(&mut game, &["e4", "e5", "Nf3", "Nc6"]);
+        let mainline = game.mainline();
+
+        // Playing a different move starts a variation, and keeps the mainline
+        game.goto_ply(2);
+        play(&mut game, &["Bc4", "Nf6", "d3"]);
+        assert_eq!(game.mainline(), mainline);
+        assert_eq!(san_line(&game), ["e4", "e5", "Bc4", "Nf6", "d3"]);
+
+        // Redo follows the most recently visited variation
+        game.goto_ply(2);
+        assert_eq!(game.variations().len(), 2);
+        game.goto_end();
+        assert_eq!(game.ply(), 5);
+        assert_eq!(game.current_move().unwrap().san, "d3");
+
+        // Playing an existing move moves along it
+        game.goto_ply(2);
+        let nf3 = game.variations()[0];
+        assert!(game.play(nf3));
+        assert_eq!(game.variations().len(), 1);
+        assert_eq!(game.last_ply(), 4);
+        game.undo();
+        assert_eq!(game.variations().len(), 2);
+
+        let bc4 = game.variations()[1];
+        assert!(game.promote_variation(bc4));
+        assert!(!game.promote_variation(BitMove::null()));
+        assert_eq!(game.variations(), vec![bc4, nf3]);
+        assert_eq!(game.mainline().len(), 5);
+        assert_eq!(game.mainline()[2], bc4);
+    }
+
+    #[test]
+    fn uci_position() {
+        let mut game = Game::new();
+        assert_eq!(game.uci_position(), "position startpos");
+        play(&mut game, &["e4", "c5"]);
+        assert_eq!(game.uci_position(), "position startpos moves e2e4 c7c5");
+        game.undo();
+        assert_eq!(game.uci_position(), "position startpos moves e2e4");
+
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let mut game = Game::from_board(&Board::from_fen(fen).unwrap());
+        play(&mut game, &["O-O"]);
+        assert_eq!(
+            game.uci_position(),
+            format!("position fen {} moves e1g1", fen)
+        );
+
+        // Moves applied to the board before the game began aren't part of it
+        let mut board = Board::start_pos();
+        board.apply_uci_move("d2d4");
+        let game = Game::from_board(&board);
+        assert_eq!(game.uci_position(), format!("position fen {}", board.fen()));
+    }
+
+    #[test]
+    fn pgn_export() {
+        let mut game = Game::new();
+        game.tags_mut().set("Event", "Test");
+        play(&mut game, &["e4", "e5", "Nf3"]);
+        {
+            let nf3 = game.current_move_mut().unwrap();
+            nf3.clock = Some(Duration::from_secs(3723));
+            nf3.eval = Some(Evaluation::Centipawns(-5));
+            nf3.comments.push("Develops".to_string());
+            nf3.nags.push(1);
+        }
+        game.undo();
+        play(&mut game, &["Bc4"]);
+        game.current_move_mut()
+            .unwrap()
+            .starting_comments
+            .push("Bishop's Opening".to_string());
+        game.current_move_mut().unwrap().eval = Some(Evaluation::Mate(-12));
+        game.set_result(GameResult::Draw);
+
+        let pgn = game.to_pgn();
+        assert_eq!(pgn.tags().get("Event"), Some("Test"));
+        assert_eq!(pgn.tags().fen(), None);
+        assert_eq!(pgn.result(), GameResult::Draw);
+        let text = pgn.to_string();
+        assert!(text.ends_with(
+            "1. e4 e5 2. Nf3 $1 {[%eval -0.05] [%clk 1:02:03]} {Develops} ({Bishop's\n\
+             Opening} 2. Bc4 {[%eval #-12]}) 1/2-1/2\n"
+        ));
+
+        // Reading the PGN back gives the same game
+        let mut read = Game::from_pgn(&PGN::parse(&text).unwrap()).unwrap();
+        assert_eq!(read.ply(), 0);
+        assert_eq!(read.to_pgn().to_string(), text);
+        assert_eq!(read.tags().get("Event"), Some("Test"));
+        read.goto_end();
+        let nf3 = read.current_move().unwrap();
+        assert_eq!(nf3.clock, Some(Duration::from_secs(3723)));
+        assert_eq!(nf3.eval, Some(Evaluation::Centipawns(-5)));
+        assert_eq!(nf3.comments, vec!["Develops".to_string()]);
+        assert_eq!(read.mainline(), game.mainline());
+    }
+
+    #[test]
+    fn pgn_import_nested_variations() {
+        let text = "[FEN \"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3\"]\n\n\
+                    3. Bb5 (3. Bc4 Bc5 (3... Nf6 4. Ng5) 4. c3) 3... a6 (3... Nf6) 4. Ba4 *";
+        let pgn = PGN::parse(text).unwrap();
+        let mut game = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(game.to_pgn(), {
+            let mut pgn = pgn.clone();
+            pgn.tags_mut().set("SetUp", "1");
+            pgn
+        });
+        assert_eq!(san_line(&game), ["Bb5", "a6", "Ba4"]);
+
+        let bc4 = game.variations()[1];
+        game.play(bc4);
+        assert_eq!(san_line(&game), ["Bc4", "Bc5", "c3"]);
+        game.undo();
+        game.goto_end();
+        assert_eq!(san_line(&game), ["Bc4", "Bc5", "c3"]);
+        game.goto_ply(1);
+        assert_eq!(game.variations().len(), 2);
+    }
+
+    #[test]
+    fn comment_commands() {
+        let board = Board::start_pos();
+        let mut game_move = GameMove::new(&board, board.generate_moves()[0]);
+        let rest = read_commands(
+            "[%clk 0:00:09.5] good [%csl Ge4] [%eval abc]",
+            &mut game_move,
+        );
+        assert_eq!(rest, "good [%csl Ge4] [%eval abc]");
+        assert_eq!(game_move.clock, Some(Duration::from_millis(9500)));
+        assert_eq!(game_move.eval, None);
+        assert_eq!(format_clock(Duration::from_millis(9500)), "0:00:09.5");
+
+        assert_eq!(parse_eval("1.5"), Some(Evaluation::Centipawns(150)));
+        assert_eq!(parse_eval("#3"), Some(Evaluation::Mate(3)));
+        assert_eq!(Evaluation::Centipawns(150).to_string(), "1.50");
+        assert_eq!(Evaluation::Centipawns(-40).to_string(), "-0.40");
+        assert_eq!(parse_clock("1:60:00"), None);
+    }
+}
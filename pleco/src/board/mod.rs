@@ -11,6 +11,7 @@
 
 use std::cmp::{max, min, PartialEq};
 use std::hint::unreachable_unchecked;
+use std::ops::Index;
 use std::option::*;
 use std::{char, fmt, num};
 
@@ -20,29 +21,39 @@ use bot_prelude::AlphaBetaSearcher;
 use core::bitboard::BitBoard;
 use core::masks::*;
 use core::mono_traits::*;
-use core::move_list::{MoveList, ScoringMoveList};
+use core::move_list::{CrazyhouseMoveList, MVPushable, MoveList, ScoringMoveList};
 use core::piece_move::{BitMove, MoveType};
 use core::score::*;
 use core::sq::{NO_SQ, SQ};
 use core::*;
 use helper::prelude::*;
 use helper::Helper;
-use tools::pleco_arc::{Arc, UniqueArc};
 use tools::prng::PRNG;
 use tools::{PreFetchable, Searcher};
 
-use self::board_state::BoardState;
-use self::castle_rights::Castling;
-use self::movegen::{Legal, MoveGen, PseudoLegal};
+use self::board_state::{BoardState, StateStack};
+use self::castle_rights::{Castling, CastlingSquares};
+use self::movegen::{Legal, MoveGen, PseudoLegal, StagedMoves, StagedMovesIter};
 use self::piece_locations::PieceLocations;
+use self::variant::Variant;
 
+pub mod attacks;
 pub mod board_state;
+pub mod builder;
 pub mod castle_rights;
+pub mod epd;
 pub mod fen;
+pub mod game;
 pub mod movegen;
+pub mod outcome;
 pub mod perft;
-mod pgn;
+pub mod pgn;
 pub mod piece_locations;
+pub mod san;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod svg;
+pub mod variant;
 
 /// Represents possible Errors encountered while building a `Board` from a fen string.
 pub enum FenBuildError {
@@ -72,7 +83,16 @@ pub enum FenBuildError {
     UnrecognizedPiece {
         piece: char,
     },
+    UnrecognizedCastling {
+        castling: String,
+    },
     UnreadableMoves(num::ParseIntError),
+    UnreadableCheckCount {
+        checks: String,
+    },
+    UnrecognizedPocket {
+        pocket: String,
+    },
     IllegalNumCheckingPieces {
         num: u8,
     },
@@ -126,9 +146,18 @@ impl fmt::Debug for FenBuildError {
             FenBuildError::UnrecognizedPiece { piece } => {
                 writeln!(f, "unrecognized piece: {}", piece)
             }
+            FenBuildError::UnrecognizedCastling { ref castling } => {
+                writeln!(f, "unrecognized castling rights: {}", castling)
+            }
             FenBuildError::UnreadableMoves(ref err) => {
                 writeln!(f, "An unknown error has occurred {:?}", err)
             }
+            FenBuildError::UnreadableCheckCount { ref checks } => {
+                writeln!(f, "unreadable check counter: {}", checks)
+            }
+            FenBuildError::UnrecognizedPocket { ref pocket } => {
+                writeln!(f, "unrecognized pocket: {}", pocket)
+            }
             FenBuildError::IllegalNumCheckingPieces { num } => {
                 writeln!(f, "too many checking piece: {}", num)
             }
@@ -206,13 +235,13 @@ pub struct Board {
     depth: u16,                                       // Current depth since last shallow_copy
     piece_counts: [[u8; PIECE_TYPE_CNT]; PLAYER_CNT], // Count of each Piece
     piece_locations: PieceLocations,                  // Mapping Squares to Pieces and Players
-    zobrist_history: Vec<u64>,                        // Historic Zobrist keys of the board
-    threefold_repetition: bool,                       // Whether the board has been repeated 3 times
+    castling_squares: CastlingSquares,                // Starting squares of castling kings & rooks
+    chess960: bool,                                   // Whether castling uses Chess960 notation
+    variant: Variant,                                 // Variant of chess being played
 
-    // State of the Board, Un modifiable.
-    // Arc to allow easy and quick copying of boards without copying memory
-    // or recomputing BoardStates.
-    state: Arc<BoardState>,
+    // States of the Board, one for each position reached. Dereferences to the current
+    // state, and is preallocated so applying a move doesn't allocate.
+    state: StateStack,
 
     /// Reference to the pre-computed lookup tables.
     #[doc(hidden)]
@@ -237,6 +266,7 @@ impl PartialEq for Board {
             && self.bbs[PieceType::All as usize] == other.bbs[PieceType::All as usize]
             && *self.state == *other.state
             && self.piece_locations == other.piece_locations
+            && self.variant == other.variant
     }
 }
 
@@ -267,11 +297,49 @@ impl Board {
         Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap()
     }
 
+    /// Constructs a board from a Chess960 starting position, given by its Scharnagl number
+    /// (SP-ID). Returns `None` if the number isn't between 0 and 959.
+    ///
+    /// The returned board is always a Chess960 board, even for position 518, the standard
+    /// starting position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    ///
+    /// let board = Board::start_pos_chess960(518).unwrap();
+    /// assert!(board.is_chess960());
+    /// assert_eq!(board.fen(), Board::start_pos().fen());
+    /// ```
+    pub fn start_pos_chess960(sp_id: u16) -> Option<Board> {
+        let fen = fen::chess960_fen(sp_id)?;
+        let mut board = Board::from_fen(&fen).unwrap();
+        board.set_chess960(true);
+        Some(board)
+    }
+
+    /// Constructs a board from the starting position of a variant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,Player};
+    /// use pleco::board::variant::Variant;
+    ///
+    /// let board = Board::start_pos_variant(Variant::Horde);
+    /// assert_eq!(board.count_pieces_player(Player::White), 36);
+    /// ```
+    pub fn start_pos_variant(variant: Variant) -> Board {
+        Board::from_fen_variant(variant.start_fen(), variant).unwrap()
+    }
+
     /// Constructs a shallow clone of the Board.
     ///
     /// Contains only the information necessary to apply future moves, more specifically
-    /// does not clone the moves list, and sets depth to zero. Intended for an Engine or
-    /// main thread to share the board to users wanting to search.
+    /// only clones the states since the last capture or pawn move (which are needed to find
+    /// repetitions), and sets depth to zero. Intended for an Engine or main thread to share
+    /// the board to users wanting to search.
     ///
     /// # Safety
     ///
@@ -304,10 +372,11 @@ impl Board {
             depth: 0,
             piece_counts: self.piece_counts,
             piece_locations: self.piece_locations.clone(),
-            state: Arc::clone(&self.state),
+            state: self.state.clone_reversible(),
             magic_helper: self.magic_helper,
-            zobrist_history: self.zobrist_history.clone(),
-            threefold_repetition: self.threefold_repetition,
+            castling_squares: self.castling_squares,
+            chess960: self.chess960,
+            variant: self.variant,
         }
     }
 
@@ -347,11 +416,136 @@ impl Board {
             depth: self.depth,
             piece_counts: self.piece_counts,
             piece_locations: self.piece_locations.clone(),
-            state: Arc::clone(&self.state),
+            state: self.state.clone_reversible(),
             magic_helper: self.magic_helper,
-            zobrist_history: self.zobrist_history.clone(),
-            threefold_repetition: self.threefold_repetition,
+            castling_squares: self.castling_squares,
+            chess960: self.chess960,
+            variant: self.variant,
+        }
+    }
+
+    /// Returns the position with the colours of the pieces swapped and the ranks flipped, so a
+    /// White pawn on e2 becomes a Black pawn on e7. The side to move, castling rights, the
+    /// en-passant square, and the check counts and pockets of variants are swapped as well.
+    ///
+    /// The flipped position is the same position from the other player's point of view, which
+    /// is useful for testing that an evaluation treats both players equally. The move history
+    /// isn't kept.
+    ///
+    /// Returns `None` for Horde and Racing Kings, where the players don't play by the same rules.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,BitMove,SQ};
+    ///
+    /// let board = Board::from_fen("r3k3/8/8/8/3pP3/8/8/4K2R b Kq e3 0 1").unwrap();
+    /// let flipped = board.flip().unwrap();
+    /// assert_eq!(flipped.fen(), "4k2r/8/8/3Pp3/8/8/8/R3K3 w Qk e6 0 1");
+    ///
+    /// let mov = BitMove::make_ep_capture(SQ::D4, SQ::E3);
+    /// assert!(flipped.legal_move(mov.flip()));
+    /// assert_eq!(flipped.flip().unwrap(), board);
+    /// ```
+    pub fn flip(&self) -> Option<Board> {
+        if self.variant == Variant::Horde || self.variant == Variant::RacingKings {
+            return None;
+        }
+        let mut board = self.transformed(SQ::flip, true);
+
+        let mut castling = Castling::empty();
+        for player in ALL_PLAYERS.iter() {
+            for side in [CastleType::KingSide, CastleType::QueenSide].iter() {
+                if self.state.castling.castle_rights(*player, *side) {
+                    castling |= Castling::single_right(!*player, *side);
+                    board.castling_squares.set(
+                        !*player,
+                        *side,
+                        self.castling_squares.king_start(*player).flip(),
+                        self.castling_squares.rook_start(*player, *side).flip(),
+                    );
+                }
+            }
+        }
+        board.set_transformed_state(self, castling, SQ::flip, BitBoard::flip, true);
+        Some(board)
+    }
+
+    /// Returns the position with the files mirrored, so a pawn on a2 moves to h2. Returns `None`
+    /// if either player can castle, as castling isn't symmetric between the two sides of the
+    /// board.
+    ///
+    /// The move history isn't kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    ///
+    /// let board = Board::from_fen("8/2k5/8/8/3P4/8/6K1/8 w - - 0 1").unwrap();
+    /// let mirrored = board.mirror_horizontal().unwrap();
+    /// assert_eq!(mirrored.fen(), "8/5k2/8/8/4P3/8/1K6/8 w - - 0 1");
+    ///
+    /// assert!(Board::start_pos().mirror_horizontal().is_none());
+    /// ```
+    pub fn mirror_horizontal(&self) -> Option<Board> {
+        if !self.state.castling.no_castling() {
+            return None;
+        }
+        let mut board = self.transformed(SQ::mirror_horizontal, false);
+        board.set_transformed_state(
+            self,
+            Castling::empty(),
+            SQ::mirror_horizontal,
+            BitBoard::mirror_horizontal,
+            false,
+        );
+        Some(board)
+    }
+
+    // Places the pieces of a `Board` onto the squares given by `map_sq`, swapping their colours
+    // and the side to move if `swap` is true. The `BoardState` is left blank.
+    fn transformed(&self, map_sq: fn(SQ) -> SQ, swap: bool) -> Board {
+        let mut board = Board::blank();
+        board.variant = self.variant;
+        board.chess960 = self.chess960;
+        for sq in 0..SQ_CNT as u8 {
+            let piece = self.piece_at_sq(SQ(sq));
+            if let Some(player) = piece.player() {
+                let player = if swap { !player } else { player };
+                board.put_piece_c(Piece::make_lossy(player, piece.type_of()), map_sq(SQ(sq)));
+            }
+        }
+        board.turn = if swap { !self.turn } else { self.turn };
+        board.half_moves = (self.half_moves & !1) | board.turn as u16;
+        board
+    }
+
+    // Sets the `BoardState` of a `Board` made by `Board::transformed()` from the current state
+    // of the original `Board`.
+    fn set_transformed_state(
+        &mut self,
+        original: &Board,
+        castling: Castling,
+        map_sq: fn(SQ) -> SQ,
+        map_bb: fn(BitBoard) -> BitBoard,
+        swap: bool,
+    ) {
+        let mut state = BoardState::blank();
+        state.castling = castling;
+        state.rule_50 = original.state.rule_50;
+        if original.state.ep_square != NO_SQ {
+            state.ep_square = map_sq(original.state.ep_square);
         }
+        state.checks = original.state.checks;
+        state.pockets = original.state.pockets;
+        if swap {
+            state.checks.swap(0, 1);
+            state.pockets.swap(0, 1);
+        }
+        state.promoted = map_bb(original.state.promoted);
+        state.set(self);
+        self.state = StateStack::new(state);
     }
 
     /// Creates a `RandBoard` (Random Board Generator) for generation of `Board`s with random
@@ -410,8 +604,39 @@ impl Board {
     /// The Constructed Board may have some Undefined Behavior as a result. It is up to the user to give a
     /// valid FEN string.
     pub fn from_fen(fen: &str) -> Result<Board, FenBuildError> {
+        Board::from_fen_variant(fen, Variant::Standard)
+    }
+
+    /// Constructs a board playing a variant of chess from a FEN String.
+    ///
+    /// Three-check positions may give the number of checks each player has left after the
+    /// en-passant square, like `3+3`, or the number of checks each player has given at the end
+    /// of the FEN, like `+0+0`. The check counter is ignored by other variants.
+    ///
+    /// Crazyhouse positions may give the pieces in each player's pocket in brackets after the
+    /// piece placement, like `[Qp]`, or as a ninth rank. Promoted pieces are followed by a `~`.
+    /// Pockets and promoted pieces are ignored by other variants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,Player};
+    /// use pleco::board::variant::Variant;
+    ///
+    /// let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 2+3 0 1";
+    /// let board = Board::from_fen_variant(fen, Variant::ThreeCheck).unwrap();
+    /// assert_eq!(board.checks_given(Player::White), 1);
+    /// assert_eq!(board.fen(), fen);
+    /// ```
+    pub fn from_fen_variant(fen: &str, variant: Variant) -> Result<Board, FenBuildError> {
         // split the string by white space
-        let det_split: Vec<&str> = fen.split_whitespace().collect();
+        let mut det_split: Vec<&str> = fen.split_whitespace().collect();
+
+        // Three-check counter, which can't be the first four sections
+        let mut checks = [0; PLAYER_CNT];
+        if let Some(i) = det_split.iter().skip(4).position(|s| s.contains('+')) {
+            checks = fen::parse_check_count(det_split.remove(i + 4))?;
+        }
 
         // must have 6 parts :
         // [ Piece Placement, Side to Move, Castling Ability, En Passant square, Half moves, full moves]
@@ -421,29 +646,43 @@ impl Board {
             });
         }
 
+        // Crazyhouse pockets, in brackets after the piece placement
+        let mut placement = det_split[0];
+        let mut pocket = None;
+        if let Some(start) = placement.find('[') {
+            if !placement.ends_with(']') {
+                return Err(FenBuildError::UnrecognizedPocket {
+                    pocket: placement[start..].to_string(),
+                });
+            }
+            pocket = Some(&placement[start + 1..placement.len() - 1]);
+            placement = &placement[..start];
+        }
+
         // Split the first part by '/' for locations
-        let b_rep: Vec<&str> = det_split[0].split('/').collect();
+        let mut b_rep: Vec<&str> = placement.split('/').collect();
+
+        // Crazyhouse pockets may also be written as a ninth rank
+        if b_rep.len() == 9 && pocket.is_none() && variant == Variant::Crazyhouse {
+            pocket = b_rep.pop();
+        }
 
         if b_rep.len() != 8 {
             return Err(FenBuildError::IncorrectRankAmounts { ranks: b_rep.len() });
         }
 
+        let pockets = match pocket {
+            Some(pocket) => fen::parse_pocket(pocket)?,
+            None => [[0; PIECE_TYPE_CNT]; PLAYER_CNT],
+        };
+        let (b_rep, promoted) = fen::strip_promoted(b_rep.as_slice());
+        let b_rep: Vec<&str> = b_rep.iter().map(String::as_str).collect();
+
         let piece_loc = PieceLocations::from_partial_fen(b_rep.as_slice())?;
 
         // Create the Board
-        let mut b = Board {
-            turn: Player::White,
-            bbs: [BitBoard(0); PIECE_TYPE_CNT],
-            bbs_player: [BitBoard(0); PLAYER_CNT],
-            half_moves: 0,
-            depth: 0,
-            piece_counts: [[0; PIECE_TYPE_CNT]; PLAYER_CNT],
-            piece_locations: PieceLocations::blank(),
-            state: Arc::new(BoardState::blank()),
-            magic_helper: Helper::new(),
-            zobrist_history: Vec::new(),
-            threefold_repetition: false,
-        };
+        let mut b = Board::blank();
+        b.variant = variant;
 
         for &(sq, plyr, piece) in piece_loc.iter() {
             b.put_piece_c(Piece::make_lossy(plyr, piece), sq);
@@ -470,11 +709,15 @@ impl Board {
 
         b.turn = turn;
 
-        // Castle Bytes
-        let mut castle_bytes = Castling::empty();
-        for ch in det_split[2].chars() {
-            castle_bytes.add_castling_char(ch);
-        }
+        // Castle Bytes, which Antichess ignores
+        let castling = if variant == Variant::Antichess {
+            "-"
+        } else {
+            det_split[2]
+        };
+        let (castle_bytes, castling_squares, chess960) = fen::parse_castling(&b, castling)?;
+        b.castling_squares = castling_squares;
+        b.chess960 = chess960;
 
         let mut ep_sq: SQ = SQ(0);
         for (i, character) in det_split[3].chars().enumerate() {
@@ -552,16 +795,43 @@ impl Board {
             state.castling = castle_bytes;
             state.rule_50 = rule_50;
             state.ep_square = ep_sq;
+            if variant == Variant::ThreeCheck {
+                state.checks = checks;
+            }
+            if variant == Variant::Crazyhouse {
+                state.pockets = pockets;
+                state.promoted = promoted
+                    & b.occupied()
+                    & !b.piece_two_bb_both_players(PieceType::P, PieceType::K);
+            }
             state.set(&b);
             state
         };
 
-        b.state = Arc::new(b_state);
+        b.state = StateStack::new(b_state);
 
         // validate
         fen::is_valid_fen(b)
     }
 
+    /// Constructs an empty board, with White to move and no `BoardState` information set.
+    fn blank() -> Board {
+        Board {
+            turn: Player::White,
+            bbs: [BitBoard(0); PIECE_TYPE_CNT],
+            bbs_player: [BitBoard(0); PLAYER_CNT],
+            half_moves: 0,
+            depth: 0,
+            piece_counts: [[0; PIECE_TYPE_CNT]; PLAYER_CNT],
+            piece_locations: PieceLocations::blank(),
+            state: StateStack::new(BoardState::blank()),
+            magic_helper: Helper::new(),
+            castling_squares: CastlingSquares::standard(),
+            chess960: false,
+            variant: Variant::Standard,
+        }
+    }
+
     /// Creates a FEN String of the Given Board.
     ///
     /// FEN stands for Forsyth-Edwards Notation, and is a way of representing a board through a
@@ -576,6 +846,26 @@ impl Board {
     /// assert_eq!(board.fen(),"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
     /// ```
     pub fn fen(&self) -> String {
+        self.fen_with_castling(fen::castling_string(self, false))
+    }
+
+    /// Creates a FEN String of the Given Board, with the castling rights written in
+    /// Shredder-FEN notation. This notation gives the file of each castling rook, and is
+    /// commonly used for Chess960 positions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    ///
+    /// let board = Board::start_pos();
+    /// assert_eq!(board.shredder_fen(),"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+    /// ```
+    pub fn shredder_fen(&self) -> String {
+        self.fen_with_castling(fen::castling_string(self, true))
+    }
+
+    fn fen_with_castling(&self, castling: String) -> String {
         // TODO: Doesn't display if rank 8 has zero pieces on it
         let mut s = String::default();
 
@@ -596,6 +886,9 @@ impl Board {
                         blanks = 0;
                     }
                     s.push(piece.character_lossy());
+                    if (self.state.promoted & sq.to_bb()).is_not_empty() {
+                        s.push('~');
+                    }
                 } else {
                     blanks += 1;
                 }
@@ -605,6 +898,10 @@ impl Board {
             }
         }
 
+        if self.variant == Variant::Crazyhouse {
+            s.push_str(&fen::pocket_string(self));
+        }
+
         s.push(' ');
         // current turn
         s.push(match self.turn {
@@ -614,7 +911,7 @@ impl Board {
         s.push(' ');
 
         // Castling State
-        s.push_str(&(self.state.castling.pretty_string()));
+        s.push_str(&castling);
         s.push(' ');
 
         // EP Square
@@ -626,6 +923,13 @@ impl Board {
             s.push(RANK_DISPLAYS[ep.rank_idx_of_sq() as usize]);
         }
         s.push(' ');
+        if self.variant == Variant::ThreeCheck {
+            s.push_str(&format!(
+                "{}+{} ",
+                3 - self.checks_given(Player::White),
+                3 - self.checks_given(Player::Black)
+            ));
+        }
         s.push_str(&format!("{}", self.rule_50()));
         s.push(' ');
         s.push_str(&format!("{}", (self.half_moves / 2) + 1));
@@ -689,103 +993,29 @@ impl Board {
         MT: PreFetchable,
     {
         // Check for stupidity
-        assert_ne!(bit_move.get_src(), bit_move.get_dest());
+        assert!(bit_move.is_okay());
 
         // Zobrist Hash
         let mut pawn_key: u64 = self.state.pawn_key;
         let mut zob: u64 = self.state.zobrist ^ z_side();
         let mut material_key: u64 = self.state.material_key;
 
-        // New Arc for the board to have by making a partial clone of the current state
-        let mut next_arc_state = UniqueArc::new(self.state.partial_clone());
+        // New state for the board to push by making a partial clone of the current state
+        let mut next_state = self.state.partial_clone();
 
-        // Separate Block to allow dereferencing the BoardState
-        // As there is guaranteed only one owner of the Arc, this is allowed
+        // Separate Block to allow borrowing the new BoardState
         {
-            let new_state: &mut BoardState = &mut next_arc_state;
-
-            // Set the prev state
-            new_state.prev = Some(Arc::clone(&self.state));
+            let new_state: &mut BoardState = &mut next_state;
 
             // Increment these
             self.half_moves += 1;
             self.depth += 1;
-            self.zobrist_history.push(self.state.zobrist);
             new_state.rule_50 += 1;
             new_state.ply += 1;
             new_state.prev_move = bit_move;
 
             let us = self.turn;
             let them = !us;
-            let from: SQ = bit_move.get_src();
-            let mut to: SQ = bit_move.get_dest();
-            let piece: Piece = self.piece_at_sq(from);
-
-            debug_assert_ne!(piece, Piece::None);
-
-            let captured: Piece = if bit_move.is_en_passant() {
-                Piece::make_lossy(them, PieceType::P)
-            } else {
-                self.piece_at_sq(to)
-            };
-
-            // Sanity checks
-            assert_eq!(piece.player_lossy(), us);
-
-            if bit_move.is_castle() {
-                // Sanity Checks, moved piece should be K, "captured" should be R
-                // As this is the encoding of Castling
-                assert_eq!(captured.type_of(), PieceType::R);
-                assert_eq!(piece.type_of(), PieceType::K);
-
-                let mut r_src: SQ = SQ(0);
-                let mut r_dst: SQ = SQ(0);
-
-                // yay helper methods
-                self.apply_castling(us, from, &mut to, &mut r_src, &mut r_dst);
-                let rook = Piece::make_lossy(us, PieceType::R);
-                new_state.psq += psq(rook, r_dst) - psq(rook, r_src);
-                zob ^= z_square(r_src, rook) ^ z_square(r_dst, rook);
-                new_state.captured_piece = PieceType::None;
-            } else if captured != Piece::None {
-                let mut cap_sq: SQ = to;
-                if captured.type_of() == PieceType::P {
-                    if bit_move.is_en_passant() {
-                        assert_eq!(cap_sq, self.state.ep_square);
-                        match us {
-                            Player::White => cap_sq -= SQ(8),
-                            Player::Black => cap_sq += SQ(8),
-                        };
-                        assert_eq!(piece.type_of(), PieceType::P);
-                        assert_eq!(us.relative_rank(Rank::R6), to.rank());
-                        assert_eq!(self.piece_at_sq(to), Piece::None);
-                        assert_eq!(self.piece_at_sq(cap_sq).type_of(), PieceType::P);
-                        assert_eq!(self.piece_at_sq(cap_sq).player().unwrap(), them);
-                        self.remove_piece_c(captured, cap_sq);
-                    } else {
-                        self.remove_piece_c(captured, cap_sq);
-                    }
-                    pawn_key ^= z_square(cap_sq, captured);
-                } else {
-                    new_state.nonpawn_material[them as usize] -= piece_value(captured, false);
-                    self.remove_piece_c(captured, cap_sq);
-                }
-                zob ^= z_square(cap_sq, captured);
-
-                // update material key and prefetch access to a Material Table
-                let cap_count = self.count_piece(them, captured.type_of());
-                material_key ^= z_square(SQ(cap_count), captured);
-                material_table.prefetch(material_key);
-                new_state.psq -= psq(captured, cap_sq);
-
-                // Reset Rule 50
-                new_state.rule_50 = 0;
-                new_state.captured_piece = captured.type_of();
-            }
-
-            // Update hash for moving piece
-            zob ^= z_square(to, piece) ^ z_square(from, piece);
-
             if self.state.ep_square != NO_SQ {
                 // Only XOR out the EP hash if it was included in the zobrist
                 // (it was included only when an opponent pawn could capture en passant)
@@ -799,82 +1029,247 @@ impl Board {
                 new_state.ep_square = NO_SQ;
             }
 
-            // Update castling rights
-            if !new_state.castling.is_empty()
-                && (to.castle_rights_mask() | from.castle_rights_mask()) != 0
-            {
-                let castle_zob_index = new_state.castling.update_castling(to, from);
-                zob ^= z_castle(castle_zob_index);
-            }
+            if bit_move.is_drop() {
+                let piece_type = bit_move.drop_piece();
+                let piece = Piece::make_lossy(us, piece_type);
+                let to: SQ = bit_move.get_dest();
+                debug_assert_eq!(self.piece_at_sq(to), Piece::None);
 
-            // Actually move the piece
-            if !bit_move.is_castle() {
-                self.move_piece_c(piece, from, to);
-            }
+                self.put_piece_c(piece, to);
+                zob ^= z_square(to, piece);
 
-            // Pawn Moves need special help :(
-            if piece.type_of() == PieceType::P {
-                if to.0 ^ from.0 == 16 {
-                    // Double Push
-                    let poss_ep: u8 = (to.0 as i8 - us.pawn_push()) as u8;
+                // Take the piece out of the pocket
+                let count = new_state.pockets[us as usize][piece_type as usize];
+                debug_assert!(count > 0);
+                zob ^= z_pocket(us, piece_type, count) ^ z_pocket(us, piece_type, count - 1);
+                new_state.pockets[us as usize][piece_type as usize] = count - 1;
 
-                    // Always set en-passant square after a double pawn push,
-                    // per the FEN standard
-                    new_state.ep_square = SQ(poss_ep);
+                let piece_count = self.count_piece(us, piece_type);
+                material_key ^= z_square(SQ(piece_count - 1), piece);
+                material_table.prefetch(material_key);
+                new_state.psq += psq(piece, to);
 
-                    // Only include in zobrist hash if the moved pawn can be captured
-                    if (pawn_attacks_from(SQ(poss_ep), us) & self.piece_bb(them, PieceType::P))
-                        .is_not_empty()
+                if piece_type == PieceType::P {
+                    pawn_key ^= z_square(to, piece);
+                    pawn_table.prefetch2(pawn_key);
+                } else {
+                    new_state.nonpawn_material[us as usize] += piece_value(piece, false);
+                }
+                new_state.captured_piece = PieceType::None;
+            } else {
+                let from: SQ = bit_move.get_src();
+                let mut to: SQ = bit_move.get_dest();
+                let piece: Piece = self.piece_at_sq(from);
+
+                debug_assert_ne!(piece, Piece::None);
+
+                let captured: Piece = if bit_move.is_en_passant() {
+                    Piece::make_lossy(them, PieceType::P)
+                } else {
+                    self.piece_at_sq(to)
+                };
+
+                // Sanity checks
+                assert_eq!(piece.player_lossy(), us);
+
+                if bit_move.is_castle() {
+                    // Sanity Checks, moved piece should be K, "captured" should be R
+                    // As this is the encoding of Castling
+                    assert_eq!(captured.type_of(), PieceType::R);
+                    assert_eq!(piece.type_of(), PieceType::K);
+
+                    let mut r_src: SQ = SQ(0);
+                    let mut r_dst: SQ = SQ(0);
+
+                    // yay helper methods
+                    self.apply_castling(us, from, &mut to, &mut r_src, &mut r_dst);
+                    let rook = Piece::make_lossy(us, PieceType::R);
+                    new_state.psq += psq(rook, r_dst) - psq(rook, r_src);
+                    zob ^= z_square(r_src, rook) ^ z_square(r_dst, rook);
+                    new_state.captured_piece = PieceType::None;
+                } else if captured != Piece::None {
+                    let mut cap_sq: SQ = to;
+                    if captured.type_of() == PieceType::P {
+                        if bit_move.is_en_passant() {
+                            assert_eq!(cap_sq, self.state.ep_square);
+                            match us {
+                                Player::White => cap_sq -= SQ(8),
+                                Player::Black => cap_sq += SQ(8),
+                            };
+                            assert_eq!(piece.type_of(), PieceType::P);
+                            assert_eq!(us.relative_rank(Rank::R6), to.rank());
+                            assert_eq!(self.piece_at_sq(to), Piece::None);
+                            assert_eq!(self.piece_at_sq(cap_sq).type_of(), PieceType::P);
+                            assert_eq!(self.piece_at_sq(cap_sq).player().unwrap(), them);
+                            self.remove_piece_c(captured, cap_sq);
+                        } else {
+                            self.remove_piece_c(captured, cap_sq);
+                        }
+                        pawn_key ^= z_square(cap_sq, captured);
+                    } else {
+                        new_state.nonpawn_material[them as usize] -= piece_value(captured, false);
+                        self.remove_piece_c(captured, cap_sq);
+                    }
+                    zob ^= z_square(cap_sq, captured);
+
+                    // update material key and prefetch access to a Material Table
+                    let cap_count = self.count_piece(them, captured.type_of());
+                    material_key ^= z_square(SQ(cap_count), captured);
+                    material_table.prefetch(material_key);
+                    new_state.psq -= psq(captured, cap_sq);
+
+                    // Reset Rule 50
+                    new_state.rule_50 = 0;
+                    new_state.captured_piece = captured.type_of();
+
+                    // Crazyhouse captures go to the capturer's pocket, with promoted pieces
+                    // reverting to pawns
+                    if self.variant == Variant::Crazyhouse {
+                        let pocket_piece = if (new_state.promoted & cap_sq.to_bb()).is_not_empty() {
+                            new_state.promoted ^= cap_sq.to_bb();
+                            PieceType::P
+                        } else {
+                            captured.type_of()
+                        };
+                        let count = new_state.pockets[us as usize][pocket_piece as usize];
+                        zob ^= z_pocket(us, pocket_piece, count)
+                            ^ z_pocket(us, pocket_piece, count + 1);
+                        new_state.pockets[us as usize][pocket_piece as usize] = count + 1;
+                    }
+                }
+
+                // Update hash for moving piece
+                zob ^= z_square(to, piece) ^ z_square(from, piece);
+
+                // Update castling rights
+                let rights_mask: u8 =
+                    self.castling_squares.rights_mask(to) | self.castling_squares.rights_mask(from);
+                if !new_state.castling.is_empty() && rights_mask != 0 {
+                    let castle_zob_index = new_state.castling.update_castling_mask(rights_mask);
+                    zob ^= z_castle(castle_zob_index);
+                }
+
+                // Actually move the piece
+                if !bit_move.is_castle() {
+                    self.move_piece_c(piece, from, to);
+                    if self.variant == Variant::Crazyhouse
+                        && (new_state.promoted & from.to_bb()).is_not_empty()
                     {
-                        zob ^= z_ep(new_state.ep_square);
+                        new_state.promoted ^= from.to_bb() | to.to_bb();
                     }
-                } else if bit_move.is_promo() {
-                    let promo_piece: PieceType = bit_move.promo_piece();
-                    let us_promo = Piece::make_lossy(us, promo_piece);
-                    self.remove_piece_c(piece, to);
-                    self.put_piece_c(us_promo, to);
-                    zob ^= z_square(to, us_promo) ^ z_square(to, piece);
-
-                    // We add the zobrist key for the pawn promotion square as we'll just take
-                    // it away later
-                    pawn_key ^= z_square(to, piece);
+                }
+
+                // Pawn Moves need special help :(
+                if piece.type_of() == PieceType::P {
+                    if to.0 ^ from.0 == 16 {
+                        // Double Push. Horde pawns double pushed from the first rank can't be
+                        // captured en passant.
+                        if from.rank() == us.relative_rank(Rank::R2) {
+                            let poss_ep: u8 = (to.0 as i8 - us.pawn_push()) as u8;
+
+                            // Always set en-passant square after a double pawn push,
+                            // per the FEN standard
+                            new_state.ep_square = SQ(poss_ep);
+
+                            // Only include in zobrist hash if the moved pawn can be captured
+                            if (pawn_attacks_from(SQ(poss_ep), us)
+                                & self.piece_bb(them, PieceType::P))
+                            .is_not_empty()
+                            {
+                                zob ^= z_ep(new_state.ep_square);
+                            }
+                        }
+                    } else if bit_move.is_promo() {
+                        let promo_piece: PieceType = bit_move.promo_piece();
+                        let us_promo = Piece::make_lossy(us, promo_piece);
+                        self.remove_piece_c(piece, to);
+                        self.put_piece_c(us_promo, to);
+                        zob ^= z_square(to, us_promo) ^ z_square(to, piece);
+
+                        // We add the zobrist key for the pawn promotion square as we'll just take
+                        // it away later
+                        pawn_key ^= z_square(to, piece);
+
+                        let promo_count = self.count_piece(us, promo_piece);
+                        let pawn_count = self.count_piece(us, PieceType::P);
+                        material_key ^= z_square(SQ(promo_count - 1), us_promo)
+                            ^ z_square(SQ(pawn_count), piece);
+
+                        new_state.psq += psq(us_promo, to) - psq(piece, to);
+                        new_state.nonpawn_material[us as usize] += piece_value(us_promo, false);
+                        if self.variant == Variant::Crazyhouse {
+                            new_state.promoted |= to.to_bb();
+                        }
+                    }
+
+                    // update pawn key and prefetch access
+                    pawn_key ^= z_square(from, piece) ^ z_square(to, piece);
+                    pawn_table.prefetch2(pawn_key);
+                    new_state.rule_50 = 0;
+                }
 
-                    let promo_count = self.count_piece(us, promo_piece);
-                    let pawn_count = self.count_piece(us, PieceType::P);
-                    material_key ^=
-                        z_square(SQ(promo_count - 1), us_promo) ^ z_square(SQ(pawn_count), piece);
+                new_state.psq += psq(piece, to) - psq(piece, from);
+                new_state.captured_piece = captured.type_of();
 
-                    new_state.psq += psq(us_promo, to) - psq(piece, to);
-                    new_state.nonpawn_material[us as usize] += piece_value(us_promo, false);
+                // Atomic captures explode the capturing piece, along with every piece other
+                // than a pawn next to the capture square
+                if self.variant == Variant::Atomic
+                    && captured != Piece::None
+                    && !bit_move.is_castle()
+                {
+                    let mut rights_mask: u8 = 0;
+                    for (i, sq) in (king_moves(to) | to.to_bb()).enumerate() {
+                        let exploded = self.piece_at_sq(sq);
+                        if exploded == Piece::None
+                            || (sq != to && exploded.type_of() == PieceType::P)
+                        {
+                            continue;
+                        }
+                        let (player, piece_type) = exploded.player_piece_lossy();
+                        self.remove_piece_c(exploded, sq);
+                        zob ^= z_square(sq, exploded);
+                        let count = self.count_piece(player, piece_type);
+                        material_key ^= z_square(SQ(count), exploded);
+                        new_state.psq -= psq(exploded, sq);
+                        if piece_type == PieceType::P {
+                            pawn_key ^= z_square(sq, exploded);
+                        } else if piece_type != PieceType::K {
+                            new_state.nonpawn_material[player as usize] -=
+                                piece_value(exploded, false);
+                        }
+                        rights_mask |= self.castling_squares.rights_mask(sq);
+                        new_state.exploded[i] = exploded;
+                    }
+                    if !new_state.castling.is_empty() && rights_mask != 0 {
+                        zob ^= z_castle(new_state.castling.update_castling_mask(rights_mask));
+                    }
                 }
+            }
 
-                // update pawn key and prefetch access
-                pawn_key ^= z_square(from, piece) ^ z_square(to, piece);
-                pawn_table.prefetch2(pawn_key);
-                new_state.rule_50 = 0;
+            if gives_check && self.variant == Variant::ThreeCheck {
+                let checks = new_state.checks[us as usize];
+                if checks < 3 {
+                    zob ^= z_checks(us, checks) ^ z_checks(us, checks + 1);
+                    new_state.checks[us as usize] = checks + 1;
+                }
             }
 
-            new_state.psq += psq(piece, to) - psq(piece, from);
-            new_state.captured_piece = captured.type_of();
             new_state.zobrist = zob;
             new_state.pawn_key = pawn_key;
             new_state.material_key = material_key;
 
             new_state.checkers_bb = if gives_check {
-                self.attackers_to(self.king_sq(them), self.occupied())
-                    & self.get_occupied_player(us)
+                self.king_attackers(them, self.occupied())
             } else {
                 BitBoard(0)
             };
 
-            self.threefold_repetition =
-                self.zobrist_history.iter().filter(|&x| x == &zob).count() >= 2;
             self.turn = them;
 
             // Set the checking information
             new_state.set_check_info(self);
         }
-        self.state = next_arc_state.shareable();
+        self.state.push(next_state);
 
         #[cfg(debug_assertions)]
         self.is_okay().unwrap();
@@ -882,6 +1277,31 @@ impl Board {
         assert!(self.is_ok_quick());
     }
 
+    /// Returns the UCI notation of a move.
+    ///
+    /// Castles are written as the king moving two squares (like "e1g1") for standard boards,
+    /// and as the king capturing its own rook (like "e1h1") for Chess960 boards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    ///
+    /// let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    /// let castle = board.generate_moves().iter().find(|m| m.is_castle()).cloned().unwrap();
+    /// assert_eq!(board.move_to_uci(castle), "e1g1");
+    ///
+    /// board.set_chess960(true);
+    /// assert_eq!(board.move_to_uci(castle), "e1h1");
+    /// ```
+    pub fn move_to_uci(&self, mov: BitMove) -> String {
+        if self.chess960 {
+            mov.stringify_chess960()
+        } else {
+            mov.stringify()
+        }
+    }
+
     /// Applies a UCI move to the board. If the move is a valid string representing a UCI move, then
     /// true will be returned & the move will be applied. Otherwise, false is returned and the board isn't
     /// changed.
@@ -897,11 +1317,12 @@ impl Board {
     /// assert!(success);
     /// ```
     pub fn apply_uci_move(&mut self, uci_move: &str) -> bool {
-        let all_moves: MoveList = self.generate_moves();
-        let bit_move: Option<BitMove> = all_moves
-            .iter()
-            .find(|m| m.stringify() == uci_move)
-            .cloned();
+        let bit_move: Option<BitMove> = self.with_legal_moves(|all_moves| {
+            all_moves
+                .iter()
+                .find(|m| self.move_to_uci(**m) == uci_move)
+                .cloned()
+        });
         if let Some(mov) = bit_move {
             self.apply_move(mov);
             return true;
@@ -933,7 +1354,7 @@ impl Board {
     ///
     /// ```
     pub fn undo_move(&mut self) {
-        assert!(self.state.prev.is_some());
+        assert!(self.state.has_prev());
         assert!(!self.state.prev_move.is_null());
 
         let undo_move: BitMove = self.state.prev_move;
@@ -942,39 +1363,58 @@ impl Board {
         let us: Player = self.turn;
         let from: SQ = undo_move.get_src();
         let to: SQ = undo_move.get_dest();
-        let mut piece_on: Piece = self.piece_at_sq(to);
 
-        // Make sure the piece moved from is not there, or there is a castle
-        assert!(self.piece_at_sq(from) == Piece::None || undo_move.is_castle());
+        if undo_move.is_drop() {
+            let piece_on: Piece = self.piece_at_sq(to);
+            assert_eq!(piece_on.type_of(), undo_move.drop_piece());
+            self.remove_piece_c(piece_on, to);
+        } else {
+            // Put back the pieces exploded by an Atomic capture
+            if self.variant == Variant::Atomic
+                && !undo_move.is_castle()
+                && !self.state.captured_piece.is_none()
+            {
+                for (i, sq) in (king_moves(to) | to.to_bb()).enumerate() {
+                    let exploded = self.state.exploded[i];
+                    if exploded != Piece::None {
+                        self.put_piece_c(exploded, sq);
+                    }
+                }
+            }
+
+            let mut piece_on: Piece = self.piece_at_sq(to);
 
-        if undo_move.is_promo() {
-            assert_eq!(piece_on.type_of(), undo_move.promo_piece());
+            // Make sure the piece moved from is not there, or there is a castle
+            assert!(self.piece_at_sq(from) == Piece::None || undo_move.is_castle());
 
-            self.remove_piece_c(piece_on, to);
-            self.put_piece_c(Piece::make_lossy(us, PieceType::P), to);
-            piece_on = Piece::make_lossy(us, PieceType::P);
-        }
+            if undo_move.is_promo() {
+                assert_eq!(piece_on.type_of(), undo_move.promo_piece());
 
-        if undo_move.is_castle() {
-            self.remove_castling(us, from, to);
-        } else {
-            self.move_piece_c(piece_on, to, from);
-            let cap_piece = self.state.captured_piece;
-            if !cap_piece.is_none() {
-                let mut cap_sq: SQ = to;
-                if undo_move.is_en_passant() {
-                    match us {
-                        Player::White => cap_sq -= SQ(8),
-                        Player::Black => cap_sq += SQ(8),
-                    };
+                self.remove_piece_c(piece_on, to);
+                self.put_piece_c(Piece::make_lossy(us, PieceType::P), to);
+                piece_on = Piece::make_lossy(us, PieceType::P);
+            }
+
+            if undo_move.is_castle() {
+                self.remove_castling(us, from, to);
+            } else {
+                self.move_piece_c(piece_on, to, from);
+                let cap_piece = self.state.captured_piece;
+                if !cap_piece.is_none() {
+                    let mut cap_sq: SQ = to;
+                    if undo_move.is_en_passant() {
+                        match us {
+                            Player::White => cap_sq -= SQ(8),
+                            Player::Black => cap_sq += SQ(8),
+                        };
+                    }
+                    self.put_piece_c(Piece::make_lossy(!us, cap_piece), cap_sq);
                 }
-                self.put_piece_c(Piece::make_lossy(!us, cap_piece), cap_sq);
             }
         }
-        self.state = self.state.get_prev().unwrap();
+        self.state.pop();
         self.half_moves -= 1;
         self.depth -= 1;
-        self.zobrist_history.pop();
 
         #[cfg(debug_assertions)]
         self.is_okay().unwrap();
@@ -1015,18 +1455,16 @@ impl Board {
         let mut zob: u64 = self.state.zobrist ^ z_side();
 
         self.depth += 1;
-        // New Arc for the board to have by making a partial clone of the current state
-        let mut next_arc_state = UniqueArc::new(self.state.partial_clone());
+        // New state for the board to push by making a partial clone of the current state
+        let mut next_state = self.state.partial_clone();
 
         {
-            let new_state: &mut BoardState = &mut next_arc_state;
+            let new_state: &mut BoardState = &mut next_state;
 
             new_state.prev_move = BitMove::null();
             new_state.rule_50 += 1;
             new_state.ply += 1;
 
-            new_state.prev = Some(Arc::clone(&self.state));
-
             if self.state.ep_square != NO_SQ {
                 // Only XOR out the EP hash if it was included in the zobrist
                 let ep_owner = !self.turn;
@@ -1045,7 +1483,7 @@ impl Board {
             // Set the checking information
             new_state.set_check_info(self);
         }
-        self.state = next_arc_state.shareable();
+        self.state.push(next_state);
 
         #[cfg(debug_assertions)]
         self.is_okay().unwrap();
@@ -1078,9 +1516,10 @@ impl Board {
     /// assert_eq!(chessboard.fen(), board_clone.fen());
     /// ```
     pub unsafe fn undo_null_move(&mut self) {
+        assert!(self.state.has_prev());
         assert!(self.state.prev_move.is_null());
         self.turn = self.turn.other_player();
-        self.state = self.state.get_prev().unwrap();
+        self.state.pop();
     }
 
     /// Get a List of legal `BitMove`s for the player whose turn it is to move.
@@ -1088,6 +1527,11 @@ impl Board {
     /// This method already takes into account if the Board is currently in check, and will return
     /// legal moves only.
     ///
+    /// # Panics
+    ///
+    /// Panics if a Crazyhouse position has more than `MAX_MOVES` moves. Use
+    /// `Board::generate_crazyhouse_moves()` for Crazyhouse boards instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -1102,31 +1546,165 @@ impl Board {
         MoveGen::generate::<Legal, AllGenType>(self)
     }
 
-    /// Get a List of legal `BitMove`s (alongside a score) for the player whose turn it is to move.
+    /// Get a List of legal `BitMove`s for the player whose turn it is to move, with room for the
+    /// `MAX_CRAZYHOUSE_MOVES` moves a Crazyhouse position may have.
     ///
-    /// This method already takes into account if the Board is currently in check, and will return
-    /// legal moves only. The `ScoringMoveList` that is returned will have a value of zero for each
-    /// move.
-    pub fn generate_scoring_moves(&self) -> ScoringMoveList {
-        MoveGen::generate_scoring::<Legal, AllGenType>(self)
+    /// Works for boards of any variant, but `Board::generate_moves()` uses a smaller list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    /// use pleco::board::variant::Variant;
+    ///
+    /// let fen = "k7/8/8/8/8/8/8/K6Q[QRBNP] w - - 0 1";
+    /// let chessboard = Board::from_fen_variant(fen, Variant::Crazyhouse).unwrap();
+    /// assert_eq!(chessboard.generate_crazyhouse_moves().len(), 315);
+    /// ```
+    pub fn generate_crazyhouse_moves(&self) -> CrazyhouseMoveList {
+        MoveGen::generate_crazyhouse::<Legal, AllGenType>(self)
     }
 
-    /// Get a List of all PseudoLegal `BitMove`s for the player whose turn it is to move.
-    /// Works exactly the same as `Board::generate_moves()`, but doesn't guarantee that all
-    /// the moves are legal for the current position. Moves need to be checked with a
-    /// `Board::legal_move(move)` in order to be certain of a legal move.
-    pub fn generate_pseudolegal_moves(&self) -> MoveList {
-        MoveGen::generate::<PseudoLegal, AllGenType>(self)
+    // Calls `f` with the legal moves, in a list with room for them in any variant
+    pub(crate) fn with_legal_moves<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&[BitMove]) -> R,
+    {
+        if self.variant == Variant::Crazyhouse {
+            f(&self.generate_crazyhouse_moves())
+        } else {
+            f(&self.generate_moves())
+        }
     }
 
-    /// Get a List of legal `BitMove`s for the player whose turn it is to move and of a certain type.
-    ///
-    /// This method already takes into account if the Board is currently in check, and will return
-    /// legal moves only. If a non-ALL `GenTypes` is supplied, only a subset of the total moves will be given.
+    /// Generates the legal `BitMove`s for the player whose turn it is to move into an existing
+    /// list, replacing its contents. Works exactly the same as `Board::generate_moves()`, but
+    /// lets a single list be reused across positions.
     ///
-    /// # Panics
+    /// # Safety
     ///
-    /// Panics if given `GenTypes::QuietChecks` while the current board is in check
+    /// The moves are written without checking the list's capacity, so the list must have room
+    /// for `MAX_MOVES` moves, as `MoveList` and `ScoringMoveList` do. For a Crazyhouse board, it
+    /// must have room for `MAX_CRAZYHOUSE_MOVES` moves, as lists created with
+    /// `with_crazyhouse_capacity()` do.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,MoveList};
+    ///
+    /// let chessboard = Board::start_pos();
+    /// let mut moves = MoveList::default();
+    /// unsafe { chessboard.generate_moves_into(&mut moves) };
+    ///
+    /// assert_eq!(moves.len(), 20);
+    /// ```
+    pub unsafe fn generate_moves_into<MP: MVPushable>(&self, movelist: &mut MP)
+    where
+        <MP as Index<usize>>::Output: Sized,
+    {
+        MoveGen::generate_into::<Legal, AllGenType, MP>(self, movelist)
+    }
+
+    /// Get a List of the legal `BitMove`s of the piece on a square, for the player whose turn it
+    /// is to move. The list is empty if the square doesn't hold one of the player's pieces.
+    ///
+    /// Only the moves of the piece on `sq` are generated. A castling move is included for the
+    /// king's square.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,SQ};
+    ///
+    /// let chessboard = Board::start_pos();
+    /// assert_eq!(chessboard.generate_moves_from(SQ::E2).len(), 2);
+    /// assert_eq!(chessboard.generate_moves_from(SQ::E1).len(), 0);
+    /// ```
+    pub fn generate_moves_from(&self, sq: SQ) -> MoveList {
+        MoveGen::generate_masked::<Legal, AllGenType>(self, sq.to_bb(), BitBoard::ALL)
+    }
+
+    /// Get a List of the legal `BitMove`s onto a square, for the player whose turn it is to move.
+    ///
+    /// As with `BitMove::get_dest()`, a castling move is included for the square of the
+    /// castling rook rather than the square the king lands on. Crazyhouse drops onto `sq` are
+    /// included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,SQ};
+    ///
+    /// let chessboard = Board::start_pos();
+    /// assert_eq!(chessboard.generate_moves_to(SQ::F3).len(), 2);
+    /// ```
+    pub fn generate_moves_to(&self, sq: SQ) -> MoveList {
+        MoveGen::generate_masked::<Legal, AllGenType>(self, BitBoard::ALL, sq.to_bb())
+    }
+
+    /// Get a List of the legal `BitMove`s of every piece of a certain type, for the player whose
+    /// turn it is to move. Crazyhouse drops aren't included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,PieceType};
+    ///
+    /// let chessboard = Board::start_pos();
+    /// assert_eq!(chessboard.generate_moves_of_piece(PieceType::N).len(), 4);
+    /// ```
+    pub fn generate_moves_of_piece(&self, piece: PieceType) -> MoveList {
+        let pieces = self.piece_bb(self.turn, piece);
+        MoveGen::generate_masked::<Legal, AllGenType>(self, pieces, BitBoard::ALL)
+    }
+
+    /// Returns an `Iterator` generating the legal `BitMove`s for the player whose turn it is to
+    /// move in stages, captures first and quiet moves after. A stage is only generated once the
+    /// moves before it have been taken, so this is cheaper than `Board::generate_moves()` when
+    /// only the first few moves are needed.
+    ///
+    /// See [`StagedMoves`] for generating moves in stages while applying them to the board.
+    ///
+    /// [`StagedMoves`]: movegen/struct.StagedMoves.html
+    pub fn staged_moves<'a>(&'a self) -> StagedMovesIter<'a> {
+        StagedMoves::new(self, BitMove::null()).iter(self)
+    }
+
+    /// Get a List of legal `BitMove`s (alongside a score) for the player whose turn it is to move.
+    ///
+    /// This method already takes into account if the Board is currently in check, and will return
+    /// legal moves only. The `ScoringMoveList` that is returned will have a value of zero for each
+    /// move.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a Crazyhouse position has more than `MAX_MOVES` moves.
+    pub fn generate_scoring_moves(&self) -> ScoringMoveList {
+        MoveGen::generate_scoring::<Legal, AllGenType>(self)
+    }
+
+    /// Get a List of all PseudoLegal `BitMove`s for the player whose turn it is to move.
+    /// Works exactly the same as `Board::generate_moves()`, but doesn't guarantee that all
+    /// the moves are legal for the current position. Moves need to be checked with a
+    /// `Board::legal_move(move)` in order to be certain of a legal move.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a Crazyhouse position has more than `MAX_MOVES` moves.
+    pub fn generate_pseudolegal_moves(&self) -> MoveList {
+        MoveGen::generate::<PseudoLegal, AllGenType>(self)
+    }
+
+    /// Get a List of legal `BitMove`s for the player whose turn it is to move and of a certain type.
+    ///
+    /// This method already takes into account if the Board is currently in check, and will return
+    /// legal moves only. If a non-ALL `GenTypes` is supplied, only a subset of the total moves will be given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if given `GenTypes::QuietChecks` while the current board is in check, or if a
+    /// Crazyhouse position has more than `MAX_MOVES` moves of the type.
     ///
     /// # Examples
     ///
@@ -1160,7 +1738,8 @@ impl Board {
     ///
     /// # Panics
     ///
-    /// Panics if given `GenTypes::QuietChecks` while the current board is in check
+    /// Panics if given `GenTypes::QuietChecks` while the current board is in check, or if a
+    /// Crazyhouse position has more than `MAX_MOVES` moves of the type.
     pub fn generate_pseudolegal_moves_of_type(&self, gen_type: GenTypes) -> MoveList {
         match gen_type {
             GenTypes::All => MoveGen::generate::<PseudoLegal, AllGenType>(self),
@@ -1265,18 +1844,24 @@ impl Board {
         r_src: &mut SQ,
         r_dst: &mut SQ,
     ) {
-        let king_side: bool = k_src < *to_r_orig;
+        let side = if k_src < *to_r_orig {
+            CastleType::KingSide
+        } else {
+            CastleType::QueenSide
+        };
 
         *r_src = *to_r_orig;
-        if king_side {
-            *to_r_orig = player.relative_square(SQ(6));
-            *r_dst = player.relative_square(SQ(5));
-        } else {
-            *to_r_orig = player.relative_square(SQ(2));
-            *r_dst = player.relative_square(SQ(3));
-        }
-        self.move_piece_c(Piece::make_lossy(player, PieceType::K), k_src, *to_r_orig);
-        self.move_piece_c(Piece::make_lossy(player, PieceType::R), *r_src, *r_dst);
+        let (k_dst, rook_dst) = CastlingSquares::destinations(player, side);
+        *to_r_orig = k_dst;
+        *r_dst = rook_dst;
+
+        // In Chess960 the king or rook may not move, or land where the other started
+        let king = Piece::make_lossy(player, PieceType::K);
+        let rook = Piece::make_lossy(player, PieceType::R);
+        self.remove_piece_c(king, k_src);
+        self.remove_piece_c(rook, *r_src);
+        self.put_piece_c(king, k_dst);
+        self.put_piece_c(rook, rook_dst);
     }
 
     /// Helper function to remove a Castling for a given player.
@@ -1287,16 +1872,19 @@ impl Board {
     ///
     /// Assumes the last move played was a castle for the given player.
     fn remove_castling(&mut self, player: Player, k_src: SQ, r_src: SQ) {
-        let k_dst: SQ = self.king_sq(player);
-        let king_side: bool = k_src < r_src;
-        let r_dst: SQ = if king_side {
-            player.relative_square(SQ(5))
+        let side = if k_src < r_src {
+            CastleType::KingSide
         } else {
-            player.relative_square(SQ(3))
+            CastleType::QueenSide
         };
-
-        self.move_piece_c(Piece::make_lossy(player, PieceType::K), k_dst, k_src);
-        self.move_piece_c(Piece::make_lossy(player, PieceType::R), r_dst, r_src);
+        let (k_dst, r_dst) = CastlingSquares::destinations(player, side);
+
+        let king = Piece::make_lossy(player, PieceType::K);
+        let rook = Piece::make_lossy(player, PieceType::R);
+        self.remove_piece_c(king, k_dst);
+        self.remove_piece_c(rook, r_dst);
+        self.put_piece_c(king, k_src);
+        self.put_piece_c(rook, r_src);
     }
 
     /// Outputs the Blockers of a given square.
@@ -1712,14 +2300,49 @@ impl Board {
     /// having the rook and king be in the correct square.
     #[inline]
     pub fn castle_impeded(&self, castle_type: CastleType) -> bool {
-        let path: BitBoard = BitBoard(CASTLING_PATH[self.turn as usize][castle_type as usize]);
+        let path: BitBoard = self.castling_squares.path(self.turn, castle_type);
         (path & self.occupied()).is_not_empty()
     }
 
     /// Square of the Rook that is involved with the current player's castle.
     #[inline]
     pub fn castling_rook_square(&self, castle_type: CastleType) -> SQ {
-        SQ(CASTLING_ROOK_START[self.turn as usize][castle_type as usize])
+        self.castling_squares.rook_start(self.turn, castle_type)
+    }
+
+    /// Returns the starting squares of the kings and rooks involved in castling.
+    #[inline(always)]
+    pub fn castling_squares(&self) -> &CastlingSquares {
+        &self.castling_squares
+    }
+
+    /// Returns if the board is a Chess960 (Fischer Random Chess) game.
+    ///
+    /// This is set automatically by [`Board::from_fen`] when the castling rights can't
+    /// happen in standard chess, or are given in Shredder-FEN notation. A Chess960 board
+    /// writes X-FEN castling rights through [`Board::fen`], and reads and writes castling
+    /// UCI moves as the king capturing its own rook.
+    ///
+    /// [`Board::from_fen`]: #method.from_fen
+    /// [`Board::fen`]: #method.fen
+    #[inline(always)]
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Sets whether the board is a Chess960 (Fischer Random Chess) game.
+    ///
+    /// This only changes the notation used for castling, as the castling squares are decided
+    /// when the board is created. A board whose castling squares aren't those of standard chess
+    /// always stays a Chess960 board.
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960 || !self.castling_squares.is_standard();
+    }
+
+    /// Returns the variant of chess being played.
+    #[inline(always)]
+    pub fn variant(&self) -> Variant {
+        self.variant
     }
 
     /// Return the last move played, if any.
@@ -1770,12 +2393,100 @@ impl Board {
     ///
     /// This method can be computationally expensive, do not use outside of Engines.
     pub fn checkmate(&self) -> bool {
-        self.in_check() && self.generate_moves().is_empty()
+        self.in_check() && self.with_legal_moves(|moves| moves.is_empty())
     }
 
     /// Return if the threefold repetition rule has been met.
     pub fn threefold_repetition(&self) -> bool {
-        self.threefold_repetition
+        self.state.repetitions() >= 2
+    }
+
+    /// Returns if the side to move can repeat an earlier position with a single reversible
+    /// move, or if an earlier position had a move reaching the current one directly. This
+    /// detects repetitions before they happen, so a search can score them as draws sooner.
+    ///
+    /// `ply` is the number of moves since the root of a search. Repetitions of positions
+    /// reached within the search count straight away, while those of positions at or before
+    /// the root require that position to have repeated already, as with
+    /// [`Board::threefold_repetition`].
+    ///
+    /// Only moves made since the last capture, pawn move, or null move are considered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    ///
+    /// let mut board = Board::start_pos();
+    /// for mov in ["g1f3", "g8f6", "f3g1"].iter() {
+    ///     assert!(!board.has_game_cycle(4));
+    ///     board.apply_uci_move(mov);
+    /// }
+    ///
+    /// // Black can return to the starting position with Ng8
+    /// assert!(board.has_game_cycle(4));
+    /// ```
+    ///
+    /// [`Board::threefold_repetition`]: struct.Board.html#method.threefold_repetition
+    pub fn has_game_cycle(&self, ply: u16) -> bool {
+        // Captures are compulsory in Antichess, so the move back may not be legal
+        if self.variant == Variant::Antichess {
+            return false;
+        }
+
+        let history = self.state.history();
+        let current = history.len() - 1;
+        let mut end = (self.state.rule_50.max(0) as usize).min(current);
+        if let Some(null) = history[current - end..]
+            .iter()
+            .rev()
+            .position(|state| state.prev_move.is_null())
+        {
+            end = end.min(null);
+        }
+        if end < 3 {
+            return false;
+        }
+
+        let zobrist = self.state.zobrist;
+        let occupied = self.occupied();
+        for i in (3..=end).step_by(2) {
+            let index = current - i;
+            let state = &history[index];
+            let mov = match cuckoo_move(zobrist ^ state.zobrist) {
+                Some(mov) => mov,
+                None => continue,
+            };
+            let (s1, s2) = (mov.get_src(), mov.get_dest());
+            if (between_bb(s1, s2) & occupied).is_not_empty() {
+                continue;
+            }
+            if ply as usize > i {
+                return true;
+            }
+
+            // At or before the root, the move must belong to the side to move, and lead to a
+            // position that has already repeated
+            let sq = if self.piece_at_sq(s1) == Piece::None {
+                s2
+            } else {
+                s1
+            };
+            if self.piece_at_sq(sq).player_lossy() != self.turn {
+                continue;
+            }
+            let reversible = (state.rule_50.max(0) as usize).min(index);
+            if history[index - reversible..index]
+                .iter()
+                .rev()
+                .skip(1)
+                .step_by(2)
+                .any(|earlier| earlier.zobrist == state.zobrist)
+            {
+                return true;
+            }
+        }
+        false
     }
 
     pub fn fifty_move_rule(&self) -> bool {
@@ -1788,7 +2499,7 @@ impl Board {
     pub fn stalemate(&self) -> bool {
         !self.in_check()
             && (self.fifty_move_rule()
-                || self.generate_moves().is_empty()
+                || self.with_legal_moves(|moves| moves.is_empty())
                 || self.threefold_repetition())
     }
 
@@ -1856,6 +2567,29 @@ impl Board {
     ///
     /// Assumes the move is legal for the current board.
     pub fn legal_move(&self, m: BitMove) -> bool {
+        if self.variant != Variant::Standard {
+            return self.variant_legal_move(m);
+        }
+        self.king_safe_move(m)
+    }
+
+    /// Returns if a pseudo-legal move of a variant doesn't leave the moving player's king in
+    /// check, which also allows for drops and for players without a king.
+    fn variant_king_safe_move(&self, m: BitMove) -> bool {
+        // Drops only add a piece to the board, and are only generated to block a check
+        if m.is_drop() {
+            return true;
+        }
+        // Nothing to leave in check without a king, as for White in Horde
+        if self.piece_bb(self.turn, PieceType::K).is_empty() {
+            return m.get_src() != m.get_dest() && self.piece_at_sq(m.get_src()) != Piece::None;
+        }
+        self.king_safe_move(m)
+    }
+
+    /// Returns if a pseudo-legal move doesn't leave the moving player's king in check.
+    #[inline(always)]
+    fn king_safe_move(&self, m: BitMove) -> bool {
         if m.get_src() == m.get_dest() {
             return false;
         }
@@ -1903,6 +2637,10 @@ impl Board {
     /// a move may possibly legal. To guarantee a move is completely legal for the position,
     /// use `Board::pseudo_legal_move()` followed by a `Board::legal_move()`.
     pub fn pseudo_legal_move(&self, m: BitMove) -> bool {
+        if m.is_drop() {
+            return self.pseudo_legal_drop(m);
+        }
+
         let us = self.turn;
         let them = !us;
         let from: SQ = m.get_src();
@@ -1920,6 +2658,9 @@ impl Board {
 
         // Use a slower but simpler function for uncommon cases
         if m.move_type() != MoveType::Normal {
+            if self.variant == Variant::Crazyhouse {
+                return MoveGen::generate_crazyhouse::<PseudoLegal, AllGenType>(self).contains(&m);
+            }
             return self.generate_pseudolegal_moves().contains(&m);
         }
 
@@ -1950,7 +2691,9 @@ impl Board {
                     && m.is_quiet_move()) // not a single push
                 && !(from.0 as i8 + 2 * us.pawn_push() == to.0 as i8
                     && m.is_double_push().0
-                    && from.rank() == us.relative_rank(Rank::R2)
+                    && (from.rank() == us.relative_rank(Rank::R2)
+                        || (self.variant == Variant::Horde
+                            && from.rank() == us.relative_rank(Rank::R1)))
                     && self.empty(to)
                     && self.empty(SQ((to.0 as i8 - us.pawn_push()) as u8)))
             // Not a double push
@@ -1972,7 +2715,8 @@ impl Board {
             }
         }
 
-        if self.in_check() {
+        // An Atomic capture may also evade a check by exploding the enemy king
+        if self.in_check() && self.variant != Variant::Atomic {
             if piece != PieceType::K {
                 if self.checkers().more_than_one() {
                     return false;
@@ -1995,6 +2739,34 @@ impl Board {
         true
     }
 
+    /// Returns if a drop is pseudo-legal. As a drop can't uncover a check, this is the same as
+    /// the drop being legal.
+    fn pseudo_legal_drop(&self, m: BitMove) -> bool {
+        let piece = m.drop_piece();
+        let to: SQ = m.get_dest();
+        if self.variant != Variant::Crazyhouse
+            || piece == PieceType::None
+            || self.pocket(self.turn, piece) == 0
+            || !self.empty(to)
+        {
+            return false;
+        }
+
+        if piece == PieceType::P
+            && (to.to_bb() & (BitBoard::RANK_1 | BitBoard::RANK_8)).is_not_empty()
+        {
+            return false;
+        }
+
+        // A drop can only evade a check by blocking it
+        if self.in_check() {
+            return !self.checkers().more_than_one()
+                && (between_bb(self.checkers().to_sq(), self.king_sq(self.turn)) & to.to_bb())
+                    .is_not_empty();
+        }
+        true
+    }
+
     /// Returns if the board contains only two bishops, one for each color, and each being
     /// on different squares.
     #[inline(always)]
@@ -2013,7 +2785,8 @@ impl Board {
     /// Checks if a move is an advanced pawn push, meaning it passes into enemy territory.
     #[inline(always)]
     pub fn advanced_pawn_push(&self, mov: BitMove) -> bool {
-        self.piece_at_sq(mov.get_src()).type_of() == PieceType::P
+        !mov.is_drop()
+            && self.piece_at_sq(mov.get_src()).type_of() == PieceType::P
             && self.turn().relative_rank_of_sq(mov.get_src()) > Rank::R4
     }
 
@@ -2023,7 +2796,7 @@ impl Board {
     /// data, rather than relying on the information encoded in the move.
     #[inline(always)]
     pub fn is_capture(&self, mov: BitMove) -> bool {
-        assert_ne!(mov.get_dest_u8(), mov.get_src_u8());
+        assert!(mov.is_okay());
         (!self.empty(mov.get_dest()) && mov.move_type() != MoveType::Castle)
             || mov.move_type() == MoveType::EnPassant
     }
@@ -2034,9 +2807,9 @@ impl Board {
     /// move to the `Board`s data, rather than relying on the information encoded in the move.
     #[inline(always)]
     pub fn is_capture_or_promotion(&self, mov: BitMove) -> bool {
-        assert_ne!(mov.get_dest_u8(), mov.get_src_u8());
+        assert!(mov.is_okay());
         if mov.move_type() != MoveType::Normal {
-            mov.move_type() != MoveType::Castle
+            mov.move_type() != MoveType::Castle && mov.move_type() != MoveType::Drop
         } else {
             !self.empty(mov.get_dest())
         }
@@ -2055,6 +2828,12 @@ impl Board {
         let dst_bb: BitBoard = dst.to_bb();
         let us: Player = self.turn();
         let them: Player = !us;
+
+        if self.variant != Variant::Standard {
+            if let Some(gives_check) = self.variant_gives_check(m) {
+                return gives_check;
+            }
+        }
         let opp_king_sq: SQ = self.king_sq(them);
 
         // Stupidity Checks
@@ -2076,7 +2855,7 @@ impl Board {
         }
 
         match m.move_type() {
-            MoveType::Normal => false, // Nothing to check here
+            MoveType::Normal | MoveType::Drop => false, // Nothing to check here
             MoveType::Promotion => {
                 // check if the Promo Piece attacks king
                 let attacks_bb = match m.promo_piece() {
@@ -2233,13 +3012,163 @@ impl Board {
                 }
                 break;
             }
-            if next_victim == PieceType::K {}
             assert_ne!(next_victim, PieceType::K);
         }
 
         us != stm
     }
 
+    /// Returns the Static Exchange Evaluation of a move, the material won or lost by the
+    /// player making it once both players have made every capture on its destination square
+    /// that gains them material.
+    ///
+    /// The least valuable attacker always captures next, attackers behind another piece join
+    /// in once it has captured, and pinned pieces only attack once a pinner has been captured.
+    /// Pawns capturing onto the last rank are counted as promoting to a queen. Castles have a
+    /// value of zero, as do moves that are invalid for the current board.
+    ///
+    /// This is slower than `see_ge`, and only agrees with it for normal moves to squares off the
+    /// first and last ranks. `see_ge` gives castles, en passant captures, promotions and drops a
+    /// value of zero, and doesn't count promotions made while recapturing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,BitMove,SQ};
+    ///
+    /// // The knight takes a defended pawn, and is taken in return
+    /// let board = Board::from_fen("4k3/8/3p4/4p3/8/5N2/8/4K3 w - - 0 1").unwrap();
+    /// let mov = BitMove::make_capture(SQ::F3, SQ::E5);
+    /// assert!(board.see(mov) < 0);
+    /// assert!(!board.see_ge(mov, 0));
+    /// ```
+    pub fn see(&self, mov: BitMove) -> Value {
+        if mov.is_castle() {
+            return 0;
+        }
+        match self.see_start(mov) {
+            Some((us, gain, on_square, occupied)) => {
+                gain - self.see_exchange(mov.get_dest(), !us, on_square, occupied)
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns the material a player can win through captures on a square, with both players
+    /// capturing with their least valuable attacker for as long as it gains them material.
+    ///
+    /// The player isn't forced to start capturing, so the value is never negative. The value
+    /// is zero if the square isn't occupied by a piece of the other player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,Player,SQ};
+    ///
+    /// // The knight on d5 is attacked by a pawn and defended by a pawn
+    /// let board = Board::from_fen("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1").unwrap();
+    /// assert!(board.see_square(SQ::D5, Player::White) > 0);
+    /// assert_eq!(board.see_square(SQ::E4, Player::White), 0);
+    /// ```
+    pub fn see_square(&self, sq: SQ, player: Player) -> Value {
+        let piece = self.piece_at_sq(sq);
+        if piece == Piece::None || piece.player_lossy() == player {
+            return 0;
+        }
+        self.see_exchange(sq, player, piece.type_of(), self.occupied())
+    }
+
+    // Returns the player making a move, the material it wins before any recapture, the piece it
+    // leaves on its destination square and the occupancy of the board after it. Returns `None`
+    // if there's no piece to move.
+    fn see_start(&self, mov: BitMove) -> Option<(Player, Value, PieceType, BitBoard)> {
+        let to = mov.get_dest();
+        if mov.is_drop() {
+            return Some((self.turn, 0, mov.drop_piece(), self.occupied() | to.to_bb()));
+        }
+
+        let from = mov.get_src();
+        let piece = self.piece_at_sq(from);
+        if piece == Piece::None {
+            return None;
+        }
+        let us = piece.player_lossy();
+        let mut occupied = (self.occupied() ^ from.to_bb()) | to.to_bb();
+        let mut gain = piece_value(self.piece_at_sq(to), false);
+        let mut on_square = piece.type_of();
+
+        if mov.is_en_passant() {
+            let cap_sq = match us {
+                Player::White => to - SQ(8),
+                Player::Black => to + SQ(8),
+            };
+            occupied ^= cap_sq.to_bb();
+            gain = piecetype_value(PieceType::P, false);
+        }
+        if mov.is_promo() {
+            on_square = mov.promo_piece();
+            gain += piecetype_value(on_square, false) - piecetype_value(PieceType::P, false);
+        }
+        Some((us, gain, on_square, occupied))
+    }
+
+    // Returns the material `stm` can win by starting to capture on `to`, with `on_square` being
+    // the piece to capture first and `occupied` the pieces still on the board.
+    fn see_exchange(
+        &self,
+        to: SQ,
+        mut stm: Player,
+        mut on_square: PieceType,
+        mut occupied: BitBoard,
+    ) -> Value {
+        let promotion = to.rank() == Rank::R1 || to.rank() == Rank::R8;
+        // As in `see_ge`, the square being captured on is left out of the occupancy, so a pinner
+        // standing on it counts as gone
+        occupied &= !to.to_bb();
+        let mut attackers: BitBoard = self.attackers_to(to, occupied) & occupied;
+
+        // The value of each capture made, alternating between the players
+        let mut captures: [Value; 32] = [0; 32];
+        let mut count = 0;
+
+        loop {
+            let mut stm_attackers = attackers & self.get_occupied_player(stm);
+            // Same as in `see_ge`, pinned pieces only attack once a pinner is gone
+            if (self.state.pinners_king[stm as usize] & !occupied).is_empty() {
+                stm_attackers &= !self.state.blockers_king[stm as usize];
+            }
+            if stm_attackers.is_empty() {
+                break;
+            }
+
+            let attacker =
+                self.min_attacker::<PawnType>(to, stm_attackers, &mut occupied, &mut attackers);
+
+            // The king can't capture a defended piece
+            if attacker == PieceType::K
+                && (attackers & self.get_occupied_player(!stm)).is_not_empty()
+            {
+                break;
+            }
+
+            captures[count] = piecetype_value(on_square, false);
+            on_square = attacker;
+            if promotion && attacker == PieceType::P {
+                captures[count] +=
+                    piecetype_value(PieceType::Q, false) - piecetype_value(PieceType::P, false);
+                on_square = PieceType::Q;
+            }
+            count += 1;
+            stm = !stm;
+        }
+
+        // Each player only captures if it gains them material
+        captures[..count]
+            .iter()
+            .rev()
+            .fold(0, |value, &capture| max(0, capture - value))
+    }
+
     fn min_attacker<P>(
         &self,
         to: SQ,
@@ -2300,6 +3229,9 @@ impl Board {
     /// Assumes the move is legal for the current board.
     #[inline(always)]
     pub fn moved_piece(&self, m: BitMove) -> Piece {
+        if m.is_drop() {
+            return Piece::make_lossy(self.turn, m.drop_piece());
+        }
         let src = m.get_src();
         self.piece_at_sq(src)
     }
@@ -2329,6 +3261,17 @@ impl Board {
     pub fn key_after(&self, m: BitMove) -> u64 {
         let src = m.get_src();
         let dst = m.get_dest();
+
+        if m.is_drop() {
+            let piece = m.drop_piece();
+            let count = self.pocket(self.turn, piece);
+            return self.zobrist()
+                ^ z_side()
+                ^ z_square(dst, Piece::make_lossy(self.turn, piece))
+                ^ z_pocket(self.turn, piece, count)
+                ^ z_pocket(self.turn, piece, count - 1);
+        }
+
         let piece_moved = self.piece_locations.piece_at(src);
         let piece_captured = self.piece_locations.piece_at(dst);
 
@@ -2415,13 +3358,101 @@ impl Board {
     }
 }
 
-// TODO: Error Propagation
-
 /// Errors concerning the current `Board` position.
+#[derive(Clone, PartialEq, Eq)]
 pub enum BoardError {
-    IncorrectKingNum { player: Player, num: u8 },
-    IncorrectKingSQ { player: Player, sq: SQ },
-    BadEPSquare { sq: SQ },
+    IncorrectKingNum {
+        player: Player,
+        num: u8,
+    },
+    IncorrectKingSQ {
+        player: Player,
+        sq: SQ,
+    },
+    BadEPSquare {
+        sq: SQ,
+    },
+    FullMovesOutOfRange {
+        full_moves: u16,
+    },
+    OpponentInCheck {
+        player: Player,
+    },
+    TooManyCheckers {
+        num: u8,
+    },
+    TooManyPawns {
+        player: Player,
+        num: u8,
+    },
+    TooManyPieces {
+        player: Player,
+        num: u8,
+    },
+    PawnOnBackRank {
+        sq: SQ,
+    },
+    CastlingWithoutKing {
+        player: Player,
+        side: CastleType,
+    },
+    CastlingWithoutRook {
+        player: Player,
+        side: CastleType,
+    },
+    BitBoardsOverlap {
+        sq: SQ,
+    },
+    BitBoardsDisagree {
+        sq: SQ,
+    },
+    PieceLocationMismatch {
+        sq: SQ,
+    },
+    IncorrectPieceCount {
+        player: Player,
+        piece: PieceType,
+        num: u8,
+        expected: u8,
+    },
+    IncorrectZobrist {
+        key: u64,
+        expected: u64,
+    },
+    IncorrectPawnKey {
+        key: u64,
+        expected: u64,
+    },
+    IncorrectMaterialKey {
+        key: u64,
+        expected: u64,
+    },
+    IncorrectPSQ {
+        psq: Score,
+        expected: Score,
+    },
+    IncorrectNonPawnMaterial {
+        player: Player,
+        value: Value,
+        expected: Value,
+    },
+    IncorrectCheckers {
+        checkers: BitBoard,
+        expected: BitBoard,
+    },
+    IncorrectBlockers {
+        player: Player,
+        blockers: BitBoard,
+        expected: BitBoard,
+    },
+    IncorrectPinners {
+        player: Player,
+        pinners: BitBoard,
+        expected: BitBoard,
+    },
+    IncorrectCheckSquares {
+        piece: PieceType,
+    },
 }
 
 impl fmt::Debug for BoardError {
@@ -2436,6 +3467,108 @@ impl fmt::Debug for BoardError {
                 player, sq
             ),
             BoardError::BadEPSquare { sq } => writeln!(f, "Bad En-passant Square: {}", sq),
+            BoardError::FullMovesOutOfRange { full_moves } => {
+                writeln!(f, "full move number out of range: {}", full_moves)
+            }
+            BoardError::OpponentInCheck { player } => writeln!(
+                f,
+                "{} is in check, but it is not their turn to move",
+                player
+            ),
+            BoardError::TooManyCheckers { num } => {
+                writeln!(f, "too many pieces giving check: {}", num)
+            }
+            BoardError::TooManyPawns { player, num } => {
+                writeln!(f, "too many pawns for {}: {}", player, num)
+            }
+            BoardError::TooManyPieces { player, num } => {
+                writeln!(f, "too many pieces for {}: {}", player, num)
+            }
+            BoardError::PawnOnBackRank { sq } => writeln!(f, "pawn on back rank: {}", sq),
+            BoardError::CastlingWithoutKing { player, side } => writeln!(
+                f,
+                "{} has {:?} castling rights, but no king on its back rank",
+                player, side
+            ),
+            BoardError::CastlingWithoutRook { player, side } => writeln!(
+                f,
+                "{} has {:?} castling rights, but no rook to castle with",
+                player, side
+            ),
+            BoardError::BitBoardsOverlap { sq } => {
+                writeln!(f, "more than one piece in the bitboards at: {}", sq)
+            }
+            BoardError::BitBoardsDisagree { sq } => writeln!(
+                f,
+                "the occupied, player and piece bitboards disagree at: {}",
+                sq
+            ),
+            BoardError::PieceLocationMismatch { sq } => {
+                writeln!(f, "the piece locations and bitboards disagree at: {}", sq)
+            }
+            BoardError::IncorrectPieceCount {
+                player,
+                piece,
+                num,
+                expected,
+            } => writeln!(
+                f,
+                "incorrect count of {} {}: {}, expected {}",
+                player, piece, num, expected
+            ),
+            BoardError::IncorrectZobrist { key, expected } => {
+                writeln!(
+                    f,
+                    "incorrect zobrist key: {:x}, expected {:x}",
+                    key, expected
+                )
+            }
+            BoardError::IncorrectPawnKey { key, expected } => {
+                writeln!(f, "incorrect pawn key: {:x}, expected {:x}", key, expected)
+            }
+            BoardError::IncorrectMaterialKey { key, expected } => writeln!(
+                f,
+                "incorrect material key: {:x}, expected {:x}",
+                key, expected
+            ),
+            BoardError::IncorrectPSQ { psq, expected } => {
+                writeln!(f, "incorrect psq score: {}, expected {}", psq, expected)
+            }
+            BoardError::IncorrectNonPawnMaterial {
+                player,
+                value,
+                expected,
+            } => writeln!(
+                f,
+                "incorrect non-pawn material for {}: {}, expected {}",
+                player, value, expected
+            ),
+            BoardError::IncorrectCheckers { checkers, expected } => writeln!(
+                f,
+                "incorrect checkers: {:x}, expected {:x}",
+                checkers.0, expected.0
+            ),
+            BoardError::IncorrectBlockers {
+                player,
+                blockers,
+                expected,
+            } => writeln!(
+                f,
+                "incorrect blockers of {}'s king: {:x}, expected {:x}",
+                player, blockers.0, expected.0
+            ),
+            BoardError::IncorrectPinners {
+                player,
+                pinners,
+                expected,
+            } => writeln!(
+                f,
+                "incorrect pinners of {}'s king: {:x}, expected {:x}",
+                player, pinners.0, expected.0
+            ),
+            BoardError::IncorrectCheckSquares { piece } => {
+                writeln!(f, "incorrect check squares for {}", piece)
+            }
         }
     }
 }
@@ -2443,81 +3576,318 @@ impl fmt::Debug for BoardError {
 impl Board {
     /// Checks the basic status of the board, returning false if something is wrong.
     pub fn is_ok_quick(&self) -> bool {
-        self.piece_at_sq(self.king_sq(Player::White)).type_of() == PieceType::K
-            && self.piece_at_sq(self.king_sq(Player::Black)).type_of() == PieceType::K
+        if self.variant == Variant::Standard {
+            return self.piece_at_sq(self.king_sq(Player::White)).type_of() == PieceType::K
+                && self.piece_at_sq(self.king_sq(Player::Black)).type_of() == PieceType::K
+                && (self.state.ep_square == NO_SQ
+                    || self.turn.relative_rank_of_sq(self.state.ep_square) == Rank::R6);
+        }
+        let king_okay = |player: Player| {
+            let king = self.piece_bb(player, PieceType::K);
+            if king.is_empty() {
+                self.may_lack_king(player)
+            } else {
+                self.variant == Variant::Antichess
+                    || self.piece_at_sq(king.to_sq()).type_of() == PieceType::K
+            }
+        };
+        king_okay(Player::White)
+            && king_okay(Player::Black)
             && (self.state.ep_square == NO_SQ
                 || self.turn.relative_rank_of_sq(self.state.ep_square) == Rank::R6)
     }
 
     /// Checks if the current state of the Board is okay.
+    ///
+    /// Every piece of information the `Board` keeps is checked against the placement of the
+    /// pieces, with the `BoardState` being recomputed from scratch. This is expensive, and
+    /// meant for debugging.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first inconsistency found, checking in this order:
+    ///
+    /// - The bitboards agree with each other and with the piece locations.
+    /// - Each player has a single king, no more than 16 pieces and 8 pawns, no pawns on the back
+    ///   ranks, and the piece counts match the bitboards. The White horde of Horde may have no
+    ///   king, any number of pawns, and pawns on the first rank. Crazyhouse players may have
+    ///   any number of pieces and pawns. An Atomic king may have exploded, and Antichess players
+    ///   may have any number of kings.
+    /// - Each castling right has its king and rook on their starting squares.
+    /// - The en-passant square is behind a pawn that could have just been double pushed.
+    /// - The zobrist, pawn and material keys, psq score, non-pawn material, checkers, blockers,
+    ///   pinners and check squares match their recomputed values.
+    /// - The side not to move isn't in check, and no more than two pieces are giving check,
+    ///   unless an Atomic explosion revealed them.
     pub fn is_okay(&self) -> Result<(), BoardError> {
+        self.check_bitboards()?;
         self.check_king()?;
+        self.check_piece_counts()?;
+        self.check_castling()?;
+        self.check_ep_square()?;
+        self.check_state()?;
+        self.check_checks()?;
+        Ok(())
+    }
+
+    fn check_bitboards(&self) -> Result<(), BoardError> {
+        let white = self.bbs_player[Player::White as usize];
+        let black = self.bbs_player[Player::Black as usize];
+        if let Some(sq) = (white & black).pop_some_lsb() {
+            return Err(BoardError::BitBoardsOverlap { sq });
+        }
+
+        let mut pieces = BitBoard(0);
+        for piece in ALL_PIECE_TYPES.iter() {
+            let bb = self.bbs[*piece as usize];
+            if let Some(sq) = (pieces & bb).pop_some_lsb() {
+                return Err(BoardError::BitBoardsOverlap { sq });
+            }
+            pieces |= bb;
+        }
+
+        let occupied = self.occupied();
+        let mut disagree = (occupied ^ pieces) | (occupied ^ (white | black));
+        if let Some(sq) = disagree.pop_some_lsb() {
+            return Err(BoardError::BitBoardsDisagree { sq });
+        }
+
+        for sq in 0..SQ_CNT as u8 {
+            let sq = SQ(sq);
+            let expected = if (occupied & sq.to_bb()).is_empty() {
+                Piece::None
+            } else {
+                let player = if (white & sq.to_bb()).is_empty() {
+                    Player::Black
+                } else {
+                    Player::White
+                };
+                let piece = ALL_PIECE_TYPES
+                    .iter()
+                    .find(|p| (self.bbs[**p as usize] & sq.to_bb()).is_not_empty())
+                    .unwrap();
+                Piece::make_lossy(player, *piece)
+            };
+            if self.piece_locations.piece_at(sq) != expected {
+                return Err(BoardError::PieceLocationMismatch { sq });
+            }
+        }
         Ok(())
     }
 
+    /// Returns if a player may have no king: White in Horde, and any player who lost theirs in
+    /// Atomic or Antichess.
+    fn may_lack_king(&self, player: Player) -> bool {
+        match self.variant {
+            Variant::Horde => player == Player::White,
+            Variant::Atomic | Variant::Antichess => true,
+            _ => false,
+        }
+    }
+
     fn check_king(&self) -> Result<(), BoardError> {
-        // TODO: Implement attacks to opposing king must be zero
-        let w_king_num = self.count_piece(Player::White, PieceType::K);
-        let b_king_num = self.count_piece(Player::Black, PieceType::K);
-        if w_king_num != 1 {
-            return Err(BoardError::IncorrectKingNum {
-                player: Player::White,
-                num: w_king_num,
+        for player in ALL_PLAYERS.iter() {
+            let king_num = self.piece_bb(*player, PieceType::K).count_bits();
+            // Antichess kings are ordinary pieces, and may be promoted to
+            if (king_num == 0 && self.may_lack_king(*player)) || self.variant == Variant::Antichess
+            {
+                continue;
+            }
+            if king_num != 1 {
+                return Err(BoardError::IncorrectKingNum {
+                    player: *player,
+                    num: king_num,
+                });
+            }
+            let ksq = self.king_sq(*player);
+            if self.piece_at_sq(ksq) != Piece::make_lossy(*player, PieceType::K) {
+                return Err(BoardError::IncorrectKingSQ {
+                    player: *player,
+                    sq: ksq,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_piece_counts(&self) -> Result<(), BoardError> {
+        for player in ALL_PLAYERS.iter() {
+            let player = *player;
+            for piece in ALL_PIECE_TYPES.iter() {
+                let expected = self.piece_bb(player, *piece).count_bits();
+                let num = self.count_piece(player, *piece);
+                if num != expected {
+                    return Err(BoardError::IncorrectPieceCount {
+                        player,
+                        piece: *piece,
+                        num,
+                        expected,
+                    });
+                }
+            }
+
+            // The White horde has more pawns than a standard army, and Crazyhouse pieces can
+            // be dropped back onto the board
+            if player == Player::White && self.variant == Variant::Horde
+                || self.variant == Variant::Crazyhouse
+            {
+                continue;
+            }
+
+            let pieces = self.get_occupied_player(player).count_bits();
+            if pieces > 16 {
+                return Err(BoardError::TooManyPieces {
+                    player,
+                    num: pieces,
+                });
+            }
+            let pawns = self.count_piece(player, PieceType::P);
+            if pawns > 8 {
+                return Err(BoardError::TooManyPawns { player, num: pawns });
+            }
+        }
+
+        let mut back_ranks =
+            self.piece_bb_both_players(PieceType::P) & (BitBoard::RANK_1 | BitBoard::RANK_8);
+        if self.variant == Variant::Horde {
+            back_ranks &= !(self.piece_bb(Player::White, PieceType::P) & BitBoard::RANK_1);
+        }
+        if let Some(sq) = back_ranks.pop_some_lsb() {
+            return Err(BoardError::PawnOnBackRank { sq });
+        }
+        Ok(())
+    }
+
+    fn check_state(&self) -> Result<(), BoardError> {
+        let mut expected = BoardState::blank();
+        expected.castling = self.state.castling;
+        expected.rule_50 = self.state.rule_50;
+        expected.ep_square = self.state.ep_square;
+        expected.checks = self.state.checks;
+        expected.pockets = self.state.pockets;
+        expected.promoted = self.state.promoted;
+        expected.set(self);
+        let state = &self.state;
+
+        if state.zobrist != expected.zobrist {
+            return Err(BoardError::IncorrectZobrist {
+                key: state.zobrist,
+                expected: expected.zobrist,
             });
         }
-        if w_king_num != 1 {
-            return Err(BoardError::IncorrectKingNum {
-                player: Player::Black,
-                num: b_king_num,
+        if state.pawn_key != expected.pawn_key {
+            return Err(BoardError::IncorrectPawnKey {
+                key: state.pawn_key,
+                expected: expected.pawn_key,
             });
         }
-        let w_ksq = self.king_sq(Player::White);
-        let b_ksq = self.king_sq(Player::Black);
-        if self.piece_at_sq(w_ksq).type_of() != PieceType::K {
-            return Err(BoardError::IncorrectKingSQ {
-                player: Player::White,
-                sq: w_ksq,
+        if state.material_key != expected.material_key {
+            return Err(BoardError::IncorrectMaterialKey {
+                key: state.material_key,
+                expected: expected.material_key,
             });
         }
-        if self.piece_at_sq(b_ksq).type_of() != PieceType::K {
-            return Err(BoardError::IncorrectKingSQ {
-                player: Player::Black,
-                sq: b_ksq,
+        if state.psq != expected.psq {
+            return Err(BoardError::IncorrectPSQ {
+                psq: state.psq,
+                expected: expected.psq,
+            });
+        }
+        if state.checkers_bb != expected.checkers_bb {
+            return Err(BoardError::IncorrectCheckers {
+                checkers: state.checkers_bb,
+                expected: expected.checkers_bb,
             });
         }
+        for player in ALL_PLAYERS.iter() {
+            let p = *player as usize;
+            if state.nonpawn_material[p] != expected.nonpawn_material[p] {
+                return Err(BoardError::IncorrectNonPawnMaterial {
+                    player: *player,
+                    value: state.nonpawn_material[p],
+                    expected: expected.nonpawn_material[p],
+                });
+            }
+            if state.blockers_king[p] != expected.blockers_king[p] {
+                return Err(BoardError::IncorrectBlockers {
+                    player: *player,
+                    blockers: state.blockers_king[p],
+                    expected: expected.blockers_king[p],
+                });
+            }
+            if state.pinners_king[p] != expected.pinners_king[p] {
+                return Err(BoardError::IncorrectPinners {
+                    player: *player,
+                    pinners: state.pinners_king[p],
+                    expected: expected.pinners_king[p],
+                });
+            }
+        }
+        for piece in ALL_PIECE_TYPES.iter() {
+            if state.check_sqs[*piece as usize] != expected.check_sqs[*piece as usize] {
+                return Err(BoardError::IncorrectCheckSquares { piece: *piece });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_castling(&self) -> Result<(), BoardError> {
+        for player in ALL_PLAYERS.iter() {
+            let player = *player;
+            for side in [CastleType::KingSide, CastleType::QueenSide].iter() {
+                let side = *side;
+                if !self.state.castling.castle_rights(player, side) {
+                    continue;
+                }
+                let king = self.castling_squares.king_start(player);
+                if self.piece_at_sq(king) != Piece::make_lossy(player, PieceType::K) {
+                    return Err(BoardError::CastlingWithoutKing { player, side });
+                }
+                let rook = self.castling_squares.rook_start(player, side);
+                if self.piece_at_sq(rook) != Piece::make_lossy(player, PieceType::R) {
+                    return Err(BoardError::CastlingWithoutRook { player, side });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_ep_square(&self) -> Result<(), BoardError> {
+        let ep = self.state.ep_square;
+        if ep == NO_SQ {
+            return Ok(());
+        }
+        let error = Err(BoardError::BadEPSquare { sq: ep });
+        if !ep.is_okay() || self.turn.relative_rank_of_sq(ep) != Rank::R6 {
+            return error;
+        }
+
+        let (pawn_sq, start_sq) = match self.turn {
+            Player::White => (ep - SQ(8), ep + SQ(8)),
+            Player::Black => (ep + SQ(8), ep - SQ(8)),
+        };
+        if self.piece_at_sq(pawn_sq) != Piece::make_lossy(!self.turn, PieceType::P)
+            || self.piece_at_sq(ep) != Piece::None
+            || self.piece_at_sq(start_sq) != Piece::None
+        {
+            return error;
+        }
+        Ok(())
+    }
+
+    fn check_checks(&self) -> Result<(), BoardError> {
+        let them = !self.turn;
+        if self.king_attackers(them, self.occupied()).is_not_empty() {
+            return Err(BoardError::OpponentInCheck { player: them });
+        }
 
+        // Explosions can reveal any number of checks in Atomic
+        let checkers = self.checkers().count_bits();
+        if checkers > 2 && self.variant != Variant::Atomic {
+            return Err(BoardError::TooManyCheckers { num: checkers });
+        }
         Ok(())
     }
-    //
-    //    fn check_bitboards(&self) -> bool {
-    //        assert_eq!(self.occupied_white() & self.occupied_black(), BitBoard(0));
-    //        assert_eq!(
-    //            self.occupied_black() | self.occupied_white(),
-    //            self.get_occupied()
-    //        );
-    //
-    //        let all: BitBoard = self.piece_bb(Player::White, Piece::P) ^ self.piece_bb(Player::Black, Piece::P)
-    //            ^ self.piece_bb(Player::White, Piece::N) ^ self.piece_bb(Player::Black, Piece::N)
-    //            ^ self.piece_bb(Player::White, Piece::B) ^ self.piece_bb(Player::Black, Piece::B)
-    //            ^ self.piece_bb(Player::White, Piece::R) ^ self.piece_bb(Player::Black, Piece::R)
-    //            ^ self.piece_bb(Player::White, Piece::Q) ^ self.piece_bb(Player::Black, Piece::Q)
-    //            ^ self.piece_bb(Player::White, Piece::K) ^ self.piece_bb(Player::Black, Piece::K);
-    //        // Note, this was once all.0, self.get_occupied.0
-    //        assert_eq!(all, self.get_occupied());
-    //        true
-    //    }
-
-    //    fn check_state_info(&self) -> bool {
-    //        true
-    //    }
-    //
-    //    fn check_lists(&self) -> bool {
-    //        true
-    //    }
-    //
-    //    fn check_castling(&self) -> bool {
-    //        true
-    //    }
 }
 
 #[derive(Eq, PartialEq)]
@@ -2725,8 +4095,14 @@ impl RandBoard {
 mod tests {
 
     extern crate rand;
-    use board::Board;
-    use {BitMove, PieceType, SQ};
+    use board::board_state::{BoardState, StateStack};
+    use board::fen;
+    use board::variant::Variant;
+    use board::{Board, BoardError};
+    use core::piece_move::MoveType;
+    use core::Rank;
+    use tools::prng::PRNG;
+    use {BitMove, Piece, PieceType, Player, SQ};
 
     #[test]
     fn random_move_apply() {
@@ -2826,7 +4202,343 @@ mod tests {
 
     fn see_ge_all_fens_inner(b: &Board) {
         for m in b.generate_moves().iter() {
-            b.see_ge(*m, 0);
+            // Special moves, and exchanges that can involve promotions, are only valued by `see`
+            let special = m.move_type() != MoveType::Normal
+                || m.get_dest().rank() == Rank::R1
+                || m.get_dest().rank() == Rank::R8;
+            let see = b.see(*m);
+            for threshold in [-1000, -300, -1, 0, 1, 300, 1000, see - 1, see, see + 1].iter() {
+                if special {
+                    if m.move_type() != MoveType::Normal {
+                        assert_eq!(b.see_ge(*m, *threshold), 0 >= *threshold);
+                    }
+                } else {
+                    assert_eq!(
+                        b.see_ge(*m, *threshold),
+                        see >= *threshold,
+                        "{} {}",
+                        b.fen(),
+                        m
+                    );
+                }
+            }
         }
     }
+
+    #[test]
+    fn see_pinner_on_target() {
+        // The bishop on d4 pins the pawn on c3, which may recapture once the bishop is taken
+        let board = Board::from_fen("4k3/8/8/4p3/3b4/2P2N2/8/K7 w - - 0 1").unwrap();
+        let mov = BitMove::make_capture(SQ::F3, SQ::D4);
+        assert!(board.see(mov) > 0);
+        see_ge_all_fens_inner(&board);
+        see_ge_all_fens_inner(&board.flip().unwrap());
+    }
+
+    #[test]
+    fn see_random_games() {
+        let mut prng = PRNG::init(0x5EE_CAFE);
+        for _ in 0..20 {
+            let mut board = Board::start_pos();
+            while board.moves_played() < 120 && !board.generate_moves().is_empty() {
+                see_ge_all_fens_inner(&board);
+                let moves = board.generate_moves();
+                board.apply_move(moves[prng.rand() as usize % moves.len()]);
+            }
+        }
+    }
+
+    #[test]
+    fn see_values() {
+        use helper::prelude::piecetype_value;
+        let value = |piece: PieceType| piecetype_value(piece, false);
+
+        // Rook takes a pawn defended by a rook, with a queen behind it to x-ray through
+        let board = Board::from_fen("3rk3/8/8/3p4/8/8/3R4/3QK3 w - - 0 1").unwrap();
+        let mov = BitMove::make_capture(SQ::D2, SQ::D5);
+        assert_eq!(board.see(mov), value(PieceType::P));
+        assert_eq!(board.see_square(SQ::D5, Player::White), value(PieceType::P));
+
+        // Without the queen, the rook is traded for a pawn
+        let board = Board::from_fen("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let mov = BitMove::make_capture(SQ::D2, SQ::D5);
+        assert_eq!(board.see(mov), value(PieceType::P) - value(PieceType::R));
+        assert_eq!(board.see_square(SQ::D5, Player::White), 0);
+        assert_eq!(board.see_square(SQ::D2, Player::Black), 0);
+
+        // The pinned knight on e5 can't recapture on d3
+        let board = Board::from_fen("4k3/8/8/4n3/8/3n4/8/3RR2K w - - 0 1").unwrap();
+        let mov = BitMove::make_capture(SQ::D1, SQ::D3);
+        assert_eq!(board.see(mov), value(PieceType::N));
+
+        // The pawn recaptures on b1 by promoting
+        let board = Board::from_fen("4k3/8/8/8/8/8/p7/1n2K1R1 w - - 0 1").unwrap();
+        let mov = BitMove::make_capture(SQ::G1, SQ::B1);
+        assert_eq!(
+            board.see(mov),
+            value(PieceType::N) - value(PieceType::R) - value(PieceType::Q) + value(PieceType::P)
+        );
+        // `see_ge` doesn't count the promotion
+        let threshold = value(PieceType::N) - value(PieceType::R);
+        assert!(board.see_ge(mov, threshold));
+        assert!(!board.see_ge(mov, threshold + 1));
+
+        // Promoting with a capture, and being recaptured by the rook
+        let board = Board::from_fen("rn2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promo = board
+            .generate_moves()
+            .iter()
+            .find(|m| m.is_promo() && m.promo_piece() == PieceType::Q && m.get_dest() == SQ::B8)
+            .cloned()
+            .unwrap();
+        assert_eq!(board.see(promo), value(PieceType::N) - value(PieceType::P));
+
+        // Castles gain nothing
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let castle = board
+            .generate_moves()
+            .iter()
+            .find(|m| m.is_castle())
+            .cloned()
+            .unwrap();
+        assert_eq!(board.see(castle), 0);
+    }
+
+    #[test]
+    fn is_okay_all_fens() {
+        for fen in super::fen::ALL_FENS.iter() {
+            let mut board = Board::from_fen(fen).unwrap();
+            assert!(board.is_okay().is_ok(), "{}", fen);
+            let mut ply = 0;
+            while ply < 40 && !board.generate_moves().is_empty() {
+                let moves = board.generate_moves();
+                board.apply_move(moves[rand::random::<usize>() % moves.len()]);
+                assert_eq!(board.is_okay(), Ok(()), "{}", board.fen());
+                ply += 1;
+            }
+            while ply > 0 {
+                board.undo_move();
+                assert_eq!(board.is_okay(), Ok(()), "{}", board.fen());
+                ply -= 1;
+            }
+        }
+    }
+
+    #[test]
+    fn is_okay_corrupted() {
+        let corrupt_state = |f: &dyn Fn(&mut BoardState)| {
+            let mut board = Board::start_pos();
+            let mut state = (*board.state).clone();
+            f(&mut state);
+            board.state = StateStack::new(state);
+            board.is_okay()
+        };
+
+        let zobrist = Board::start_pos().zobrist();
+        assert_eq!(
+            corrupt_state(&|s| s.zobrist ^= 1),
+            Err(BoardError::IncorrectZobrist {
+                key: zobrist ^ 1,
+                expected: zobrist,
+            })
+        );
+        match corrupt_state(&|s| s.pawn_key = 0) {
+            Err(BoardError::IncorrectPawnKey { .. }) => {}
+            err => panic!("{:?}", err),
+        }
+        match corrupt_state(&|s| s.psq += ::core::score::Score(1, 1)) {
+            Err(BoardError::IncorrectPSQ { .. }) => {}
+            err => panic!("{:?}", err),
+        }
+        match corrupt_state(&|s| s.checkers_bb = SQ::E2.to_bb()) {
+            Err(BoardError::IncorrectCheckers { .. }) => {}
+            err => panic!("{:?}", err),
+        }
+        match corrupt_state(&|s| s.ep_square = SQ::E3) {
+            Err(BoardError::BadEPSquare { sq }) => assert_eq!(sq, SQ::E3),
+            err => panic!("{:?}", err),
+        }
+
+        let mut board = Board::start_pos();
+        board.piece_counts[Player::Black as usize][PieceType::N as usize] = 3;
+        assert_eq!(
+            board.is_okay(),
+            Err(BoardError::IncorrectPieceCount {
+                player: Player::Black,
+                piece: PieceType::N,
+                num: 3,
+                expected: 2,
+            })
+        );
+
+        let mut board = Board::start_pos();
+        board.piece_locations.remove(SQ::D1);
+        assert_eq!(
+            board.is_okay(),
+            Err(BoardError::PieceLocationMismatch { sq: SQ::D1 })
+        );
+
+        let mut board = Board::start_pos();
+        board.bbs[PieceType::N as usize] |= SQ::D1.to_bb();
+        assert_eq!(
+            board.is_okay(),
+            Err(BoardError::BitBoardsOverlap { sq: SQ::D1 })
+        );
+
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        board.remove_piece_c(Piece::BlackRook, SQ::A8);
+        board.state = StateStack::new({
+            let mut state = BoardState::blank();
+            state.castling = board.state.castling;
+            state.set(&board);
+            state
+        });
+        assert_eq!(
+            board.is_okay(),
+            Err(BoardError::CastlingWithoutRook {
+                player: Player::Black,
+                side: ::core::CastleType::QueenSide,
+            })
+        );
+    }
+
+    #[test]
+    fn is_okay_opponent_in_check() {
+        // from_fen doesn't reject a position where the side not to move is in check
+        let board = Board::from_fen("4k3/8/8/8/4R3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            board.is_okay(),
+            Err(BoardError::OpponentInCheck {
+                player: Player::Black,
+            })
+        );
+    }
+
+    fn assert_transform(board: &Board, transformed: &Board, map: fn(BitMove) -> BitMove) {
+        assert!(transformed.is_okay().is_ok(), "{}", transformed.fen());
+        let rebuilt = Board::from_fen_variant(&transformed.fen(), board.variant()).unwrap();
+        assert_eq!(
+            rebuilt.zobrist(),
+            transformed.zobrist(),
+            "{}",
+            transformed.fen()
+        );
+        assert_eq!(rebuilt.psq(), transformed.psq());
+
+        let moves = board.generate_moves();
+        let transformed_moves = transformed.generate_moves();
+        assert_eq!(moves.len(), transformed_moves.len(), "{}", board.fen());
+        for mov in moves.iter() {
+            assert!(
+                transformed_moves.contains(&map(*mov)),
+                "{} {}",
+                board.fen(),
+                mov
+            );
+        }
+    }
+
+    #[test]
+    fn flip_and_mirror() {
+        let boards = fen::ALL_FENS
+            .iter()
+            .map(|f| Board::from_fen(f).unwrap())
+            .chain(Board::random().pseudo_random(9_182_736).many(50));
+        for board in boards {
+            let flipped = board.flip().unwrap();
+            let twice = flipped.flip().unwrap();
+            assert_eq!(flipped.turn(), !board.turn());
+            assert_eq!(flipped.moves_played() / 2, board.moves_played() / 2);
+            assert_eq!(twice.fen(), board.fen());
+            assert_eq!(twice.zobrist(), board.zobrist());
+            assert_transform(&board, &flipped, BitMove::flip);
+
+            match board.mirror_horizontal() {
+                Some(mirrored) => {
+                    let twice = mirrored.mirror_horizontal().unwrap();
+                    assert_eq!(twice.fen(), board.fen());
+                    assert_eq!(twice.zobrist(), board.zobrist());
+                    assert_transform(&board, &mirrored, BitMove::mirror_horizontal);
+                }
+                None => assert_ne!(board.castling_bits(), 0),
+            }
+        }
+    }
+
+    #[test]
+    fn flip_variants() {
+        let board = Board::from_fen_variant(
+            "r3k2r/8/8/8/8/8/8/1R2K2R w Kk - 0 1 +1+2",
+            Variant::ThreeCheck,
+        )
+        .unwrap();
+        let flipped = board.flip().unwrap();
+        assert_eq!(flipped.checks_given(Player::White), 2);
+        assert_eq!(flipped.checks_given(Player::Black), 1);
+        assert_eq!(flipped.flip().unwrap(), board);
+
+        let board =
+            Board::from_fen_variant("4k3/8/8/8/8/8/8/2Q~1K3[Pn] w - - 0 1", Variant::Crazyhouse)
+                .unwrap();
+        let flipped = board.flip().unwrap();
+        assert_eq!(flipped.pocket(Player::White, PieceType::N), 1);
+        assert_eq!(flipped.pocket(Player::Black, PieceType::P), 1);
+        assert_eq!(flipped.fen(), "2q~1k3/8/8/8/8/8/8/4K3[Np] b - - 0 1");
+        assert_transform(&board, &flipped, BitMove::flip);
+        let mirrored = board.mirror_horizontal().unwrap();
+        assert_eq!(mirrored.fen(), "3k4/8/8/8/8/8/8/3K1Q~2[Pn] w - - 0 1");
+        assert_transform(&board, &mirrored, BitMove::mirror_horizontal);
+    }
+
+    #[test]
+    fn flip_asymmetric_variants() {
+        assert!(Board::start_pos_variant(Variant::Horde).flip().is_none());
+        assert!(Board::start_pos_variant(Variant::RacingKings)
+            .flip()
+            .is_none());
+        for variant in [Variant::KingOfTheHill, Variant::Crazyhouse].iter() {
+            assert!(Board::start_pos_variant(*variant).flip().is_some());
+        }
+    }
+
+    fn apply_uci_moves(board: &mut Board, moves: &[&str]) {
+        for mov in moves {
+            assert!(board.apply_uci_move(mov), "illegal move {}", mov);
+        }
+    }
+
+    #[test]
+    fn game_cycle() {
+        let mut board = Board::start_pos();
+        apply_uci_moves(&mut board, &["g1f3", "g8f6", "f3g1"]);
+        assert!(board.has_game_cycle(4));
+        // At the root, the starting position must already have repeated
+        assert!(!board.has_game_cycle(0));
+        assert!(!board.has_game_cycle(3));
+
+        apply_uci_moves(&mut board, &["f6g8", "g1f3", "g8f6", "f3g1"]);
+        assert!(board.has_game_cycle(0));
+        assert!(!board.threefold_repetition());
+
+        // A null move hides the positions before it
+        unsafe { board.apply_null_move() };
+        assert!(!board.has_game_cycle(10));
+        unsafe { board.undo_null_move() };
+
+        // A capture or pawn move does the same
+        apply_uci_moves(&mut board, &["e7e6"]);
+        assert!(!board.has_game_cycle(10));
+
+        // The rook can't return to a1, as the knight is in the way
+        let mut board = Board::from_fen("7k/8/8/8/8/8/N7/R3K3 w - - 0 1").unwrap();
+        apply_uci_moves(&mut board, &["a2b4", "h8h7", "a1a3", "h7h8", "b4a2"]);
+        assert!(!board.has_game_cycle(10));
+        // Once the knight has moved on, it can move back again
+        apply_uci_moves(&mut board, &["h8h7", "a2b4", "h7h8"]);
+        assert!(board.has_game_cycle(10));
+
+        let mut board = Board::start_pos_variant(Variant::Antichess);
+        apply_uci_moves(&mut board, &["g1f3", "g8f6", "f3g1"]);
+        assert!(!board.has_game_cycle(4));
+    }
 }
@@ -47,19 +47,52 @@
 //! let moves: MoveList = board.generate_moves_of_type(GenTypes::Captures);
 //! ```
 //!
+//! Generating legal moves into an existing list, replacing its contents. The list must have
+//! room for `MAX_MOVES` moves, or `MAX_CRAZYHOUSE_MOVES` in Crazyhouse:
+//!
+//! ```ignore
+//! unsafe { board.generate_moves_into(&mut moves) };
+//! ```
+//!
+//! # Staged Generation
+//!
+//! A [`StagedMoves`] generates legal moves lazily, one stage at a time: first a given move (such
+//! as one from a transposition table), then captures, then quiet moves. When in check, the
+//! evasions are generated in a single stage. A search that cuts off after the first few moves
+//! never generates the later stages.
+//!
+//! ```
+//! use pleco::Board;
+//! use pleco::board::movegen::MoveStage;
+//!
+//! let board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+//! let mut staged = board.staged_moves();
+//!
+//! let capture = staged.next().unwrap();
+//! assert!(capture.is_capture());
+//! assert_eq!(staged.stage(), MoveStage::Captures);
+//! assert_eq!(staged.count(), board.generate_moves().len() - 1);
+//! ```
+//!
 //! [`GenTypes`]: ../../core/enum.GenTypes.html
 //! [`Board`]: ../struct.Board.html
 //! [`Board::legal_move`]: ../struct.Board.html#method.legal_move
+//! [`StagedMoves`]: struct.StagedMoves.html
 
 use std::hint::unreachable_unchecked;
+use std::iter::FromIterator;
 use std::mem;
 use std::ops::Index;
 use std::ptr;
 
+use board::castle_rights::CastlingSquares;
+use board::variant::Variant;
 use board::*;
 
 use core::mono_traits::GenTypeTrait;
-use core::move_list::{MVPushable, MoveList, ScoringMoveList};
+use core::move_list::{
+    CrazyhouseMoveList, MVPushable, MoveList, ScoringMoveList, MAX_CRAZYHOUSE_MOVES, MAX_MOVES,
+};
 use core::piece_move::{BitMove, MoveFlag, PreMoveInfo, ScoringMove};
 
 use {BitBoard, PieceType, Player, SQ};
@@ -115,6 +148,22 @@ impl Legality for PseudoLegal {
 const STANDARD_PIECES: [PieceType; 4] = [PieceType::B, PieceType::N, PieceType::R, PieceType::Q];
 const DEFAULT_MOVES_LENGTH: usize = 32;
 
+// Fits the moves of a Crazyhouse position into a smaller list, for when a list with room for
+// `MAX_CRAZYHOUSE_MOVES` wasn't asked for
+#[cold]
+fn fit_crazyhouse_moves<T, F: FromIterator<T>>(moves: &[T]) -> F
+where
+    T: Copy,
+{
+    assert!(
+        moves.len() <= MAX_MOVES,
+        "a Crazyhouse position has {} moves, more than fit in a `MoveList`, \
+         use `MoveGen::generate_crazyhouse()` or `Board::generate_crazyhouse_moves()` instead",
+        moves.len()
+    );
+    moves.iter().cloned().collect()
+}
+
 /// Public move generator.
 ///
 /// This is a wrapper type around `InnerMoveGen`, allowing for a more friendly API
@@ -122,12 +171,54 @@ pub struct MoveGen {}
 
 impl MoveGen {
     /// Returns `MoveList` of all moves for a given board, Legality & GenType.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a Crazyhouse position has more than `MAX_MOVES` moves, which a
+    /// `MoveGen::generate_crazyhouse()` list has room for.
     #[inline]
     pub fn generate<L: Legality, G: GenTypeTrait>(chessboard: &Board) -> MoveList {
-        let mut movelist = MoveList::default();
+        MoveGen::generate_list::<L, G, MAX_MOVES>(chessboard)
+    }
+
+    /// Returns a `CrazyhouseMoveList` of all moves for a given board, Legality & GenType. This
+    /// works for any variant, but the list has room for the moves of any Crazyhouse position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    /// use pleco::board::movegen::{MoveGen,Legal};
+    /// use pleco::board::variant::Variant;
+    /// use pleco::core::mono_traits::AllGenType;
+    /// use pleco::core::move_list::MAX_MOVES;
+    ///
+    /// let fen = "k7/8/8/8/8/8/8/K6Q[QRBNP] w - - 0 1";
+    /// let board = Board::from_fen_variant(fen, Variant::Crazyhouse).unwrap();
+    /// let moves = MoveGen::generate_crazyhouse::<Legal, AllGenType>(&board);
+    /// assert!(moves.len() > MAX_MOVES);
+    /// ```
+    #[inline]
+    pub fn generate_crazyhouse<L: Legality, G: GenTypeTrait>(
+        chessboard: &Board,
+    ) -> CrazyhouseMoveList {
+        MoveGen::generate_list::<L, G, MAX_CRAZYHOUSE_MOVES>(chessboard)
+    }
+
+    // Generates into a list with room for `N` moves. The moves of a Crazyhouse position are
+    // generated into a list with room for all of them first, then fit into the smaller list.
+    #[inline(always)]
+    pub(crate) fn generate_list<L: Legality, G: GenTypeTrait, const N: usize>(
+        chessboard: &Board,
+    ) -> MoveList<N> {
+        if N < MAX_CRAZYHOUSE_MOVES && chessboard.variant() == Variant::Crazyhouse {
+            let moves = MoveGen::generate_crazyhouse::<L, G>(chessboard);
+            return fit_crazyhouse_moves(&moves);
+        }
+        let mut movelist = MoveList::empty();
         unsafe {
             let ptr: *mut BitMove = movelist.as_mut_ptr();
-            let new_ptr = InnerMoveGen::<MoveList>::generate::<L, G>(chessboard, ptr);
+            let new_ptr = InnerMoveGen::<MoveList<N>>::generate::<L, G>(chessboard, ptr);
             let new_size = (new_ptr as usize - ptr as usize) / mem::size_of::<BitMove>();
             movelist.unchecked_set_len(new_size);
         }
@@ -135,8 +226,17 @@ impl MoveGen {
     }
 
     /// Returns a `ScoringMoveList` of all moves for a given board, Legality & GenType.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a Crazyhouse position has more than `MAX_MOVES` moves, which a
+    /// `MoveGen::generate_crazyhouse()` list has room for.
     #[inline]
     pub fn generate_scoring<L: Legality, G: GenTypeTrait>(chessboard: &Board) -> ScoringMoveList {
+        if chessboard.variant() == Variant::Crazyhouse {
+            let moves = MoveGen::generate_crazyhouse::<L, G>(chessboard);
+            return fit_crazyhouse_moves(&moves);
+        }
         let mut movelist = ScoringMoveList::default();
         unsafe {
             let ptr: *mut ScoringMove = movelist.as_mut_ptr();
@@ -147,6 +247,79 @@ impl MoveGen {
         movelist
     }
 
+    /// Returns a `MoveList` of the moves for a given board, Legality & GenType, of only the pieces
+    /// on the `from` squares moving onto the `to` squares.
+    ///
+    /// Rather than filtering every move, only the moves of the pieces on `from` are generated, so
+    /// this is much cheaper than `MoveGen::generate()` when the masks are small. Castling is
+    /// matched by the king's square and the castling rook's square, as in `BitMove`. Crazyhouse
+    /// drops have no source square, so they are only generated when `from` is every square.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a Crazyhouse position has more than `MAX_MOVES` moves within the masks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,BitBoard,SQ};
+    /// use pleco::board::movegen::{MoveGen,Legal};
+    /// use pleco::core::mono_traits::AllGenType;
+    ///
+    /// let board = Board::start_pos();
+    /// let moves = MoveGen::generate_masked::<Legal, AllGenType>(&board, SQ::G1.to_bb(), BitBoard::ALL);
+    /// assert_eq!(moves.len(), 2);
+    /// ```
+    #[inline]
+    pub fn generate_masked<L: Legality, G: GenTypeTrait>(
+        chessboard: &Board,
+        from: BitBoard,
+        to: BitBoard,
+    ) -> MoveList {
+        if chessboard.variant() == Variant::Crazyhouse {
+            let mut moves = CrazyhouseMoveList::with_crazyhouse_capacity();
+            unsafe {
+                let ptr: *mut BitMove = moves.as_mut_ptr();
+                let new_ptr = InnerMoveGen::<CrazyhouseMoveList>::generate_masked::<L, G>(
+                    chessboard, ptr, from, to,
+                );
+                let new_size = (new_ptr as usize - ptr as usize) / mem::size_of::<BitMove>();
+                moves.unchecked_set_len(new_size);
+            }
+            return fit_crazyhouse_moves(&moves);
+        }
+        let mut movelist = MoveList::default();
+        unsafe {
+            let ptr: *mut BitMove = movelist.as_mut_ptr();
+            let new_ptr =
+                InnerMoveGen::<MoveList>::generate_masked::<L, G>(chessboard, ptr, from, to);
+            let new_size = (new_ptr as usize - ptr as usize) / mem::size_of::<BitMove>();
+            movelist.unchecked_set_len(new_size);
+        }
+        movelist
+    }
+
+    /// Generates the moves of a certain Legality and Generation type into a list, replacing its
+    /// contents. Unlike `MoveGen::generate()`, the moves are written directly into the
+    /// caller's list, so a single list can be reused without being created or copied again.
+    ///
+    /// # Safety
+    ///
+    /// The moves are written without checking the list's capacity, so the list must have room
+    /// for `MAX_MOVES` moves, as `MoveList` and `ScoringMoveList` do. For a Crazyhouse board, it
+    /// must have room for `MAX_CRAZYHOUSE_MOVES` moves, as `CrazyhouseMoveList` and
+    /// `CrazyhouseScoringMoveList` do.
+    #[inline]
+    pub unsafe fn generate_into<L: Legality, G: GenTypeTrait, MP: MVPushable>(
+        chessboard: &Board,
+        movelist: &mut MP,
+    ) where
+        <MP as Index<usize>>::Output: Sized,
+    {
+        movelist.unchecked_set_len(0);
+        MoveGen::extend::<L, G, MP>(chessboard, movelist);
+    }
+
     /// Extends the current list of moves of a certain Legality, and Generation type. This method
     /// will correctly set the new length of the list.
     ///
@@ -201,6 +374,10 @@ struct InnerMoveGen<'a, MP: MVPushable + 'a> {
     us_occ: BitBoard,
     // squares occupied by player to move
     them_occ: BitBoard, // Squares occupied by the opposing player
+    // Squares of the pieces to generate moves for
+    src_mask: BitBoard,
+    // Squares the generated moves may move onto
+    dst_mask: BitBoard,
 }
 
 impl<'a, MP: MVPushable> InnerMoveGen<'a, MP>
@@ -213,49 +390,75 @@ where
         chessboard: &Board,
         movelist: *mut MP::Output,
     ) -> *mut MP::Output {
+        InnerMoveGen::<MP>::generate_masked::<L, G>(
+            chessboard,
+            movelist,
+            BitBoard::ALL,
+            BitBoard::ALL,
+        )
+    }
+
+    /// Returns a pointer to the last element of the moves from the `src_mask` squares onto the
+    /// `dst_mask` squares for a given board, Legality & GenType.
+    #[inline(always)]
+    fn generate_masked<L: Legality, G: GenTypeTrait>(
+        chessboard: &Board,
+        movelist: *mut MP::Output,
+        src_mask: BitBoard,
+        dst_mask: BitBoard,
+    ) -> *mut MP::Output {
+        // No moves are legal once a variant's win condition has been reached
+        if L::gen_legal() && chessboard.is_variant_end() {
+            return movelist;
+        }
+        let movegen = InnerMoveGen::<MP>::get_self(chessboard, movelist, src_mask, dst_mask);
         match chessboard.turn() {
-            Player::White => {
-                InnerMoveGen::<MP>::generate_helper::<L, G, WhiteType>(chessboard, movelist)
-            }
-            Player::Black => {
-                InnerMoveGen::<MP>::generate_helper::<L, G, BlackType>(chessboard, movelist)
-            }
+            Player::White => movegen.generate_helper::<L, G, WhiteType>(),
+            Player::Black => movegen.generate_helper::<L, G, BlackType>(),
         }
     }
 
     // Helper function to setup the MoveGen structure.
     #[inline(always)]
-    fn get_self(chessboard: &'a Board, ptr: *mut MP::Output) -> Self {
+    fn get_self(
+        chessboard: &'a Board,
+        ptr: *mut MP::Output,
+        src_mask: BitBoard,
+        dst_mask: BitBoard,
+    ) -> Self {
         InnerMoveGen {
             ptr,
             board: chessboard,
             occ: chessboard.occupied(),
             us_occ: chessboard.get_occupied_player(chessboard.turn()),
             them_occ: chessboard.get_occupied_player(chessboard.turn().other_player()),
+            src_mask,
+            dst_mask,
         }
     }
 
     /// Directly generates the moves.
-    fn generate_helper<L: Legality, G: GenTypeTrait, P: PlayerTrait>(
-        chessboard: &Board,
-        ptr: *mut MP::Output,
-    ) -> *mut MP::Output {
-        let mut movegen = InnerMoveGen::<MP>::get_self(chessboard, ptr);
+    fn generate_helper<L: Legality, G: GenTypeTrait, P: PlayerTrait>(mut self) -> *mut MP::Output {
         let gen_type = G::gen_type();
-        if gen_type == GenTypes::Evasions {
-            movegen.generate_evasions::<L, P>();
+        let variant = self.board.variant();
+        if variant == Variant::Antichess
+            || (variant == Variant::Atomic && gen_type != GenTypes::QuietChecks)
+        {
+            self.generate_without_evasions::<L, G, P>();
+        } else if gen_type == GenTypes::Evasions {
+            self.generate_evasions::<L, P>();
         } else if gen_type == GenTypes::QuietChecks {
-            movegen.generate_quiet_checks::<L, P>();
+            self.generate_quiet_checks::<L, P>();
         } else if gen_type == GenTypes::All {
-            if movegen.board.in_check() {
-                movegen.generate_evasions::<L, P>();
+            if self.board.in_check() {
+                self.generate_evasions::<L, P>();
             } else {
-                movegen.generate_non_evasions::<L, NonEvasionsGenType, P>();
+                self.generate_non_evasions::<L, NonEvasionsGenType, P>();
             }
         } else {
-            movegen.generate_non_evasions::<L, G, P>();
+            self.generate_non_evasions::<L, G, P>();
         }
-        movegen.ptr
+        self.ptr
     }
 
     /// Generates non-evasions, ala the board is in check.
@@ -276,9 +479,36 @@ where
         self.generate_all::<L, G, P>(target);
     }
 
+    /// Generates moves for Atomic and Antichess, where a check can't always be answered by
+    /// evading it. Every move of the `GenType` is checked for legality, even when in check.
+    fn generate_without_evasions<L: Legality, G: GenTypeTrait, P: PlayerTrait>(&mut self) {
+        let gen_type = G::gen_type();
+        if self.board.variant() != Variant::Antichess {
+            self.generate_to_target::<L, G, P>();
+        } else if L::gen_legal() && self.board.capture_available() {
+            // Captures are compulsory in Antichess
+            if gen_type != GenTypes::Quiets {
+                self.generate_all::<L, CapturesGenType, P>(self.them_occ);
+            }
+        } else if gen_type != GenTypes::QuietChecks {
+            // Without a capture available, every pseudo-legal Antichess move is legal
+            self.generate_to_target::<PseudoLegal, G, P>();
+        }
+    }
+
+    /// Generates the moves of a `GenType` onto their target squares, without regard to check.
+    fn generate_to_target<L: Legality, G: GenTypeTrait, P: PlayerTrait>(&mut self) {
+        match G::gen_type() {
+            GenTypes::Captures => self.generate_all::<L, CapturesGenType, P>(self.them_occ),
+            GenTypes::Quiets => self.generate_all::<L, QuietsGenType, P>(!self.occ),
+            _ => self.generate_all::<L, NonEvasionsGenType, P>(!self.us_occ),
+        }
+    }
+
     /// Generates all moves of a certain legality, `GenType`, and player. The target is the
     /// bitboard of the squares where moves should be generated.
     fn generate_all<L: Legality, G: GenTypeTrait, P: PlayerTrait>(&mut self, target: BitBoard) {
+        let target: BitBoard = target & self.dst_mask;
         self.generate_pawn_moves::<L, G, P>(target);
         self.moves_per_piece::<L, P, KnightType>(target);
         self.moves_per_piece::<L, P, BishopType>(target);
@@ -291,18 +521,34 @@ where
 
         if G::gen_type() != GenTypes::Captures
             && G::gen_type() != GenTypes::Evasions
+            && !self.board.in_check()
             && (self.board.can_castle(P::player(), CastleType::KingSide)
                 || self.board.can_castle(P::player(), CastleType::QueenSide))
         {
             self.generate_castling::<L, P>();
         }
+
+        if G::gen_type() != GenTypes::Captures
+            && self.board.variant() == Variant::Crazyhouse
+            && self.src_mask == BitBoard::ALL
+        {
+            self.generate_drops::<L, G, P>(target);
+        }
     }
 
     /// Generates quiet checks.
     fn generate_quiet_checks<L: Legality, P: PlayerTrait>(&mut self) {
         assert!(!self.board.in_check());
-        let mut disc_check: BitBoard = self.board.discovered_check_candidates();
-        let target: BitBoard = !self.board.occupied();
+        // Horde's White player has no king to check
+        if self
+            .board
+            .piece_bb(P::opp_player(), PieceType::K)
+            .is_empty()
+        {
+            return;
+        }
+        let mut disc_check: BitBoard = self.board.discovered_check_candidates() & self.src_mask;
+        let target: BitBoard = !self.board.occupied() & self.dst_mask;
 
         // discovered check candidates
         while let Some(from) = disc_check.pop_some_lsb() {
@@ -337,7 +583,11 @@ where
         }
 
         // Possible king moves, Where the king cannot move into a slider / own pieces
-        let k_moves: BitBoard = king_moves(ksq) & !slider_attacks & !self.us_occ;
+        let mut k_moves: BitBoard =
+            king_moves(ksq) & !slider_attacks & !self.us_occ & self.dst_mask;
+        if (self.src_mask & ksq.to_bb()).is_empty() {
+            k_moves = BitBoard(0);
+        }
 
         // Separate captures and non captures
         let mut captures_bb: BitBoard = k_moves & self.them_occ;
@@ -355,6 +605,34 @@ where
         }
     }
 
+    // Generates Crazyhouse drops of the pieces in the pocket onto the empty target squares
+    fn generate_drops<L: Legality, G: GenTypeTrait, P: PlayerTrait>(&mut self, target: BitBoard) {
+        let empty: BitBoard = target & !self.occ;
+        for piece in [
+            PieceType::P,
+            PieceType::N,
+            PieceType::B,
+            PieceType::R,
+            PieceType::Q,
+        ]
+        .iter()
+        {
+            if self.board.pocket(P::player(), *piece) == 0 {
+                continue;
+            }
+            let mut b: BitBoard = empty;
+            if *piece == PieceType::P {
+                b &= !(BitBoard::RANK_1 | BitBoard::RANK_8);
+            }
+            if G::gen_type() == GenTypes::QuietChecks {
+                b &= self.board.state.check_sqs[*piece as usize];
+            }
+            while let Some(dst) = b.pop_some_lsb() {
+                self.check_and_add::<L>(BitMove::make_drop(*piece, dst));
+            }
+        }
+    }
+
     // Generate king moves with a given target
     fn generate_king_moves<L: Legality, P: PlayerTrait>(&mut self, target: BitBoard) {
         self.moves_per_piece::<L, P, KingType>(target);
@@ -371,6 +649,8 @@ where
         // Make sure we can castle AND the space between the king / rook is clear AND the piece at castling_side is a Rook
         if !self.board.castle_impeded(side)
             && self.board.can_castle(P::player(), side)
+            && (self.src_mask & self.board.king_sq(P::player()).to_bb()).is_not_empty()
+            && (self.dst_mask & self.board.castling_rook_square(side).to_bb()).is_not_empty()
             && self
                 .board
                 .piece_at_sq(self.board.castling_rook_square(side))
@@ -383,8 +663,29 @@ where
             let r_from: SQ = self.board.castling_rook_square(side);
             let k_to = P::player().relative_square(if king_side { SQ::G1 } else { SQ::C1 });
 
-            let enemies: BitBoard = self.them_occ;
-            let direction: fn(SQ) -> SQ = if king_side {
+            let mut enemies: BitBoard = self.them_occ;
+            let atomic: bool = self.board.variant() == Variant::Atomic;
+
+            // Atomic kings can't capture, so squares next to the enemy king are safe. A king
+            // next to the enemy king may also be attacked without being in check, so it can't
+            // shield the squares it passes through, but the castled rook can shield its
+            // destination.
+            let mut safe: BitBoard = BitBoard(0);
+            let mut occ: BitBoard = self.occ;
+            let mut k_to_occ: BitBoard = self.occ;
+            if atomic {
+                let their_king = self.board.piece_bb(P::opp_player(), PieceType::K);
+                enemies &= !their_king;
+                if their_king.is_not_empty() {
+                    safe = king_moves(their_king.to_sq());
+                }
+                let (_, r_to) = CastlingSquares::destinations(P::player(), side);
+                occ ^= ksq.to_bb();
+                k_to_occ = (occ ^ r_from.to_bb()) | r_to.to_bb();
+            }
+
+            // In Chess960 the king may move in either direction, or not at all
+            let direction: fn(SQ) -> SQ = if k_to > ksq {
                 |x: SQ| x - SQ(1)
             } else {
                 |x: SQ| x + SQ(1)
@@ -396,13 +697,25 @@ where
             // Loop through all the squares the king goes through
             // If any enemies attack that square, cannot castle
             'outer: while s != ksq {
-                let attackers: BitBoard = self.board.attackers_to(s, self.occ) & enemies;
-                if attackers.is_not_empty() {
+                let occupied: BitBoard = if s == k_to { k_to_occ } else { occ };
+                let attackers: BitBoard = self.board.attackers_to(s, occupied) & enemies;
+                if attackers.is_not_empty() && (safe & s.to_bb()).is_empty() {
                     can_castle = false;
                     break 'outer;
                 }
                 s = direction(s);
             }
+
+            // The castling rook may have been shielding the king's destination from a
+            // rook or queen on the back rank
+            if can_castle
+                && !atomic
+                && (rook_moves(self.occ ^ r_from.to_bb(), k_to)
+                    & self.board.sliding_piece_bb(P::opp_player()))
+                .is_not_empty()
+            {
+                can_castle = false;
+            }
             if can_castle {
                 self.check_and_add::<L>(BitMove::init(PreMoveInfo {
                     src: ksq,
@@ -423,7 +736,8 @@ where
 
     // Get the captures and non-captures for a piece
     fn moves_per_piece<L: Legality, PL: PlayerTrait, P: PieceTrait>(&mut self, target: BitBoard) {
-        let mut piece_bb: BitBoard = self.board.piece_bb(PL::player(), P::piece_type());
+        let mut piece_bb: BitBoard =
+            self.board.piece_bb(PL::player(), P::piece_type()) & self.src_mask;
         while let Some(src) = piece_bb.pop_some_lsb() {
             let moves_bb: BitBoard = self.moves_bb2::<P>(src) & !self.us_occ & target;
             let mut captures_bb: BitBoard = moves_bb & self.them_occ;
@@ -438,14 +752,27 @@ where
         &mut self,
         target: BitBoard,
     ) {
-        let (rank_8, rank_7, rank_3): (BitBoard, BitBoard, BitBoard) =
+        let (rank_8, rank_7, rank_3, rank_2): (BitBoard, BitBoard, BitBoard, BitBoard) =
             if P::player() == Player::White {
-                (BitBoard::RANK_8, BitBoard::RANK_7, BitBoard::RANK_3)
+                (
+                    BitBoard::RANK_8,
+                    BitBoard::RANK_7,
+                    BitBoard::RANK_3,
+                    BitBoard::RANK_2,
+                )
             } else {
-                (BitBoard::RANK_1, BitBoard::RANK_2, BitBoard::RANK_6)
+                (
+                    BitBoard::RANK_1,
+                    BitBoard::RANK_2,
+                    BitBoard::RANK_6,
+                    BitBoard::RANK_7,
+                )
             };
 
-        let all_pawns: BitBoard = self.board.piece_bb(P::player(), PieceType::P);
+        // Pawns may only be on the first rank in Horde, where they can also double push
+        let double_push_ranks = rank_3 | rank_2;
+
+        let all_pawns: BitBoard = self.board.piece_bb(P::player(), PieceType::P) & self.src_mask;
 
         let mut empty_squares = BitBoard(0);
 
@@ -458,7 +785,7 @@ where
         } else if G::gen_type() == GenTypes::Captures {
             target
         } else {
-            self.them_occ
+            self.them_occ & self.dst_mask
         };
 
         // Single and Double Pawn Pushes
@@ -467,11 +794,14 @@ where
                 if G::gen_type() == GenTypes::Quiets || G::gen_type() == GenTypes::QuietChecks {
                     target
                 } else {
-                    !self.board.occupied()
+                    !self.board.occupied() & self.dst_mask
                 };
 
-            let mut push_one: BitBoard = empty_squares & P::shift_up(pawns_not_rank_7);
-            let mut push_two: BitBoard = P::shift_up(push_one & rank_3) & empty_squares;
+            // The square a pawn double pushes over only needs to be empty, not a destination
+            let single_pushes: BitBoard = P::shift_up(pawns_not_rank_7) & !self.occ;
+            let mut push_one: BitBoard = empty_squares & single_pushes;
+            let mut push_two: BitBoard =
+                P::shift_up(single_pushes & double_push_ranks) & empty_squares;
 
             if G::gen_type() == GenTypes::Evasions {
                 push_one &= target;
@@ -485,10 +815,10 @@ where
 
                 let dc_candidates: BitBoard = self.board.discovered_check_candidates();
                 if (pawns_not_rank_7 & dc_candidates).is_not_empty() {
-                    let dc1: BitBoard = P::shift_up(pawns_not_rank_7 & dc_candidates)
-                        & empty_squares
-                        & !ksq.file_bb();
-                    let dc2: BitBoard = P::shift_up(rank_3 & dc1) & empty_squares;
+                    let dc_pushes: BitBoard =
+                        P::shift_up(pawns_not_rank_7 & dc_candidates) & !self.occ & !ksq.file_bb();
+                    let dc1: BitBoard = dc_pushes & empty_squares;
+                    let dc2: BitBoard = P::shift_up(double_push_ranks & dc_pushes) & empty_squares;
 
                     push_one |= dc1;
                     push_two |= dc2;
@@ -512,7 +842,7 @@ where
             && (G::gen_type() != GenTypes::Evasions || (target & rank_8).is_not_empty())
         {
             if G::gen_type() == GenTypes::Captures {
-                empty_squares = !self.occ;
+                empty_squares = !self.occ & self.dst_mask;
             } else if G::gen_type() == GenTypes::Evasions {
                 empty_squares &= target;
             }
@@ -562,8 +892,9 @@ where
                 // An en passant capture can be an evasion only if the checking piece
                 // is the double pushed pawn and so is in the target. Otherwise this
                 // is a discovery check and we are forced to do otherwise.
-                if G::gen_type() != GenTypes::Evasions
-                    || (target & P::down(ep_sq).to_bb()).is_not_empty()
+                if (G::gen_type() != GenTypes::Evasions
+                    || (target & P::down(ep_sq).to_bb()).is_not_empty())
+                    && (self.dst_mask & ep_sq.to_bb()).is_not_empty()
                 {
                     left_cap = pawns_not_rank_7 & pawn_attacks_from(ep_sq, P::opp_player());
 
@@ -581,6 +912,9 @@ where
         self.check_and_add::<L>(BitMove::make(BitMove::FLAG_PROMO_B, src, dst));
         self.check_and_add::<L>(BitMove::make(BitMove::FLAG_PROMO_R, src, dst));
         self.check_and_add::<L>(BitMove::make(BitMove::FLAG_PROMO_Q, src, dst));
+        if self.board.variant() == Variant::Antichess {
+            self.check_and_add::<L>(BitMove::make_king_promotion(src, dst));
+        }
     }
 
     #[inline]
@@ -589,6 +923,9 @@ where
         self.check_and_add::<L>(BitMove::make(BitMove::FLAG_PROMO_CAP_B, src, dst));
         self.check_and_add::<L>(BitMove::make(BitMove::FLAG_PROMO_CAP_R, src, dst));
         self.check_and_add::<L>(BitMove::make(BitMove::FLAG_PROMO_CAP_Q, src, dst));
+        if self.board.variant() == Variant::Antichess {
+            self.check_and_add::<L>(BitMove::make_king_promotion(src, dst));
+        }
     }
 
     // Return the moves Bitboard
@@ -651,12 +988,196 @@ where
     }
 }
 
+/// The stages of move generation of a `StagedMoves`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveStage {
+    /// The move given to try first, if it's legal.
+    TTMove,
+    /// Captures and promotions.
+    Captures,
+    /// Moves that neither capture nor promote.
+    Quiets,
+    /// Moves out of check, generated all at once.
+    Evasions,
+    /// Every move has been generated.
+    Done,
+}
+
+/// Lazily generates the legal moves of a `Board` in stages: a move given to try first, then
+/// captures and promotions, then quiet moves. When in check, the moves out of check make up a
+/// single stage instead. Each stage is only generated once the moves of the stage before it
+/// have run out, and the move tried first isn't repeated in later stages.
+///
+/// A `StagedMoves` doesn't borrow the `Board`, so moves can be applied and undone between calls
+/// to `StagedMoves::next_move()`, as a search does. The board must be back in the position the
+/// `StagedMoves` was created for whenever the next move is asked for.
+/// `StagedMoves::iter()` wraps it into an `Iterator` for when the board isn't modified.
+///
+/// # Examples
+///
+/// ```
+/// use pleco::{Board,BitMove};
+/// use pleco::board::movegen::StagedMoves;
+///
+/// let mut board = Board::start_pos();
+/// let tt_move = BitMove::make_pawn_push(pleco::SQ::E2, pleco::SQ::E4);
+/// let mut staged = StagedMoves::new(&board, tt_move);
+///
+/// let mut count = 0;
+/// while let Some(mov) = staged.next_move(&board) {
+///     if count == 0 {
+///         assert_eq!(mov, tt_move);
+///     }
+///     board.apply_move(mov);
+///     board.undo_move();
+///     count += 1;
+/// }
+/// assert_eq!(count, 20);
+/// ```
+pub struct StagedMoves {
+    stage: MoveStage,
+    generated: bool,
+    tt_move: BitMove,
+    // Has room for the moves of any board, as the board is given again for each move
+    moves: CrazyhouseMoveList,
+    index: usize,
+}
+
+impl StagedMoves {
+    /// Creates a `StagedMoves` for the position of a `Board`, which starts with `tt_move` if it's
+    /// legal. `BitMove::null()` can be given to start with the captures instead.
+    pub fn new(board: &Board, tt_move: BitMove) -> StagedMoves {
+        let legal_tt_move =
+            !tt_move.is_null() && board.pseudo_legal_move(tt_move) && board.legal_move(tt_move);
+        StagedMoves {
+            stage: MoveStage::TTMove,
+            generated: false,
+            tt_move: if legal_tt_move {
+                tt_move
+            } else {
+                BitMove::null()
+            },
+            moves: CrazyhouseMoveList::with_crazyhouse_capacity(),
+            index: 0,
+        }
+    }
+
+    /// Returns the stage of the last move returned.
+    #[inline]
+    pub fn stage(&self) -> MoveStage {
+        self.stage
+    }
+
+    /// Returns the next legal move, or `None` once every move has been returned.
+    ///
+    /// The board must be in the same position the `StagedMoves` was created for.
+    pub fn next_move(&mut self, board: &Board) -> Option<BitMove> {
+        loop {
+            match self.stage {
+                MoveStage::TTMove => {
+                    if !self.generated && !self.tt_move.is_null() {
+                        self.generated = true;
+                        return Some(self.tt_move);
+                    }
+                    self.generated = false;
+                    self.stage = if board.in_check() {
+                        MoveStage::Evasions
+                    } else {
+                        MoveStage::Captures
+                    };
+                }
+                MoveStage::Done => return None,
+                stage => {
+                    if !self.generated {
+                        // The list has room for the moves of any board
+                        unsafe {
+                            match stage {
+                                MoveStage::Captures => {
+                                    MoveGen::generate_into::<Legal, CapturesGenType, _>(
+                                        board,
+                                        &mut self.moves,
+                                    )
+                                }
+                                MoveStage::Quiets => {
+                                    MoveGen::generate_into::<Legal, QuietsGenType, _>(
+                                        board,
+                                        &mut self.moves,
+                                    )
+                                }
+                                _ => MoveGen::generate_into::<Legal, EvasionsGenType, _>(
+                                    board,
+                                    &mut self.moves,
+                                ),
+                            }
+                        }
+                        self.generated = true;
+                        self.index = 0;
+                    }
+
+                    while self.index < self.moves.len() {
+                        let mov = self.moves[self.index];
+                        self.index += 1;
+                        if mov != self.tt_move {
+                            return Some(mov);
+                        }
+                    }
+
+                    self.generated = false;
+                    self.stage = if stage == MoveStage::Captures {
+                        MoveStage::Quiets
+                    } else {
+                        MoveStage::Done
+                    };
+                }
+            }
+        }
+    }
+
+    /// Turns the `StagedMoves` into an `Iterator` over the moves of a board, which must be in
+    /// the same position the `StagedMoves` was created for.
+    pub fn iter<'a>(self, board: &'a Board) -> StagedMovesIter<'a> {
+        StagedMovesIter { board, moves: self }
+    }
+}
+
+/// An `Iterator` over the moves of a `StagedMoves`, created by `StagedMoves::iter()` or
+/// `Board::staged_moves()`.
+pub struct StagedMovesIter<'a> {
+    board: &'a Board,
+    moves: StagedMoves,
+}
+
+impl<'a> StagedMovesIter<'a> {
+    /// Returns the stage of the last move returned.
+    #[inline]
+    pub fn stage(&self) -> MoveStage {
+        self.moves.stage()
+    }
+}
+
+impl<'a> Iterator for StagedMovesIter<'a> {
+    type Item = BitMove;
+
+    #[inline]
+    fn next(&mut self) -> Option<BitMove> {
+        self.moves.next_move(self.board)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Legal, MoveGen};
+    use std::panic;
+
+    use super::{Legal, MoveGen, MoveStage, StagedMoves};
     use board::fen::ALL_FENS;
+    use board::variant::{Variant, ALL_VARIANTS};
     use board::Board;
-    use core::mono_traits::AllGenType;
+    use core::mono_traits::{AllGenType, CapturesGenType};
+    use core::move_list::{MoveList, MAX_MOVES};
+    use core::piece_move::BitMove;
+    use core::sq::SQ;
+    use core::PieceType;
+    use tools::prng::PRNG;
 
     #[test]
     fn movegen_legal_pseudo() {
@@ -718,4 +1239,239 @@ mod tests {
             iter.for_each(|(mb, ms)| assert_eq!(*mb, ms.bit_move));
         });
     }
+
+    fn assert_staged_matches(b: &Board, tt_move: BitMove) {
+        let all = b.generate_moves();
+        let staged: Vec<BitMove> = StagedMoves::new(b, tt_move).iter(b).collect();
+        assert_eq!(all.len(), staged.len(), "fen: {}", b.fen());
+        for (i, mov) in staged.iter().enumerate() {
+            assert!(all.contains(mov), "fen: {}, move: {}", b.fen(), mov);
+            assert!(
+                !staged[i + 1..].contains(mov),
+                "fen: {}, move: {}",
+                b.fen(),
+                mov
+            );
+        }
+        if all.contains(&tt_move) {
+            assert_eq!(staged[0], tt_move);
+        }
+    }
+
+    #[test]
+    fn movegen_staged_all_fens() {
+        for fen in ALL_FENS.iter() {
+            let b = Board::from_fen(fen).unwrap();
+            assert_staged_matches(&b, BitMove::null());
+            for mov in b.generate_moves().iter() {
+                assert_staged_matches(&b, *mov);
+            }
+            assert_staged_matches(&b, b.generate_pseudolegal_moves()[0]);
+        }
+    }
+
+    #[test]
+    fn movegen_staged_random() {
+        let boards = Board::random().pseudo_random(2627288300002).many(20);
+        for b in boards.iter() {
+            let moves = b.generate_moves();
+            assert_staged_matches(b, BitMove::null());
+            if !moves.is_empty() {
+                assert_staged_matches(b, moves[moves.len() - 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn movegen_staged_variants() {
+        let mut prng = PRNG::init(0x1C7C_4D3F);
+        for variant in ALL_VARIANTS.iter() {
+            for _ in 0..4 {
+                let mut b = Board::start_pos_variant(*variant);
+                for _ in 0..80 {
+                    let moves = b.generate_moves();
+                    if moves.is_empty() || b.variant_outcome().is_some() {
+                        break;
+                    }
+                    let mov = moves[prng.rand() as usize % moves.len()];
+                    assert_staged_matches(&b, BitMove::null());
+                    assert_staged_matches(&b, mov);
+                    b.apply_move(mov);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn movegen_staged_stages() {
+        let b = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let quiet = b
+            .generate_moves()
+            .iter()
+            .find(|m| !m.is_capture())
+            .cloned()
+            .unwrap();
+        let mut staged = StagedMoves::new(&b, quiet);
+        assert_eq!(staged.next_move(&b), Some(quiet));
+        assert_eq!(staged.stage(), MoveStage::TTMove);
+        assert!(staged.next_move(&b).unwrap().is_capture());
+        assert_eq!(staged.stage(), MoveStage::Captures);
+        assert!(!staged.next_move(&b).unwrap().is_capture());
+        assert_eq!(staged.stage(), MoveStage::Quiets);
+        while staged.next_move(&b).is_some() {}
+        assert_eq!(staged.stage(), MoveStage::Done);
+        assert_eq!(staged.next_move(&b), None);
+
+        let check = Board::from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+        let mut staged = StagedMoves::new(&check, BitMove::null());
+        assert!(staged.next_move(&check).is_some());
+        assert_eq!(staged.stage(), MoveStage::Evasions);
+    }
+
+    fn assert_masked_matches(b: &Board) {
+        let all = b.generate_moves();
+        for sq in 0..64 {
+            let sq = SQ(sq);
+            let from: Vec<BitMove> = all
+                .iter()
+                .filter(|m| !m.is_drop() && m.get_src() == sq)
+                .cloned()
+                .collect();
+            let to: Vec<BitMove> = all.iter().filter(|m| m.get_dest() == sq).cloned().collect();
+            assert_eq!(
+                b.generate_moves_from(sq).vec(),
+                from,
+                "fen: {}, sq: {}",
+                b.fen(),
+                sq
+            );
+            assert_eq!(
+                b.generate_moves_to(sq).vec(),
+                to,
+                "fen: {}, sq: {}",
+                b.fen(),
+                sq
+            );
+        }
+        for piece in [
+            PieceType::P,
+            PieceType::N,
+            PieceType::B,
+            PieceType::R,
+            PieceType::Q,
+            PieceType::K,
+        ]
+        .iter()
+        {
+            let of_piece: Vec<BitMove> = all
+                .iter()
+                .filter(|m| !m.is_drop() && b.piece_at_sq(m.get_src()).type_of() == *piece)
+                .cloned()
+                .collect();
+            assert_eq!(b.generate_moves_of_piece(*piece).vec(), of_piece);
+        }
+    }
+
+    #[test]
+    fn movegen_masked() {
+        for fen in ALL_FENS.iter() {
+            assert_masked_matches(&Board::from_fen(fen).unwrap());
+        }
+        for b in Board::random().pseudo_random(2627288300002).many(20).iter() {
+            assert_masked_matches(b);
+        }
+    }
+
+    #[test]
+    fn movegen_masked_variants() {
+        let mut prng = PRNG::init(0x5EED_F00D);
+        for variant in ALL_VARIANTS.iter() {
+            let mut b = Board::start_pos_variant(*variant);
+            for _ in 0..100 {
+                assert_masked_matches(&b);
+                let moves = b.generate_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                b.apply_move(moves[prng.rand() as usize % moves.len()]);
+            }
+        }
+    }
+
+    #[test]
+    fn movegen_masked_pins_and_checks() {
+        // The knight on d2 is pinned by the bishop on b4
+        let b = Board::from_fen("4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1").unwrap();
+        assert!(b.generate_moves_from(SQ::D2).is_empty());
+        assert!(b.generate_moves_of_piece(PieceType::N).is_empty());
+        assert_eq!(b.generate_moves_to(SQ::D1).len(), 1);
+
+        // The queen on a5 gives check, which the pawn can only block with a double push
+        let b = Board::from_fen("4k3/8/8/q7/8/8/1P6/4K1NR w - - 0 1").unwrap();
+        assert!(b.generate_moves_to(SQ::B3).is_empty());
+        assert!(b.generate_moves_to(SQ::F3).is_empty());
+        let moves = b.generate_moves_from(SQ::B2);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].get_dest(), SQ::B4);
+        assert_eq!(b.generate_moves_to(SQ::B4).vec(), moves.vec());
+    }
+
+    #[test]
+    fn movegen_into() {
+        let mut list = MoveList::default();
+        for fen in ALL_FENS.iter() {
+            let b = Board::from_fen(fen).unwrap();
+            unsafe { b.generate_moves_into(&mut list) };
+            assert_eq!(list.vec(), b.generate_moves().vec());
+            if !b.in_check() {
+                unsafe { MoveGen::generate_into::<Legal, CapturesGenType, _>(&b, &mut list) };
+                assert_eq!(
+                    list.vec(),
+                    MoveGen::generate::<Legal, CapturesGenType>(&b).vec()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn movegen_crazyhouse_capacity() {
+        // Drops give this position more moves than fit in a standard list
+        let fen = "k7/8/8/8/8/8/8/K6Q[QRBNP] w - - 0 1";
+        let b = Board::from_fen_variant(fen, Variant::Crazyhouse).unwrap();
+        let moves = b.generate_crazyhouse_moves();
+        assert!(moves.len() > MAX_MOVES);
+        assert_eq!(moves.iter().filter(|m| m.is_drop()).count(), 4 * 61 + 48);
+        assert_eq!(b.staged_moves().count(), moves.len());
+
+        let mut list = MoveList::with_crazyhouse_capacity();
+        unsafe { b.generate_moves_into(&mut list) };
+        assert_eq!(list.vec(), moves.vec());
+
+        // They don't fit in the lists with room for `MAX_MOVES` moves
+        assert!(panic::catch_unwind(|| b.generate_moves()).is_err());
+        assert!(panic::catch_unwind(|| b.generate_scoring_moves()).is_err());
+
+        // Positions with fewer moves still fit
+        let fen = "k7/8/8/8/8/8/8/K6Q[N] w - - 0 1";
+        let b = Board::from_fen_variant(fen, Variant::Crazyhouse).unwrap();
+        assert_eq!(
+            b.generate_moves().vec(),
+            b.generate_crazyhouse_moves().vec()
+        );
+        assert_eq!(b.generate_scoring_moves().len(), b.generate_moves().len());
+    }
+
+    #[test]
+    fn staged_crazyhouse_board() {
+        // A `StagedMoves` created for a standard board must still fit a Crazyhouse board's moves
+        let fen = "k7/8/8/8/8/8/8/K6Q[QRBNP] w - - 0 1";
+        let b = Board::from_fen_variant(fen, Variant::Crazyhouse).unwrap();
+        let mut staged = StagedMoves::new(&Board::start_pos(), BitMove::null());
+        let mut moves = Vec::new();
+        while let Some(mov) = staged.next_move(&b) {
+            moves.push(mov);
+        }
+        assert_eq!(moves.len(), b.generate_crazyhouse_moves().len());
+        assert!(moves.len() > MAX_MOVES);
+    }
 }
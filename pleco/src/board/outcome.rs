@@ -0,0 +1,327 @@
+//! Contains functions for deciding if a game has ended, and why.
+//!
+//! A game is only considered over when the rules end it automatically: checkmate, stalemate,
+//! insufficient material, fivefold repetition, or the seventy-five move rule. Threefold repetition
+//! and the fifty move rule only allow a player to *claim* a draw, and are left to
+//! [`Board::threefold_repetition`] and [`Board::fifty_move_rule`].
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::Board;
+//! use pleco::board::outcome::{Outcome, Termination};
+//! use pleco::Player;
+//!
+//! let mut board = Board::start_pos();
+//! for mov in ["f2f3", "e7e5", "g2g4", "d8h4"].iter() {
+//!     assert!(board.outcome().is_none());
+//!     board.apply_uci_move(mov);
+//! }
+//!
+//! let outcome = board.outcome().unwrap();
+//! assert_eq!(outcome.winner(), Some(Player::Black));
+//! assert_eq!(outcome.termination(), Termination::Checkmate);
+//! assert_eq!(outcome.to_string(), "0-1");
+//! ```
+//!
+//! [`Board::threefold_repetition`]: ../struct.Board.html#method.threefold_repetition
+//! [`Board::fifty_move_rule`]: ../struct.Board.html#method.fifty_move_rule
+
+use std::fmt;
+
+use super::variant::Variant;
+use super::Board;
+use core::bitboard::BitBoard;
+use core::masks::{PIECE_TYPE_CNT, PLAYER_CNT};
+use core::{PieceType, Player};
+
+/// The reason a game has ended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Termination {
+    /// The side to move is in check, and has no legal moves.
+    Checkmate,
+    /// The side to move is not in check, but has no legal moves.
+    Stalemate,
+    /// Neither side has enough material left to deliver checkmate.
+    InsufficientMaterial,
+    /// The same position has occurred five times.
+    FivefoldRepetition,
+    /// Seventy-five moves have been made by each side without a capture or pawn move.
+    SeventyFiveMoves,
+    /// A win condition of the `Board`'s variant has been reached, such as a third check.
+    VariantWin,
+    /// The `Board`'s variant has drawn the game, as when both kings finish a race.
+    VariantDraw,
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Termination::Checkmate => "checkmate",
+            Termination::Stalemate => "stalemate",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::FivefoldRepetition => "fivefold repetition",
+            Termination::SeventyFiveMoves => "seventy-five move rule",
+            Termination::VariantWin => "variant win",
+            Termination::VariantDraw => "variant draw",
+        })
+    }
+}
+
+/// The result of a finished game.
+///
+/// Displays as the result token used by PGN, e.g. `1-0` or `1/2-1/2`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The game was won by `winner`.
+    Decisive {
+        winner: Player,
+        termination: Termination,
+    },
+    /// The game was drawn.
+    Draw { termination: Termination },
+}
+
+impl Outcome {
+    /// Returns the winning player, or `None` if the game was drawn.
+    pub fn winner(&self) -> Option<Player> {
+        match *self {
+            Outcome::Decisive { winner, .. } => Some(winner),
+            Outcome::Draw { .. } => None,
+        }
+    }
+
+    /// Returns the reason the game ended.
+    pub fn termination(&self) -> Termination {
+        match *self {
+            Outcome::Decisive { termination, .. } | Outcome::Draw { termination } => termination,
+        }
+    }
+
+    /// Returns if the game was drawn.
+    pub fn is_draw(&self) -> bool {
+        self.winner().is_none()
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self.winner() {
+            Some(Player::White) => "1-0",
+            Some(Player::Black) => "0-1",
+            None => "1/2-1/2",
+        })
+    }
+}
+
+impl Board {
+    /// Returns the `Outcome` of the game if it is over, or `None` if it can continue.
+    ///
+    /// Only automatic endings are detected. The variant's own rules are checked first, then
+    /// checkmate, so a move that both mates and reaches the seventy-five move limit is a win.
+    ///
+    /// This method can be computationally expensive, do not use outside of Engines.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if let Some(outcome) = self.variant_outcome() {
+            return Some(outcome);
+        }
+
+        if self.with_legal_moves(|moves| moves.is_empty()) {
+            return Some(if self.variant() == Variant::Antichess {
+                // A player without a legal move wins Antichess
+                Outcome::Decisive {
+                    winner: self.turn(),
+                    termination: Termination::VariantWin,
+                }
+            } else if self.in_check() {
+                Outcome::Decisive {
+                    winner: !self.turn(),
+                    termination: Termination::Checkmate,
+                }
+            } else {
+                Outcome::Draw {
+                    termination: Termination::Stalemate,
+                }
+            });
+        }
+
+        let termination = if self.insufficient_material() {
+            Termination::InsufficientMaterial
+        } else if self.seventy_five_move_rule() {
+            Termination::SeventyFiveMoves
+        } else if self.fivefold_repetition() {
+            Termination::FivefoldRepetition
+        } else {
+            return None;
+        };
+        Some(Outcome::Draw { termination })
+    }
+
+    /// Returns if neither player can possibly checkmate.
+    ///
+    /// This is the case for King versus King, King and a single minor piece versus King, and
+    /// positions where the only remaining pieces other than Kings are Bishops on squares of the
+    /// same color.
+    ///
+    /// In Three-check, any piece other than a King can give check, so only King versus King is
+    /// insufficient. Kings can still win King of the Hill and Racing Kings, and a Horde game
+    /// always has a way to be won, so material is never insufficient for those variants. In
+    /// Crazyhouse, captured pieces come back into play, so only bare Kings with empty pockets
+    /// are insufficient. Atomic Kings are exploded rather than mated, so only bare
+    /// Kings are treated as insufficient there too. In Antichess, the only insufficient material
+    /// is each player having only Bishops, on squares of a different color than the other's.
+    pub fn insufficient_material(&self) -> bool {
+        match self.variant() {
+            Variant::Standard => {}
+            Variant::Antichess => {
+                let bishops = self.piece_bb_both_players(PieceType::B);
+                let white = self.occupied_white();
+                let black = self.occupied_black();
+                return self.occupied() == bishops
+                    && white.is_not_empty()
+                    && black.is_not_empty()
+                    && (((white & BitBoard::DARK_SQUARES).is_empty()
+                        && (black & BitBoard::LIGHT_SQUARES).is_empty())
+                        || ((white & BitBoard::LIGHT_SQUARES).is_empty()
+                            && (black & BitBoard::DARK_SQUARES).is_empty()));
+            }
+            Variant::ThreeCheck | Variant::Atomic => {
+                return self.occupied() == self.piece_bb_both_players(PieceType::K);
+            }
+            Variant::Crazyhouse => {
+                return self.occupied() == self.piece_bb_both_players(PieceType::K)
+                    && self.state.pockets == [[0; PIECE_TYPE_CNT]; PLAYER_CNT];
+            }
+            Variant::KingOfTheHill | Variant::RacingKings | Variant::Horde => return false,
+        }
+
+        let heavy = self.piece_bb_both_players(PieceType::P)
+            | self.piece_bb_both_players(PieceType::R)
+            | self.piece_bb_both_players(PieceType::Q);
+        if heavy.is_not_empty() {
+            return false;
+        }
+
+        let knights = self.piece_bb_both_players(PieceType::N);
+        let bishops = self.piece_bb_both_players(PieceType::B);
+        if knights.is_empty() {
+            (bishops & BitBoard::LIGHT_SQUARES).is_empty()
+                || (bishops & BitBoard::DARK_SQUARES).is_empty()
+        } else {
+            bishops.is_empty() && knights.count_bits() == 1
+        }
+    }
+
+    /// Returns if the current position has occurred five times, ending the game in a draw.
+    pub fn fivefold_repetition(&self) -> bool {
+        self.state.repetitions() >= 4
+    }
+
+    /// Returns if seventy-five moves have been made by each player without a capture or pawn
+    /// move, ending the game in a draw.
+    pub fn seventy_five_move_rule(&self) -> bool {
+        self.rule_50() >= 150
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(fen: &str) -> Option<Outcome> {
+        Board::from_fen(fen).unwrap().outcome()
+    }
+
+    fn draw(termination: Termination) -> Option<Outcome> {
+        Some(Outcome::Draw { termination })
+    }
+
+    #[test]
+    fn checkmate_and_stalemate() {
+        assert_eq!(outcome("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1"), None);
+        let mate = outcome("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert_eq!(mate.winner(), Some(Player::White));
+        assert_eq!(mate.termination(), Termination::Checkmate);
+        assert_eq!(mate.to_string(), "1-0");
+
+        assert_eq!(
+            outcome("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            draw(Termination::Stalemate)
+        );
+        // checkmate takes precedence over the seventy-five move rule
+        let mate = outcome("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 120").unwrap();
+        assert_eq!(mate.termination(), Termination::Checkmate);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let insufficient = [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/8/5N2 w - - 0 1",
+            "8/8/4k3/8/8/3K4/8/5b2 b - - 0 1",
+            "2b5/8/4k3/8/8/3K4/8/5B2 w - - 0 1",
+            "2b1b3/8/4k3/8/8/3K4/8/5B2 w - - 0 1",
+        ];
+        for fen in insufficient.iter() {
+            assert_eq!(
+                outcome(fen),
+                draw(Termination::InsufficientMaterial),
+                "{}",
+                fen
+            );
+        }
+
+        let sufficient = [
+            "8/8/4k3/8/8/3K4/8/4NN2 w - - 0 1",
+            "8/8/4k3/8/8/3K4/8/4bN2 w - - 0 1",
+            "1b6/8/4k3/8/8/3K4/8/5B2 w - - 0 1",
+            "8/8/4k3/8/8/3K4/4P3/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/8/4R3 w - - 0 1",
+        ];
+        for fen in sufficient.iter() {
+            assert_eq!(outcome(fen), None, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn seventy_five_moves() {
+        let fen = "8/8/4k3/8/8/3K4/8/4R3 w - - 149 100";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert!(board.fifty_move_rule());
+        assert_eq!(board.outcome(), None);
+        board.apply_uci_move("e1e2");
+        assert_eq!(board.outcome(), draw(Termination::SeventyFiveMoves));
+    }
+
+    #[test]
+    fn fivefold_repetition() {
+        let mut board = Board::start_pos();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        for _ in 0..3 {
+            for mov in shuffle.iter() {
+                board.apply_uci_move(mov);
+            }
+        }
+        assert!(board.threefold_repetition());
+        assert!(board.shallow_clone().threefold_repetition());
+        assert_eq!(board.outcome(), None);
+
+        for _ in 0..5 {
+            board.undo_move();
+        }
+        assert!(!board.threefold_repetition());
+        board.apply_uci_move("f6g8");
+        for mov in shuffle.iter() {
+            board.apply_uci_move(mov);
+        }
+
+        for mov in shuffle.iter() {
+            board.apply_uci_move(mov);
+        }
+        assert!(board.fivefold_repetition());
+        assert_eq!(board.outcome(), draw(Termination::FivefoldRepetition));
+
+        board.undo_move();
+        assert!(!board.fivefold_repetition());
+    }
+}
@@ -2,9 +2,35 @@
 //!
 //! Use these functions on a [`Board`] to test that the correct amount of leaf nodes are created.
 //!
+//! Leaf nodes are bulk counted: at the last ply, the number of legal moves is used directly instead
+//! of playing each one. For deeper searches, [`perft_parallel`] splits the root moves across threads,
+//! and [`perft_hashed`] caches the counts of transposed subtrees in a [`PerftTable`].
+//! [`perft_divide`] gives the count below each root move, which is useful for finding the move a
+//! move-generation bug is hiding under.
+//!
+//! Positions with known node counts can be checked in bulk with [`run_perft_suite`], which reads
+//! the common `perftsuite.epd` format. Once a failing position is known, [`find_divergence`]
+//! compares divides against a reference, such as another engine, to find the smallest subtree
+//! where the move lists disagree.
+//!
 //! [`Board`]: ../struct.Board.html
+//! [`perft_parallel`]: fn.perft_parallel.html
+//! [`perft_hashed`]: fn.perft_hashed.html
+//! [`PerftTable`]: struct.PerftTable.html
+//! [`perft_divide`]: fn.perft_divide.html
+//! [`run_perft_suite`]: fn.run_perft_suite.html
+//! [`find_divergence`]: fn.find_divergence.html
+
+use std::fmt;
 
-use super::{Board, MoveList};
+use rayon::prelude::*;
+
+use super::movegen::{Legal, MoveGen};
+use super::variant::Variant;
+use super::Board;
+use core::mono_traits::AllGenType;
+use core::move_list::{MoveList, MAX_CRAZYHOUSE_MOVES, MAX_MOVES};
+use core::piece_move::BitMove;
 
 /// Holds all information about the number of nodes counted.
 pub struct PerftNodes {
@@ -100,7 +126,7 @@ pub fn perft(board: &Board, depth: u16) -> u64 {
         1
     } else {
         let mut pos = board.shallow_clone();
-        inner_perft(&mut pos, depth)
+        count_leaves(&mut pos, depth)
     }
 }
 
@@ -108,12 +134,370 @@ pub fn perft(board: &Board, depth: u16) -> u64 {
 pub fn perft_all(board: &Board, depth: u16) -> PerftNodes {
     let mut b = board.shallow_clone();
     let mut perft = PerftNodes::default();
-    inner_perft_all(&mut b, depth, &mut perft);
+    if b.variant() == Variant::Crazyhouse {
+        inner_perft_all::<MAX_CRAZYHOUSE_MOVES>(&mut b, depth, &mut perft);
+    } else {
+        inner_perft_all::<MAX_MOVES>(&mut b, depth, &mut perft);
+    }
     perft
 }
 
-fn inner_perft(board: &mut Board, depth: u16) -> u64 {
-    let moves: MoveList = board.generate_moves();
+/// Returns the number of leaf nodes below each legal move, in the order the moves are generated.
+///
+/// The counts sum up to `perft(board, depth)`. A `depth` of zero returns no moves.
+pub fn perft_divide(board: &Board, depth: u16) -> Vec<(BitMove, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    board.with_legal_moves(|moves| {
+        let mut pos = board.shallow_clone();
+        moves
+            .iter()
+            .map(|&mov| {
+                pos.apply_move(mov);
+                let count = if depth == 1 {
+                    1
+                } else {
+                    count_leaves(&mut pos, depth - 1)
+                };
+                pos.undo_move();
+                (mov, count)
+            })
+            .collect()
+    })
+}
+
+/// Returns the number of leaf nodes from generating moves to a certain depth, searching the
+/// moves at the root in parallel.
+pub fn perft_parallel(board: &Board, depth: u16) -> u64 {
+    if depth <= 1 {
+        return perft(board, depth);
+    }
+    board.with_legal_moves(|moves| {
+        moves
+            .par_iter()
+            .map(|&mov| {
+                let mut pos = board.parallel_clone();
+                pos.apply_move(mov);
+                count_leaves(&mut pos, depth - 1)
+            })
+            .sum()
+    })
+}
+
+#[derive(Copy, Clone, Default)]
+struct PerftEntry {
+    key: u64,
+    depth: u16,
+    nodes: u64,
+}
+
+/// A table caching the number of leaf nodes below a position, keyed by the position's zobrist
+/// key and the remaining depth.
+///
+/// A table can be reused between calls to [`perft_hashed`], even for different positions.
+/// Entries are always replaced on a collision, and as with any zobrist-keyed table, two positions
+/// sharing a key could give a wrong count.
+///
+/// [`perft_hashed`]: fn.perft_hashed.html
+pub struct PerftTable {
+    entries: Vec<PerftEntry>,
+}
+
+impl PerftTable {
+    /// Creates a table with at least `num_entries` entries, rounded up to a power of two.
+    pub fn new(num_entries: usize) -> PerftTable {
+        PerftTable {
+            entries: vec![PerftEntry::default(); num_entries.max(1).next_power_of_two()],
+        }
+    }
+
+    /// Creates a table taking up about `mb` megabytes.
+    pub fn with_megabytes(mb: usize) -> PerftTable {
+        let entry_size = ::std::mem::size_of::<PerftEntry>();
+        PerftTable::new((mb * 1024 * 1024 / entry_size).max(1).next_power_of_two() / 2)
+    }
+
+    /// Returns the number of entries in the table.
+    pub fn num_entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Removes every entry from the table.
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter_mut() {
+            *entry = PerftEntry::default();
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    fn probe(&self, key: u64, depth: u16) -> Option<u64> {
+        let entry = &self.entries[self.index(key)];
+        if entry.key == key && entry.depth == depth {
+            Some(entry.nodes)
+        } else {
+            None
+        }
+    }
+
+    fn store(&mut self, key: u64, depth: u16, nodes: u64) {
+        let index = self.index(key);
+        self.entries[index] = PerftEntry { key, depth, nodes };
+    }
+}
+
+/// Returns the number of leaf nodes from generating moves to a certain depth, using `table` to
+/// skip subtrees that have already been counted.
+pub fn perft_hashed(board: &Board, depth: u16, table: &mut PerftTable) -> u64 {
+    if depth == 0 {
+        1
+    } else {
+        let mut pos = board.shallow_clone();
+        if pos.variant() == Variant::Crazyhouse {
+            inner_perft_hashed::<MAX_CRAZYHOUSE_MOVES>(&mut pos, depth, table)
+        } else {
+            inner_perft_hashed::<MAX_MOVES>(&mut pos, depth, table)
+        }
+    }
+}
+
+fn inner_perft_hashed<const N: usize>(
+    board: &mut Board,
+    depth: u16,
+    table: &mut PerftTable,
+) -> u64 {
+    let moves: MoveList<N> = MoveGen::generate_list::<Legal, AllGenType, N>(board);
+
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let key = board.zobrist();
+    if let Some(nodes) = table.probe(key, depth) {
+        return nodes;
+    }
+
+    let mut count: u64 = 0;
+
+    for mov in moves {
+        board.apply_move(mov);
+        count += inner_perft_hashed::<N>(board, depth - 1, table);
+        board.undo_move();
+    }
+
+    table.store(key, depth, count);
+    count
+}
+
+/// A position of a perft suite, alongside the number of leaf nodes expected at each depth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PerftSuiteEntry {
+    /// The FEN of the position.
+    pub fen: String,
+    /// Pairs of depth and expected number of leaf nodes.
+    pub expected: Vec<(u16, u64)>,
+}
+
+impl PerftSuiteEntry {
+    /// Parses a line of a perft suite, such as `<fen> ;D1 20 ;D2 400`.
+    ///
+    /// Returns `None` if any of the depth counts are malformed.
+    pub fn parse(line: &str) -> Option<PerftSuiteEntry> {
+        let mut fields = line.split(';');
+        let fen = fields.next()?.trim().to_string();
+        let mut expected = Vec::new();
+        for field in fields {
+            let mut parts = field.split_whitespace();
+            let depth = parts.next()?;
+            if !depth.starts_with('D') {
+                return None;
+            }
+            let depth = depth[1..].parse::<u16>().ok()?;
+            let nodes = parts.next()?.parse::<u64>().ok()?;
+            if parts.next().is_some() {
+                return None;
+            }
+            expected.push((depth, nodes));
+        }
+        Some(PerftSuiteEntry { fen, expected })
+    }
+}
+
+/// Error returned by [`run_perft_suite`].
+///
+/// Lines are numbered from 1.
+///
+/// [`run_perft_suite`]: fn.run_perft_suite.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PerftSuiteError {
+    /// A line of the suite couldn't be parsed.
+    InvalidLine { line: usize },
+    /// The FEN of a line couldn't be parsed.
+    InvalidFen { line: usize, fen: String },
+    /// The number of leaf nodes found doesn't match the suite. `depth` is the smallest depth that
+    /// fails for the position.
+    Mismatch {
+        line: usize,
+        fen: String,
+        depth: u16,
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl fmt::Display for PerftSuiteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PerftSuiteError::InvalidLine { line } => {
+                write!(f, "line {}: invalid perft entry", line)
+            }
+            PerftSuiteError::InvalidFen { line, ref fen } => {
+                write!(f, "line {}: invalid FEN: {}", line, fen)
+            }
+            PerftSuiteError::Mismatch {
+                line,
+                ref fen,
+                depth,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: {} at depth {}: expected {} nodes, found {}",
+                line, fen, depth, expected, found
+            ),
+        }
+    }
+}
+
+/// Runs every position of a perft suite up to `max_depth`, returning the number of positions
+/// checked, or the first position that fails.
+///
+/// Each line of `suite` holds a FEN, followed by the expected counts as `;D<depth> <nodes>`.
+/// Empty lines and lines starting with `#` are skipped. Depths of a position are checked from
+/// shallowest to deepest, so a mismatch is reported at the smallest failing depth.
+///
+/// # Examples
+///
+/// ```
+/// use pleco::board::perft::run_perft_suite;
+///
+/// let suite = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - ;D1 20 ;D2 400 ;D3 8902\n\
+///              8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - ;D1 14 ;D2 191";
+/// assert_eq!(run_perft_suite(suite, 3), Ok(2));
+/// ```
+pub fn run_perft_suite(suite: &str, max_depth: u16) -> Result<usize, PerftSuiteError> {
+    let mut checked = 0;
+    for (i, text) in suite.lines().enumerate() {
+        let line = i + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        let mut entry =
+            PerftSuiteEntry::parse(text).ok_or(PerftSuiteError::InvalidLine { line })?;
+        let board = Board::from_fen(&entry.fen).map_err(|_| PerftSuiteError::InvalidFen {
+            line,
+            fen: entry.fen.clone(),
+        })?;
+
+        entry.expected.sort();
+        for &(depth, expected) in entry.expected.iter().filter(|&&(d, _)| d <= max_depth) {
+            let found = perft_parallel(&board, depth);
+            if found != expected {
+                return Err(PerftSuiteError::Mismatch {
+                    line,
+                    fen: entry.fen,
+                    depth,
+                    expected,
+                    found,
+                });
+            }
+        }
+        checked += 1;
+    }
+    Ok(checked)
+}
+
+/// The smallest subtree where a perft disagrees with a reference, found by [`find_divergence`].
+///
+/// [`find_divergence`]: fn.find_divergence.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PerftDivergence {
+    /// The moves leading from the starting position to the diverging position.
+    pub moves: Vec<BitMove>,
+    /// The FEN of the diverging position.
+    pub fen: String,
+    /// Moves the reference generates, but the `Board` doesn't, in UCI notation.
+    pub missing: Vec<String>,
+    /// Moves the `Board` generates, but the reference doesn't, in UCI notation.
+    pub extra: Vec<String>,
+}
+
+/// Finds the position where the moves generated by the `Board` differ from a reference.
+///
+/// `reference` returns the divide of a position to a given depth, as pairs of UCI moves and
+/// leaf node counts, for example by asking another engine. Starting from `board`, both divides are
+/// compared. If the moves differ, that position is returned. Otherwise, the search continues
+/// one ply deeper below the first move whose counts differ. Returns `None` if the divides agree.
+pub fn find_divergence<F>(board: &Board, depth: u16, mut reference: F) -> Option<PerftDivergence>
+where
+    F: FnMut(&Board, u16) -> Vec<(String, u64)>,
+{
+    let mut pos = board.shallow_clone();
+    let mut moves = Vec::new();
+    let mut depth = depth;
+
+    while depth > 0 {
+        let ours: Vec<(String, BitMove, u64)> = perft_divide(&pos, depth)
+            .into_iter()
+            .map(|(mov, nodes)| (pos.move_to_uci(mov), mov, nodes))
+            .collect();
+        let theirs = reference(&pos, depth);
+
+        let missing: Vec<String> = theirs
+            .iter()
+            .filter(|t| !ours.iter().any(|o| o.0 == t.0))
+            .map(|t| t.0.clone())
+            .collect();
+        let extra: Vec<String> = ours
+            .iter()
+            .filter(|o| !theirs.iter().any(|t| t.0 == o.0))
+            .map(|o| o.0.clone())
+            .collect();
+        if !missing.is_empty() || !extra.is_empty() {
+            return Some(PerftDivergence {
+                moves,
+                fen: pos.fen(),
+                missing,
+                extra,
+            });
+        }
+
+        let diverging = ours
+            .iter()
+            .find(|o| theirs.iter().any(|t| t.0 == o.0 && t.1 != o.2))?;
+        pos.apply_move(diverging.1);
+        moves.push(diverging.1);
+        depth -= 1;
+    }
+    None
+}
+
+// Counts the leaf nodes with move lists that have room for the moves of the board's variant
+fn count_leaves(board: &mut Board, depth: u16) -> u64 {
+    if board.variant() == Variant::Crazyhouse {
+        inner_perft::<MAX_CRAZYHOUSE_MOVES>(board, depth)
+    } else {
+        inner_perft::<MAX_MOVES>(board, depth)
+    }
+}
+
+fn inner_perft<const N: usize>(board: &mut Board, depth: u16) -> u64 {
+    let moves: MoveList<N> = MoveGen::generate_list::<Legal, AllGenType, N>(board);
 
     if depth == 1 {
         return moves.len() as u64;
@@ -123,15 +507,15 @@ fn inner_perft(board: &mut Board, depth: u16) -> u64 {
 
     for mov in moves {
         board.apply_move(mov);
-        count += inner_perft(board, depth - 1);
+        count += inner_perft::<N>(board, depth - 1);
         board.undo_move();
     }
 
     count
 }
 
-fn inner_perft_all(board: &mut Board, depth: u16, perft: &mut PerftNodes) {
-    let moves: MoveList = board.generate_moves();
+fn inner_perft_all<const N: usize>(board: &mut Board, depth: u16, perft: &mut PerftNodes) {
+    let moves: MoveList<N> = MoveGen::generate_list::<Legal, AllGenType, N>(board);
 
     if depth == 0 {
         perft.nodes += 1;
@@ -141,24 +525,35 @@ fn inner_perft_all(board: &mut Board, depth: u16, perft: &mut PerftNodes) {
                 perft.checkmates += 1;
             }
         }
-    } else {
+    } else if depth == 1 {
+        // Bulk count the leaves, only playing the moves that give check to look for mate.
         for mov in moves {
-            if depth == 1 {
-                if mov.is_capture() {
-                    perft.captures += 1
-                }
-                if mov.is_en_passant() {
-                    perft.en_passant += 1
-                }
-                if mov.is_castle() {
-                    perft.castles += 1
-                }
-                if mov.is_promo() {
-                    perft.promotions += 1
+            perft.nodes += 1;
+            if mov.is_capture() {
+                perft.captures += 1
+            }
+            if mov.is_en_passant() {
+                perft.en_passant += 1
+            }
+            if mov.is_castle() {
+                perft.castles += 1
+            }
+            if mov.is_promo() {
+                perft.promotions += 1
+            }
+            if board.gives_check(mov) {
+                perft.checks += 1;
+                board.apply_move(mov);
+                if MoveGen::generate_list::<Legal, AllGenType, N>(board).is_empty() {
+                    perft.checkmates += 1;
                 }
+                board.undo_move();
             }
+        }
+    } else {
+        for mov in moves {
             board.apply_move(mov);
-            inner_perft_all(board, depth - 1, perft);
+            inner_perft_all::<N>(board, depth - 1, perft);
             board.undo_move();
         }
     }
@@ -199,6 +594,141 @@ mod tests {
         assert_eq!(193690690, perft(&b, 5));
     }
 
+    #[test]
+    fn perft_chess960() {
+        let b: Board =
+            Board::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9")
+                .unwrap();
+        assert_eq!(21, perft(&b, 1));
+        assert_eq!(528, perft(&b, 2));
+        assert_eq!(12189, perft(&b, 3));
+        assert_eq!(326672, perft(&b, 4));
+
+        let b: Board =
+            Board::from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9")
+                .unwrap();
+        assert_eq!(21, perft(&b, 1));
+        assert_eq!(807, perft(&b, 2));
+        assert_eq!(18002, perft(&b, 3));
+        assert_eq!(667366, perft(&b, 4));
+    }
+
+    #[test]
+    fn perft_divide_start_pos() {
+        let b: Board = Board::start_pos();
+        let divide = perft_divide(&b, 3);
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.iter().map(|&(_, n)| n).sum::<u64>(), 8902);
+        for &(mov, nodes) in divide.iter() {
+            match b.move_to_uci(mov).as_str() {
+                "e2e4" => assert_eq!(nodes, 600),
+                "a2a3" => assert_eq!(nodes, 380),
+                "g1f3" => assert_eq!(nodes, 440),
+                _ => {}
+            }
+        }
+        assert!(perft_divide(&b, 0).is_empty());
+        assert!(perft_divide(&b, 1).iter().all(|&(_, n)| n == 1));
+    }
+
+    #[test]
+    fn perft_parallel_and_hashed() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - -",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        let expected: [u64; 4] = [197_281, 4_085_603, 43_238, 2_103_487];
+        let mut table = PerftTable::new(1 << 16);
+        for (fen, &nodes) in fens.iter().zip(expected.iter()) {
+            let b = Board::from_fen(fen).unwrap();
+            assert_eq!(perft_parallel(&b, 4), nodes, "{}", fen);
+            assert_eq!(perft_hashed(&b, 4, &mut table), nodes, "{}", fen);
+            // second run is served from the table
+            assert_eq!(perft_hashed(&b, 4, &mut table), nodes, "{}", fen);
+        }
+        assert_eq!(perft_parallel(&Board::start_pos(), 1), 20);
+        assert_eq!(perft_hashed(&Board::start_pos(), 0, &mut table), 1);
+
+        table.clear();
+        let b = Board::start_pos();
+        assert_eq!(perft_hashed(&b, 5, &mut table), 4_865_609);
+    }
+
+    #[test]
+    fn perft_suite() {
+        let suite = "# comment\n\
+                     \n\
+                     rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - ;D1 20 ;D2 400 ;D3 8902\n\
+                     8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - ;D2 191 ;D1 14 ;D3 2813 ;D4 43239\n";
+        assert_eq!(run_perft_suite(suite, 2), Ok(2));
+        assert_eq!(
+            run_perft_suite(suite, 4),
+            Err(PerftSuiteError::Mismatch {
+                line: 4,
+                fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - -".to_string(),
+                depth: 3,
+                expected: 2813,
+                found: 2812,
+            })
+        );
+        assert_eq!(
+            run_perft_suite("8/8/8 w - - ;D1 1", 1),
+            Err(PerftSuiteError::InvalidFen {
+                line: 1,
+                fen: "8/8/8 w - -".to_string()
+            })
+        );
+        assert_eq!(
+            run_perft_suite("8/8/8/8/8/8/8/8 w - - ;D1", 1),
+            Err(PerftSuiteError::InvalidLine { line: 1 })
+        );
+    }
+
+    #[test]
+    fn divergence() {
+        let start = Board::start_pos();
+        let mut after_e4 = Board::start_pos();
+        after_e4.apply_uci_move("e2e4");
+        let target = after_e4.fen();
+
+        // A reference that believes Black can play a7a4 after 1. e4.
+        let reference = |b: &Board, d: u16| {
+            let mut divide: Vec<(String, u64)> = perft_divide(b, d)
+                .into_iter()
+                .map(|(mov, nodes)| {
+                    let mut child = b.shallow_clone();
+                    child.apply_move(mov);
+                    let nodes = if d == 2 && child.fen() == target {
+                        nodes + 1
+                    } else {
+                        nodes
+                    };
+                    (b.move_to_uci(mov), nodes)
+                })
+                .collect();
+            if d == 1 && b.fen() == target {
+                divide.push(("a7a4".to_string(), 1));
+            }
+            divide
+        };
+
+        let divergence = find_divergence(&start, 2, reference).unwrap();
+        assert_eq!(divergence.fen, target);
+        assert_eq!(start.move_to_uci(divergence.moves[0]), "e2e4");
+        assert_eq!(divergence.missing, vec!["a7a4".to_string()]);
+        assert!(divergence.extra.is_empty());
+
+        let honest = |b: &Board, d: u16| {
+            perft_divide(b, d)
+                .into_iter()
+                .map(|(mov, nodes)| (b.move_to_uci(mov), nodes))
+                .collect()
+        };
+        assert_eq!(find_divergence(&start, 3, honest), None);
+    }
+
     // This passes, but we're gonna ignore it as it takes a long time to use.
     #[ignore]
     #[test]
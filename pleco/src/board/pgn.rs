@@ -1,36 +1,96 @@
-//! Module for generating chess boards from PGN notation.
+//! Module for reading and writing games in Portable Game Notation (PGN).
+//!
+//! A [`PGN`] is parsed from the text of a single game, and contains the game's tag pairs
+//! ([`PGNTags`]), its movetext as a tree of moves ([`PGNVariation`] and [`PGNMove`]), and the
+//! game's result. Every move is resolved against a [`Board`] while reading, so illegal or
+//! ambiguous moves are reported as errors, and the position at any point of the game can be
+//! recreated by replaying the moves.
+//!
+//! Writing a [`PGN`] through its `Display` implementation produces export-format PGN: the
+//! Seven Tag Roster comes first, moves are in normalized SAN, and lines are wrapped before
+//! 80 characters.
+//!
+//! See the [PGN specification](https://www.chessclub.com/user/help/PGN-spec) for more
+//! information.
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::board::pgn::{PGN, GameResult};
+//!
+//! let input = "[Event \"Casual Game\"]\n\
+//!              [Result \"1-0\"]\n\
+//!              \n\
+//!              1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6?? (3... g6) 4. Qxf7# 1-0";
+//!
+//! let game = PGN::parse(input).unwrap();
+//! assert_eq!(game.tags().get("Event"), Some("Casual Game"));
+//! assert_eq!(game.result(), GameResult::WhiteWins);
+//! assert_eq!(game.moves().moves.len(), 7);
+//! assert!(game.board().unwrap().checkmate());
+//! ```
+//!
+//! [`PGN`]: struct.PGN.html
+//! [`PGNTags`]: struct.PGNTags.html
+//! [`PGNVariation`]: struct.PGNVariation.html
+//! [`PGNMove`]: struct.PGNMove.html
+//! [`Board`]: ../struct.Board.html
 
-//use super::Board;
-use core::sq::SQ;
-use core::{File, PieceType, Rank};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::BufRead;
 
-//[Event "F/S Return Match"]
-//[Site "Belgrade, Serbia JUG"]
-//[Date "1992.11.04"]
-//[Round "29"]
-//[White "Fischer, Robert J."]
-//[Black "Spassky, Boris V."]
-//[Result "1/2-1/2"]
+use super::san::SanError;
+use super::Board;
+use core::piece_move::BitMove;
 
-// 1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3
-// O-O 9. h3 Nb8 10. d4 Nbd7 11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15.
-// Nb1 h6 16. Bh4 c5 17. dxe5 Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21.
-// Nc4 Nxc4 22. Bxc4 Nb6 23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7
-// 27. Qe3 Qg5 28. Qxg5 hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33.
-// f3 Bc8 34. Kf2 Bf5 35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5
-// 40. Rd6 Kc5 41. Ra6 Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+/// The tags making up the Seven Tag Roster, in the order they are exported.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
 
-// https://www.chessclub.com/user/help/PGN-spec
+/// Maximum length of an exported line of movetext.
+const MAX_LINE_LENGTH: usize = 79;
 
+/// The result of a game, as given by a game termination marker.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum GameResult {
+    /// `1-0`
     WhiteWins,
+    /// `0-1`
     BlackWins,
+    /// `1/2-1/2`
     Draw,
+    /// `*`, the game is ongoing, abandoned, or the result is unknown.
     Other,
 }
 
+impl GameResult {
+    /// Parses a game termination marker, returning `None` if the string isn't one.
+    pub fn parse_game_result(result: &str) -> Option<GameResult> {
+        match result {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Other),
+            _ => None,
+        }
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad(match *self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Other => "*",
+        })
+    }
+}
+
+/// The date a game was played, as given by the `Date` tag.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ChessDate {
     Unknown,
     Year(u16),
@@ -39,8 +99,9 @@ pub enum ChessDate {
 }
 
 impl ChessDate {
+    /// Parses a date of the form `YYYY.MM.DD`, where unknown fields are given as question marks.
     pub fn parse_chess_date(date: &str) -> Self {
-        let mut args = date[1..(date.len() - 1)].split('.');
+        let mut args = date.split('.');
 
         let y = args.next().map(|m: &str| m.parse::<u16>());
 
@@ -82,28 +143,26 @@ impl ChessDate {
 impl Display for ChessDate {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            ChessDate::Unknown => write!(f, "??"),
-            ChessDate::Year(y) => write!(f, "{}.??.??", y),
-            ChessDate::YearMonth(y, m) => write!(f, "{}.{}.??", y, m),
-            ChessDate::Full(y, m, d) => write!(f, "{}.{}.{}", y, m, d),
+            ChessDate::Unknown => write!(f, "????.??.??"),
+            ChessDate::Year(y) => write!(f, "{:04}.??.??", y),
+            ChessDate::YearMonth(y, m) => write!(f, "{:04}.{:02}.??", y, m),
+            ChessDate::Full(y, m, d) => write!(f, "{:04}.{:02}.{:02}", y, m, d),
         }
     }
 }
 
+/// The round of a game, as given by the `Round` tag. Multiple levels of rounds are
+/// separated by periods, like `3.1`.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct ChessRound {
     rounds: Vec<u32>,
 }
 
-impl Default for ChessRound {
-    fn default() -> ChessRound {
-        ChessRound { rounds: Vec::new() }
-    }
-}
-
 impl ChessRound {
+    /// Parses a round of the form `N.N.N`. Unknown rounds result in an empty `ChessRound`.
     pub fn parse_chess_round(round: &str) -> ChessRound {
         let mut cr = ChessRound::default();
-        let args = round[1..(round.len() - 1)].split('.');
+        let args = round.split('.');
         args.for_each(|r: &str| {
             //            r.parse().map(|m: u32| cr.rounds.push(m));
             if let Ok(m) = r.parse() {
@@ -112,187 +171,992 @@ impl ChessRound {
         });
         cr
     }
+
+    /// Returns each level of the round.
+    pub fn rounds(&self) -> &[u32] {
+        &self.rounds
+    }
 }
 
 impl Display for ChessRound {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "\"")?;
+        if self.rounds.is_empty() {
+            return write!(f, "?");
+        }
         for (i, x) in self.rounds.iter().enumerate() {
             write!(f, "{}", x)?;
             if i != self.rounds.len() - 1 {
                 write!(f, ".")?;
             }
         }
-        write!(f, "\"")
+        Ok(())
     }
 }
 
+/// The tag pairs of a game, mapping tag names to their values.
+///
+/// When written, the Seven Tag Roster (`Event`, `Site`, `Date`, `Round`, `White`, `Black` and
+/// `Result`) always comes first, using the PGN placeholder values for any missing tags. The
+/// remaining tags follow in ASCII order.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct PGNTags {
-    event: String,
-    site: String,
-    date: ChessDate,
-    round: ChessRound,
-    white: String,
-    black: String,
-    result: String,
+    tags: BTreeMap<String, String>,
 }
 
 impl fmt::Display for PGNTags {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "[Event{}]", self.event)?;
-        writeln!(f, "[Site{}]", self.site)?;
-        writeln!(f, "[Date{}]", self.date)?;
-        writeln!(f, "[Round{}]", self.round)?;
-        writeln!(f, "[White{}]", self.white)?;
-        writeln!(f, "[Black{}]", self.black)?;
-        writeln!(f, "[Result{}]", self.result)
+        for tag in SEVEN_TAG_ROSTER.iter() {
+            let value = match self.get(tag) {
+                Some(value) => value,
+                None if *tag == "Date" => "????.??.??",
+                None if *tag == "Result" => "*",
+                None => "?",
+            };
+            write_tag(f, tag, value)?;
+        }
+        for (tag, value) in self.tags.iter() {
+            if !SEVEN_TAG_ROSTER.contains(&tag.as_str()) {
+                write_tag(f, tag, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_tag(f: &mut fmt::Formatter, tag: &str, value: &str) -> fmt::Result {
+    write!(f, "[{} \"", tag)?;
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            write!(f, "\\")?;
+        }
+        write!(f, "{}", c)?;
     }
+    writeln!(f, "\"]")
 }
 
 impl PGNTags {
-    pub fn add(mut self, input: &str) -> Result<PGNTags, PGNError> {
-        let first_char = input.chars().next().ok_or(PGNError::TagParse)?;
-        let last_char = input.chars().last().ok_or(PGNError::TagParse)?;
-        if input.len() < 3 || first_char != '[' || last_char != ']' {
-            return Err(PGNError::TagParse);
-        }
-        let r = &input[1..(input.len() - 1)];
-        let quote_first = r.find('"').ok_or(PGNError::TagParse)?;
-        let quote_second = r.rfind('"').ok_or(PGNError::TagParse)?;
-        if quote_first >= quote_second - 2 {
-            return Err(PGNError::TagParse);
-        }
-        let in_quote = r[(quote_first)..(quote_second + 1)].to_owned();
-        let no_quote = r[..(quote_first - 1)]
-            .split_whitespace()
-            .next()
-            .ok_or(PGNError::TagParse)?;
-        self = self.parse_tag(no_quote, in_quote)?;
-        Ok(self)
+    /// Parses a single tag pair of the form `[Name "Value"]` and adds it to the tags.
+    pub fn with_tag(self, input: &str) -> Result<PGNTags, PGNError> {
+        let mut parser = Parser::new(input, 1);
+        let (tag, value, _) = match parser.parse_tag_pair()? {
+            Some(tag_pair) => tag_pair,
+            None => return Err(parser.error_at_next()?),
+        };
+        if let Some((_, line, column)) = parser.next()? {
+            return Err(PGNError::TagParse { line, column });
+        }
+        self.parse_tag(&tag, value)
     }
 
+    /// Adds a tag with the given name and value.
+    ///
+    /// Returns `PGNError::InvalidTagName` if the name isn't a valid PGN symbol.
     pub fn parse_tag(mut self, tag: &str, data: String) -> Result<PGNTags, PGNError> {
-        match tag {
-            "Event" => self.event = data,
-            "Site" => self.site = data,
-            "Date" => self.date = ChessDate::parse_chess_date(data.as_ref()),
-            "Round" => self.round = ChessRound::parse_chess_round(data.as_ref()),
-            "White" => self.white = data,
-            "Black" => self.black = data,
-            "Result" => self.result = data,
-            _ => return Err(PGNError::TagParse),
+        if tag.is_empty() || !tag.chars().all(is_symbol_char) {
+            return Err(PGNError::InvalidTagName(tag.to_string()));
         }
+        self.tags.insert(tag.to_string(), data);
         Ok(self)
     }
-}
 
-impl Default for PGNTags {
-    fn default() -> Self {
-        PGNTags {
-            event: String::new(),
-            site: String::new(),
-            date: ChessDate::Unknown,
-            round: ChessRound::default(),
-            white: String::new(),
-            black: String::new(),
-            result: String::new(),
-        }
+    /// Returns the value of a tag, if it exists.
+    pub fn get(&self, tag: &str) -> Option<&str> {
+        self.tags.get(tag).map(|s| s.as_str())
     }
-}
 
-pub enum PGNMoveTag {
-    None,        // ''
-    Good,        // '!'
-    Excellent,   // '!!'
-    Bad,         // '?'
-    Blunder,     // '??'
-    Interesting, // '!?'
-    Doubtful,    // '?!'
-}
+    /// Sets the value of a tag, replacing any previous value.
+    pub fn set(&mut self, tag: &str, value: &str) {
+        self.tags.insert(tag.to_string(), value.to_string());
+    }
 
-// Check = +
-// Checkmate = #
-pub enum CheckType {
-    Check,
-    CheckMate,
-}
+    /// Removes a tag, returning its value if it existed.
+    pub fn remove(&mut self, tag: &str) -> Option<String> {
+        self.tags.remove(tag)
+    }
 
-// (File) OR (Rank) OR (Square)
-pub struct PGNMoveSpecifier {
-    rank: Option<Rank>,
-    file: Option<File>,
-    square: Option<SQ>,
-}
+    /// Iterates through all tags and their values, in ASCII order of the tag names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
 
-// [Piece](specifier)("capture")["dest"]("Promo")
-// [Piece] => K, Q, R, B, N, '' if pawn
-// (specifier) => rank or file or square if needed
-// (capture) => x if capture
-//
-pub struct PGNRegMove {
-    piece: Option<PieceType>,
-    specifier: Option<PGNMoveSpecifier>,
-    dest: SQ,
-    promo: Option<PieceType>,
-    capture: bool,
-}
+    /// Returns the date of the game.
+    pub fn date(&self) -> ChessDate {
+        self.get("Date")
+            .map_or(ChessDate::Unknown, ChessDate::parse_chess_date)
+    }
+
+    /// Returns the round of the game.
+    pub fn round(&self) -> ChessRound {
+        self.get("Round")
+            .map_or_else(ChessRound::default, ChessRound::parse_chess_round)
+    }
 
-//
-pub enum PGNMoveType {
-    KingSideCastle,  // O-O
-    QueenSideCastle, // O-O-O
-    Reg(PGNRegMove),
+    /// Returns the result of the game given by the `Result` tag, if any.
+    pub fn result(&self) -> Option<GameResult> {
+        self.get("Result").and_then(GameResult::parse_game_result)
+    }
+
+    /// Returns the FEN of the starting position, if the game doesn't start from the
+    /// standard starting position.
+    pub fn fen(&self) -> Option<&str> {
+        self.get("FEN")
+    }
 }
 
-// (move)(check ?)(tag)
+/// A single move of a game, alongside any annotations and alternatives to it.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PGNMove {
-    move_type: PGNMoveType,
-    check: Option<CheckType>,
-    tag: PGNMoveTag,
+    /// The move played.
+    pub bit_move: BitMove,
+    /// The move in Standard Algebraic Notation.
+    pub san: String,
+    /// Numeric Annotation Glyphs of the move. Suffix annotations such as `!` or `?!` are
+    /// stored as their equivalent NAG.
+    pub nags: Vec<u8>,
+    /// Comments following the move.
+    pub comments: Vec<String>,
+    /// Alternatives to this move. Each variation starts from the position before this move.
+    pub variations: Vec<PGNVariation>,
 }
 
 impl PGNMove {
-    pub fn parse(_input: &str) -> Result<PGNMove, PGNError> {
-        unimplemented!()
+    /// Creates a `PGNMove` with no annotations from a move legal on the given `Board`.
+    pub fn new(board: &Board, bit_move: BitMove) -> PGNMove {
+        PGNMove {
+            bit_move,
+            san: board.move_to_san(bit_move),
+            nags: Vec::new(),
+            comments: Vec::new(),
+            variations: Vec::new(),
+        }
     }
 }
 
-pub struct PGNRound {
-    move_num: u32,
-    white_move: PGNMove,
-    black_move: Option<PGNMove>,
+/// A line of consecutive moves. The mainline of a game is a `PGNVariation`, as is any
+/// variation nested inside it.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct PGNVariation {
+    /// Comments before the first move of the line.
+    pub comments: Vec<String>,
+    /// The moves of the line.
+    pub moves: Vec<PGNMove>,
 }
 
-#[derive(Debug)]
+/// Errors encountered while reading PGN. Line and column numbers start from one.
+#[derive(Clone, Debug, PartialEq)]
 pub enum PGNError {
-    TagParse,
-    Length,
+    /// A tag pair is malformed.
+    TagParse { line: usize, column: usize },
+    /// A tag name given to `PGNTags::parse_tag` isn't a valid PGN symbol.
+    InvalidTagName(String),
+    /// The input ended in the middle of a token or game.
+    UnexpectedEnd { line: usize, column: usize },
+    /// A token appeared where it isn't allowed.
+    UnexpectedToken {
+        token: String,
+        line: usize,
+        column: usize,
+    },
+    /// A move doesn't match exactly one legal move of the position.
+    IllegalMove {
+        san: String,
+        error: SanError,
+        line: usize,
+        column: usize,
+    },
+    /// The `FEN` tag is not a valid position. The line and column are those of the tag pair,
+    /// or zero if the game wasn't read from text.
+    InvalidFen {
+        fen: String,
+        line: usize,
+        column: usize,
+    },
+    /// Reading from the underlying reader failed at the given line.
+    Io { kind: io::ErrorKind, line: usize },
+}
+
+impl PGNError {
+    /// Returns the line and column the error occurred at, if known.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match *self {
+            PGNError::TagParse { line, column }
+            | PGNError::UnexpectedEnd { line, column }
+            | PGNError::UnexpectedToken { line, column, .. }
+            | PGNError::IllegalMove { line, column, .. } => Some((line, column)),
+            PGNError::InvalidFen { line, column, .. } if line != 0 => Some((line, column)),
+            PGNError::InvalidFen { .. } | PGNError::InvalidTagName(_) => None,
+            PGNError::Io { line, .. } => Some((line, 1)),
+        }
+    }
 }
 
+impl Display for PGNError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            PGNError::TagParse { line, column } => {
+                write!(f, "{}:{}: malformed tag pair", line, column)
+            }
+            PGNError::InvalidTagName(ref tag) => write!(f, "invalid tag name: {}", tag),
+            PGNError::UnexpectedEnd { line, column } => {
+                write!(f, "{}:{}: unexpected end of input", line, column)
+            }
+            PGNError::UnexpectedToken {
+                ref token,
+                line,
+                column,
+            } => write!(f, "{}:{}: unexpected token: {}", line, column, token),
+            PGNError::IllegalMove {
+                ref error,
+                line,
+                column,
+                ..
+            } => write!(f, "{}:{}: {}", line, column, error),
+            PGNError::InvalidFen {
+                ref fen, line: 0, ..
+            } => write!(f, "invalid FEN tag: {}", fen),
+            PGNError::InvalidFen {
+                ref fen,
+                line,
+                column,
+            } => write!(f, "{}:{}: invalid FEN tag: {}", line, column, fen),
+            PGNError::Io { kind, line } => {
+                write!(f, "{}: {}", line, io::Error::from(kind))
+            }
+        }
+    }
+}
+
+/// A single game in Portable Game Notation.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PGN {
     tags: PGNTags,
-    moves: Vec<PGNRound>,
+    moves: PGNVariation,
+    result: GameResult,
 }
 
-// [Event "F/S Return Match"]
 impl PGN {
+    /// Creates a game from its tags, mainline and result.
+    ///
+    /// The moves of the mainline and variations are expected to be legal, starting from the
+    /// position given by the `FEN` tag, or the start position if there is none.
+    pub fn new(tags: PGNTags, moves: PGNVariation, result: GameResult) -> PGN {
+        PGN {
+            tags,
+            moves,
+            result,
+        }
+    }
+
+    /// Parses the text of a single game.
+    ///
+    /// Any text following the game's termination marker is ignored.
     pub fn parse(input: &str) -> Result<PGN, PGNError> {
-        let mut tags = PGNTags::default();
+        let mut parser = Parser::new(input, 1);
+        match parser.parse_game()? {
+            Some(game) => Ok(game),
+            None => Err(parser.lexer.end_error()),
+        }
+    }
+
+    /// Returns the tags of the game.
+    pub fn tags(&self) -> &PGNTags {
+        &self.tags
+    }
+
+    /// Returns a mutable reference to the tags of the game.
+    pub fn tags_mut(&mut self) -> &mut PGNTags {
+        &mut self.tags
+    }
+
+    /// Returns the mainline of the game.
+    pub fn moves(&self) -> &PGNVariation {
+        &self.moves
+    }
+
+    /// Returns the result of the game.
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    /// Returns the moves of the mainline.
+    pub fn mainline(&self) -> Vec<BitMove> {
+        self.moves.moves.iter().map(|m| m.bit_move).collect()
+    }
+
+    /// Returns the position the game starts from.
+    pub fn initial_board(&self) -> Result<Board, PGNError> {
+        initial_board(&self.tags, (0, 0))
+    }
+
+    /// Returns the position at the end of the mainline.
+    pub fn board(&self) -> Result<Board, PGNError> {
+        let mut board = self.initial_board()?;
+        for mov in self.moves.moves.iter() {
+            board.apply_move(mov.bit_move);
+        }
+        Ok(board)
+    }
+}
+
+impl Display for PGN {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut tags = self.tags.clone();
+        tags.set("Result", &self.result.to_string());
+        writeln!(f, "{}", tags)?;
+
+        let ply = self.initial_board().map_or(0, |b| b.moves_played());
+        let mut writer = MovetextWriter::default();
+        writer.write_variation(&self.moves, ply);
+        writer.push(&self.result.to_string());
+        writeln!(f, "{}", writer.text)
+    }
+}
+
+/// Predicate deciding which games a `PGNReader` yields.
+type TagFilter = Box<dyn FnMut(&PGNTags) -> bool>;
+
+/// Reads games one at a time from a stream of PGN text, such as a large database file.
+///
+/// Only the text of the current game is kept in memory. Games are separated by the start of a
+/// new tag section after some movetext. Text that isn't valid UTF-8 is read lossily.
+///
+/// A filter can be set through [`with_filter`] to only yield games whose tags match. Games
+/// that are rejected by the filter are skipped without their moves being parsed or replayed.
+///
+/// If a game fails to parse, its error is yielded, and reading continues from the next line
+/// starting with `[Event`. Errors carry line and column numbers relative to the whole stream.
+/// Reading stops after an I/O error.
+///
+/// # Examples
+///
+/// ```
+/// use pleco::board::pgn::PGNReader;
+///
+/// let input = "[Event \"A\"]\n[White \"Carlsen\"]\n\n1. e4 e5 1-0\n\n\
+///              [Event \"B\"]\n[White \"Caruana\"]\n\n1. d4 d5 0-1\n";
+///
+/// let reader = PGNReader::new(input.as_bytes())
+///     .with_filter(|tags| tags.get("White") == Some("Caruana"));
+/// let games: Vec<_> = reader.map(|game| game.unwrap()).collect();
+/// assert_eq!(games.len(), 1);
+/// assert_eq!(games[0].tags().get("Event"), Some("B"));
+/// ```
+///
+/// [`with_filter`]: struct.PGNReader.html#method.with_filter
+pub struct PGNReader<R: BufRead> {
+    reader: R,
+    filter: Option<TagFilter>,
+    // Line that starts the next game, already read while finding the end of the last one
+    pending: Option<(String, usize)>,
+    line: usize,
+    resync: bool,
+    done: bool,
+}
+
+impl<R: BufRead> PGNReader<R> {
+    /// Creates a reader yielding every game of the stream.
+    pub fn new(reader: R) -> PGNReader<R> {
+        PGNReader {
+            reader,
+            filter: None,
+            pending: None,
+            line: 0,
+            resync: false,
+            done: false,
+        }
+    }
+
+    /// Sets a predicate on the tags of each game. Only games for which the predicate returns
+    /// `true` are parsed and yielded.
+    pub fn with_filter<F>(mut self, filter: F) -> PGNReader<R>
+    where
+        F: FnMut(&PGNTags) -> bool + 'static,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Returns the number of lines read so far.
+    pub fn lines_read(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the next line and its line number, or `None` at the end of the stream.
+    fn read_line(&mut self) -> Result<Option<(String, usize)>, PGNError> {
+        if let Some(pending) = self.pending.take() {
+            return Ok(Some(pending));
+        }
+        let mut buf = Vec::new();
+        match self.reader.read_until(b'\n', &mut buf) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                self.line += 1;
+                Ok(Some((
+                    String::from_utf8_lossy(&buf).into_owned(),
+                    self.line,
+                )))
+            }
+            Err(e) => Err(PGNError::Io {
+                kind: e.kind(),
+                line: self.line + 1,
+            }),
+        }
+    }
+
+    /// Reads the text of the next game, returning it alongside the line it starts at.
+    fn read_game_text(&mut self) -> Result<Option<(String, usize)>, PGNError> {
+        let mut text = String::new();
+        let mut first_line = None;
+        let mut seen_movetext = false;
+        let mut in_comment = false;
+
+        while let Some((line, line_num)) = self.read_line()? {
+            let trimmed = line.trim_start();
+            if self.resync {
+                if !trimmed.starts_with("[Event") {
+                    continue;
+                }
+                self.resync = false;
+            }
+
+            if first_line.is_some() && trimmed.starts_with('[') {
+                // An unterminated comment can only be recovered from at the next game
+                let new_game = if in_comment {
+                    trimmed.starts_with("[Event")
+                } else {
+                    seen_movetext
+                };
+                if new_game {
+                    self.pending = Some((line, line_num));
+                    break;
+                }
+            }
+
+            if first_line.is_none() {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                first_line = Some(line_num);
+            }
+
+            if in_comment || !(trimmed.starts_with('[') || trimmed.starts_with('%')) {
+                seen_movetext |= !trimmed.is_empty();
+                in_comment = ends_in_comment(&line, in_comment);
+            }
+            text.push_str(&line);
+        }
+
+        Ok(first_line.map(|line| (text, line)))
+    }
+
+    fn next_game(&mut self) -> Result<Option<PGN>, PGNError> {
+        while let Some((text, line)) = self.read_game_text()? {
+            let mut parser = Parser::new(&text, line);
+            let tags = parser.parse_tags()?;
+            if let Some(ref mut filter) = self.filter {
+                if !filter(&tags) {
+                    continue;
+                }
+            }
+            return parser.parse_moves(tags).map(Some);
+        }
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Iterator for PGNReader<R> {
+    type Item = Result<PGN, PGNError>;
+
+    fn next(&mut self) -> Option<Result<PGN, PGNError>> {
+        if self.done {
+            return None;
+        }
+        match self.next_game() {
+            Ok(game) => game.map(Ok),
+            Err(e) => {
+                match e {
+                    PGNError::Io { .. } => self.done = true,
+                    _ => self.resync = true,
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Returns if a line of movetext ends inside a brace comment.
+fn ends_in_comment(line: &str, mut in_comment: bool) -> bool {
+    for c in line.chars() {
+        match c {
+            '}' if in_comment => in_comment = false,
+            '{' if !in_comment => in_comment = true,
+            ';' if !in_comment => return false,
+            _ => {}
+        }
+    }
+    in_comment
+}
+
+/// Returns the starting position given by the tags, with the position of the `FEN` tag used
+/// for errors.
+fn initial_board(tags: &PGNTags, fen_position: (usize, usize)) -> Result<Board, PGNError> {
+    match tags.fen() {
+        Some(fen) => Board::from_fen(fen).map_err(|_| PGNError::InvalidFen {
+            fen: fen.to_string(),
+            line: fen_position.0,
+            column: fen_position.1,
+        }),
+        None => Ok(Board::start_pos()),
+    }
+}
+
+/// Writes movetext tokens, wrapping lines before they exceed `MAX_LINE_LENGTH`.
+#[derive(Default)]
+struct MovetextWriter {
+    text: String,
+    line_len: usize,
+    // Set after an opening parenthesis, so the next token isn't separated by a space
+    attach: bool,
+}
+
+impl MovetextWriter {
+    fn push(&mut self, token: &str) {
+        if self.attach {
+            self.text.push_str(token);
+            self.line_len += token.len();
+            self.attach = false;
+        } else if self.line_len == 0 {
+            self.text.push_str(token);
+            self.line_len = token.len();
+        } else if self.line_len + 1 + token.len() > MAX_LINE_LENGTH {
+            self.text.push('\n');
+            self.text.push_str(token);
+            self.line_len = token.len();
+        } else {
+            self.text.push(' ');
+            self.text.push_str(token);
+            self.line_len += 1 + token.len();
+        }
+    }
+
+    fn push_comment(&mut self, comment: &str) {
+        let comment = comment.replace('}', "");
+        let mut words = comment.split_whitespace().peekable();
+        if words.peek().is_none() {
+            self.push("{}");
+            return;
+        }
+        let mut first = true;
+        while let Some(word) = words.next() {
+            let mut token = String::new();
+            if first {
+                token.push('{');
+                first = false;
+            }
+            token.push_str(word);
+            if words.peek().is_none() {
+                token.push('}');
+            }
+            self.push(&token);
+        }
+    }
+
+    fn write_variation(&mut self, variation: &PGNVariation, mut ply: u16) {
+        for comment in variation.comments.iter() {
+            self.push_comment(comment);
+        }
+        let mut needs_number = true;
+        for mov in variation.moves.iter() {
+            let move_number = ply / 2 + 1;
+            if ply & 1 == 0 {
+                self.push(&format!("{}.", move_number));
+            } else if needs_number {
+                self.push(&format!("{}...", move_number));
+            }
+            self.push(&mov.san);
+            needs_number = false;
+
+            for nag in mov.nags.iter() {
+                self.push(&format!("${}", nag));
+            }
+            for comment in mov.comments.iter() {
+                self.push_comment(comment);
+                needs_number = true;
+            }
+            for sub_variation in mov.variations.iter() {
+                self.push("(");
+                self.attach = true;
+                self.write_variation(sub_variation, ply);
+                self.text.push(')');
+                self.line_len += 1;
+                needs_number = true;
+            }
+            ply += 1;
+        }
+    }
+}
+
+/// Returns if the character can continue a PGN symbol token.
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_+#=:-/".contains(c)
+}
+
+/// Tokens of PGN text.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    TagOpen,
+    TagClose,
+    VariationOpen,
+    VariationClose,
+    Period,
+    Str(String),
+    Symbol(String),
+    Nag(u8),
+    Comment(String),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Token::TagOpen => write!(f, "["),
+            Token::TagClose => write!(f, "]"),
+            Token::VariationOpen => write!(f, "("),
+            Token::VariationClose => write!(f, ")"),
+            Token::Period => write!(f, "."),
+            Token::Str(ref s) => write!(f, "\"{}\"", s),
+            Token::Symbol(ref s) => write!(f, "{}", s),
+            Token::Nag(n) => write!(f, "${}", n),
+            Token::Comment(ref s) => write!(f, "{{{}}}", s),
+        }
+    }
+}
+
+/// Splits PGN text into tokens, keeping track of the current line and column.
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str, line: usize) -> Lexer<'a> {
+        Lexer {
+            input,
+            pos: 0,
+            line,
+            column: 1,
+        }
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn end_error(&self) -> PGNError {
+        PGNError::UnexpectedEnd {
+            line: self.line,
+            column: self.column,
+        }
+    }
 
-        let mut lines = input.lines();
+    /// Reads characters until the predicate fails, or the input ends.
+    fn take_while<F: Fn(char) -> bool>(&mut self, pred: F) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek_char() {
+            if !pred(c) {
+                break;
+            }
+            self.bump();
+        }
+        &self.input[start..self.pos]
+    }
+
+    /// Returns the next token, alongside the line and column it starts at.
+    fn next_token(&mut self) -> Result<Option<(Token, usize, usize)>, PGNError> {
         loop {
-            let mut line = lines.next().ok_or(PGNError::Length)?;
-            if line.is_empty() {
+            self.take_while(char::is_whitespace);
+            // Lines starting with '%' are escaped from parsing
+            if self.column == 1 && self.peek_char() == Some('%') {
+                self.take_while(|c| c != '\n');
+            } else {
                 break;
             }
-            line = line.trim();
-            tags = tags.add(line)?;
         }
+
+        let (line, column) = (self.line, self.column);
+        let c = match self.bump() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        let token = match c {
+            '[' => Token::TagOpen,
+            ']' => Token::TagClose,
+            '(' => Token::VariationOpen,
+            ')' => Token::VariationClose,
+            '.' => Token::Period,
+            '*' => Token::Symbol("*".to_string()),
+            ';' => Token::Comment(self.take_while(|c| c != '\n').trim().to_string()),
+            '{' => {
+                let comment = self.take_while(|c| c != '}').trim().to_string();
+                self.bump().ok_or_else(|| self.end_error())?;
+                Token::Comment(comment)
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match self.bump().ok_or_else(|| self.end_error())? {
+                        '"' => break,
+                        '\\' => s.push(self.bump().ok_or_else(|| self.end_error())?),
+                        c => s.push(c),
+                    }
+                }
+                Token::Str(s)
+            }
+            '$' => {
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                let nag = digits
+                    .parse::<u8>()
+                    .map_err(|_| PGNError::UnexpectedToken {
+                        token: format!("${}", digits),
+                        line,
+                        column,
+                    })?;
+                Token::Nag(nag)
+            }
+            '!' | '?' => {
+                let rest = self.take_while(|c| c == '!' || c == '?');
+                let nag = match (c, rest) {
+                    ('!', "") => 1,
+                    ('?', "") => 2,
+                    ('!', "!") => 3,
+                    ('?', "?") => 4,
+                    ('!', "?") => 5,
+                    ('?', "!") => 6,
+                    _ => {
+                        return Err(PGNError::UnexpectedToken {
+                            token: format!("{}{}", c, rest),
+                            line,
+                            column,
+                        });
+                    }
+                };
+                Token::Nag(nag)
+            }
+            c if c.is_ascii_alphanumeric() => {
+                let start = self.pos - c.len_utf8();
+                self.take_while(is_symbol_char);
+                Token::Symbol(self.input[start..self.pos].to_string())
+            }
+            c => {
+                return Err(PGNError::UnexpectedToken {
+                    token: c.to_string(),
+                    line,
+                    column,
+                });
+            }
+        };
+        Ok(Some((token, line, column)))
+    }
+}
+
+/// A tag name and value, with the line and column the tag pair starts at.
+type TagPair = (String, String, (usize, usize));
+
+/// Parses games from PGN text.
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<(Token, usize, usize)>,
+    fen_position: (usize, usize),
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser for the given text, which starts at the given line of the input.
+    fn new(input: &'a str, line: usize) -> Parser<'a> {
+        Parser {
+            lexer: Lexer::new(input, line),
+            peeked: None,
+            fen_position: (0, 0),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>, PGNError> {
+        if self.peeked.is_none() {
+            self.peeked = self.lexer.next_token()?;
+        }
+        Ok(self.peeked.as_ref().map(|(t, _, _)| t))
+    }
+
+    fn next(&mut self) -> Result<Option<(Token, usize, usize)>, PGNError> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    /// Returns a `TagParse` error at the next token, or an `UnexpectedEnd` error if there is
+    /// none.
+    fn error_at_next(&mut self) -> Result<PGNError, PGNError> {
+        Ok(match self.next()? {
+            Some((_, line, column)) => PGNError::TagParse { line, column },
+            None => self.lexer.end_error(),
+        })
+    }
+
+    /// Returns the next token, treating the end of input as an error.
+    fn expect(&mut self) -> Result<(Token, usize, usize), PGNError> {
+        self.next()?.ok_or_else(|| self.lexer.end_error())
+    }
+
+    /// Parses a game, returning `None` if there are no more tokens.
+    fn parse_game(&mut self) -> Result<Option<PGN>, PGNError> {
+        if self.peek()?.is_none() {
+            return Ok(None);
+        }
+        let tags = self.parse_tags()?;
+        self.parse_moves(tags).map(Some)
+    }
+
+    /// Parses the movetext of a game, following its tags.
+    fn parse_moves(&mut self, tags: PGNTags) -> Result<PGN, PGNError> {
+        let mut board = initial_board(&tags, self.fen_position)?;
+        let (moves, result) = self.parse_variation(&mut board, false)?;
+        let result = result
+            .or_else(|| tags.result())
+            .unwrap_or(GameResult::Other);
         Ok(PGN {
             tags,
-            moves: Vec::new(),
+            moves,
+            result,
         })
     }
+
+    fn parse_tags(&mut self) -> Result<PGNTags, PGNError> {
+        let mut tags = PGNTags::default();
+        while let Some((tag, value, position)) = self.parse_tag_pair()? {
+            if tag == "FEN" {
+                self.fen_position = position;
+            }
+            tags.tags.insert(tag, value);
+        }
+        Ok(tags)
+    }
+
+    /// Parses a tag pair, returning `None` if the next token doesn't start a tag pair.
+    fn parse_tag_pair(&mut self) -> Result<Option<TagPair>, PGNError> {
+        if self.peek()? != Some(&Token::TagOpen) {
+            return Ok(None);
+        }
+        let (_, line, column) = self.expect()?;
+        let tag_error = PGNError::TagParse { line, column };
+        let tag = match self.expect()? {
+            (Token::Symbol(tag), _, _) => tag,
+            _ => return Err(tag_error),
+        };
+        let value = match self.expect()? {
+            (Token::Str(value), _, _) => value,
+            _ => return Err(tag_error),
+        };
+        match self.expect()? {
+            (Token::TagClose, _, _) => Ok(Some((tag, value, (line, column)))),
+            _ => Err(tag_error),
+        }
+    }
+
+    /// Parses a line of moves, applying them to the board. Nested variations end at a closing
+    /// parenthesis, and leave the board unchanged. The mainline ends at a game termination
+    /// marker, and leaves the board at the end of the line.
+    fn parse_variation(
+        &mut self,
+        board: &mut Board,
+        nested: bool,
+    ) -> Result<(PGNVariation, Option<GameResult>), PGNError> {
+        let mut variation = PGNVariation::default();
+        let mut result = None;
+
+        loop {
+            let (token, line, column) = match self.next()? {
+                Some(token) => token,
+                None if nested => return Err(self.lexer.end_error()),
+                None => break,
+            };
+            let unexpected = |token: Token| PGNError::UnexpectedToken {
+                token: token.to_string(),
+                line,
+                column,
+            };
+
+            match token {
+                Token::Period => {}
+                Token::Symbol(ref s) if s.chars().all(|c| c.is_ascii_digit()) => {}
+                Token::Symbol(ref s) if GameResult::parse_game_result(s).is_some() => {
+                    if nested {
+                        return Err(unexpected(token.clone()));
+                    }
+                    result = GameResult::parse_game_result(s);
+                    break;
+                }
+                Token::Symbol(san) => {
+                    let bit_move =
+                        board
+                            .parse_san(&san)
+                            .map_err(|error| PGNError::IllegalMove {
+                                san: san.clone(),
+                                error,
+                                line,
+                                column,
+                            })?;
+                    variation.moves.push(PGNMove::new(board, bit_move));
+                    board.apply_move(bit_move);
+                }
+                Token::Nag(nag) => match variation.moves.last_mut() {
+                    Some(mov) => mov.nags.push(nag),
+                    None => return Err(unexpected(token)),
+                },
+                Token::Comment(comment) => match variation.moves.last_mut() {
+                    Some(mov) => mov.comments.push(comment),
+                    None => variation.comments.push(comment),
+                },
+                Token::VariationOpen => {
+                    let last_move = match variation.moves.last() {
+                        Some(mov) => mov.bit_move,
+                        None => return Err(unexpected(token)),
+                    };
+                    board.undo_move();
+                    let (sub_variation, _) = self.parse_variation(board, true)?;
+                    board.apply_move(last_move);
+                    variation
+                        .moves
+                        .last_mut()
+                        .unwrap()
+                        .variations
+                        .push(sub_variation);
+                }
+                Token::VariationClose if nested => break,
+                token => return Err(unexpected(token)),
+            }
+        }
+
+        if nested {
+            for _ in 0..variation.moves.len() {
+                board.undo_move();
+            }
+        }
+        Ok((variation, result))
+    }
 }
 
 #[cfg(test)]
@@ -304,19 +1168,257 @@ mod tests {
     static TEST_ROUND: &str = "[Round \"0.0\"]";
     static TEST_RESULT: &str = "[Round \"1-0\"]";
 
+    static FISCHER_SPASSKY: &str = "[Event \"F/S Return Match\"]
+[Site \"Belgrade, Serbia JUG\"]
+[Date \"1992.11.04\"]
+[Round \"29\"]
+[White \"Fischer, Robert J.\"]
+[Black \"Spassky, Boris V.\"]
+[Result \"1/2-1/2\"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 {This opening is called the Ruy Lopez.} 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3
+O-O 9. h3 Nb8 10. d4 Nbd7 11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15.
+Nb1 h6 16. Bh4 c5 17. dxe5 Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21.
+Nc4 Nxc4 22. Bxc4 Nb6 23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7
+27. Qe3 Qg5 28. Qxg5 hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33.
+f3 Bc8 34. Kf2 Bf5 35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5
+40. Rd6 Kc5 41. Ra6 Nf2 42. g4 Bd3 43. Re6 1/2-1/2";
+
     extern crate rand;
     use super::*;
 
     #[test]
     fn tags_test() {
-        PGNTags::default()
-            .add(TEST_WHITE)
+        let tags = PGNTags::default()
+            .with_tag(TEST_WHITE)
             .unwrap()
-            .add(TEST_BLACK)
+            .with_tag(TEST_BLACK)
             .unwrap()
-            .add(TEST_DATE)
+            .with_tag(TEST_DATE)
             .unwrap()
-            .add(TEST_ROUND)
+            .with_tag(TEST_ROUND)
+            .unwrap()
+            .with_tag(TEST_RESULT)
             .unwrap();
+        assert_eq!(tags.get("White"), Some("David Sr. Johnson"));
+        assert_eq!(tags.date(), ChessDate::Full(2017, 4, 2));
+        assert_eq!(tags.get("Round"), Some("1-0"));
+        assert_eq!(tags.round().to_string(), "?");
+        assert!(PGNTags::default().with_tag("[White David]").is_err());
+        assert_eq!(
+            PGNTags::default().with_tag("  White \"David\""),
+            Err(PGNError::TagParse { line: 1, column: 3 })
+        );
+        assert_eq!(
+            PGNTags::default().with_tag("\n "),
+            Err(PGNError::UnexpectedEnd { line: 2, column: 2 })
+        );
+        let error = PGNTags::default()
+            .parse_tag("White Player", "David".to_string())
+            .unwrap_err();
+        assert_eq!(error, PGNError::InvalidTagName("White Player".to_string()));
+        assert_eq!(error.position(), None);
+    }
+
+    #[test]
+    fn tag_escapes() {
+        let tags = PGNTags::default()
+            .with_tag("[Event \"The \\\"Big\\\" One \\\\ 2\"]")
+            .unwrap();
+        assert_eq!(tags.get("Event"), Some("The \"Big\" One \\ 2"));
+        let written = tags.to_string();
+        assert!(written.starts_with("[Event \"The \\\"Big\\\" One \\\\ 2\"]\n"));
+        assert!(written.contains("[Date \"????.??.??\"]\n"));
+    }
+
+    #[test]
+    fn parse_full_game() {
+        let game = PGN::parse(FISCHER_SPASSKY).unwrap();
+        assert_eq!(game.tags().get("Site"), Some("Belgrade, Serbia JUG"));
+        assert_eq!(game.tags().date(), ChessDate::Full(1992, 11, 4));
+        assert_eq!(game.result(), GameResult::Draw);
+        assert_eq!(game.moves().moves.len(), 85);
+        assert_eq!(
+            game.moves().moves[5].comments,
+            vec!["This opening is called the Ruy Lopez.".to_string()]
+        );
+        assert_eq!(
+            game.board().unwrap().fen(),
+            "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43"
+        );
+    }
+
+    #[test]
+    fn parse_variations_and_nags() {
+        let input = "{Start} 1. e4 $1 e5 (1... c5 {Sicilian} 2. Nf3 (2. c3!?) 2... d6) \
+                     (1... e6) 2. Nf3 ?? *";
+        let game = PGN::parse(input).unwrap();
+        let main = game.moves();
+        assert_eq!(main.comments, vec!["Start".to_string()]);
+        assert_eq!(main.moves.len(), 3);
+        assert_eq!(main.moves[0].nags, vec![1]);
+        assert_eq!(main.moves[2].nags, vec![4]);
+        assert_eq!(game.result(), GameResult::Other);
+
+        let e5 = &main.moves[1];
+        assert_eq!(e5.variations.len(), 2);
+        let sicilian = &e5.variations[0];
+        assert_eq!(sicilian.moves.len(), 3);
+        assert_eq!(sicilian.moves[0].san, "c5");
+        assert_eq!(sicilian.moves[0].comments, vec!["Sicilian".to_string()]);
+        assert_eq!(sicilian.moves[1].variations[0].moves[0].san, "c3");
+        assert_eq!(sicilian.moves[1].variations[0].moves[0].nags, vec![5]);
+        assert_eq!(e5.variations[1].moves[0].san, "e6");
+    }
+
+    #[test]
+    fn parse_errors() {
+        match PGN::parse("1. e4 e5\n2. Ke3 *") {
+            Err(PGNError::IllegalMove { line, column, .. }) => {
+                assert_eq!((line, column), (2, 4));
+            }
+            _ => panic!(),
+        }
+        match PGN::parse("1. e4 (1. d4") {
+            Err(PGNError::UnexpectedEnd { .. }) => {}
+            _ => panic!(),
+        }
+        match PGN::parse("[Event \"x\"\n1. e4 *") {
+            Err(PGNError::TagParse { line, column }) => assert_eq!((line, column), (1, 1)),
+            _ => panic!(),
+        }
+        assert!(PGN::parse("[FEN \"8/8/8 w - - 0 1\"]\n\n*").is_err());
+        assert_eq!(
+            PGN::parse("\n\n% escaped\n  ").unwrap_err().position(),
+            Some((4, 3))
+        );
+    }
+
+    #[test]
+    fn parse_from_fen() {
+        let input = "[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 30\"]\n[SetUp \"1\"]\n\n\
+                     30... Kd7 31. O-O-O+ *";
+        let game = PGN::parse(input).unwrap();
+        assert_eq!(game.moves().moves.len(), 2);
+        assert!(game.moves().moves[1].bit_move.is_castle());
+        let written = game.to_string();
+        assert!(written.contains("\n30... Kd7 31. O-O-O+ *"));
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let input = "[Event \"Test\"]\n\n1. e4 {Best by test} e5 (1... c5 2. Nf3 (2. c3 $5) d6) \
+                     2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O \
+                     9. h3 Nb8 10. d4 Nbd7 1-0";
+        let game = PGN::parse(input).unwrap();
+        let written = game.to_string();
+        assert!(written.contains("[Result \"1-0\"]"));
+        assert!(written
+            .contains("1. e4 {Best by test} 1... e5 (1... c5 2. Nf3 (2. c3 $5) 2... d6) 2. Nf3"));
+        for line in written.lines() {
+            assert!(line.len() <= MAX_LINE_LENGTH);
+        }
+        let reparsed = PGN::parse(&written).unwrap();
+        assert_eq!(reparsed.moves(), game.moves());
+        assert_eq!(reparsed.result(), game.result());
+
+        let game = PGN::parse(FISCHER_SPASSKY).unwrap();
+        assert_eq!(PGN::parse(&game.to_string()).unwrap(), game);
+    }
+
+    static DATABASE: &str = "[Event \"One\"]
+[White \"Alice\"]
+
+1. e4 e5 2. Nf3 {A comment
+[that spans lines]} Nc6 1-0
+
+[Event \"Two\"]
+[White \"Bob\"]
+
+1. d4 d5 2. Kd3 Nf6 0-1
+[Event \"Three\"]
+[White \"Alice\"]
+[FEN \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\"]
+
+1. Kd2 *
+
+[Event \"Four\"]
+1. e4 {unterminated
+2. d4
+
+[Event \"Five\"]
+
+1. c4 1/2-1/2
+";
+
+    #[test]
+    fn reader_all_games() {
+        let games: Vec<_> = PGNReader::new(DATABASE.as_bytes()).collect();
+        assert_eq!(games.len(), 5);
+
+        let one = games[0].as_ref().unwrap();
+        assert_eq!(one.tags().get("Event"), Some("One"));
+        assert_eq!(one.moves().moves.len(), 4);
+        assert_eq!(
+            one.moves().moves[2].comments,
+            vec!["A comment\n[that spans lines]".to_string()]
+        );
+
+        match games[1] {
+            Err(PGNError::IllegalMove { line, column, .. }) => {
+                assert_eq!((line, column), (10, 13));
+            }
+            ref e => panic!("{:?}", e),
+        }
+
+        let three = games[2].as_ref().unwrap();
+        assert_eq!(three.result(), GameResult::Other);
+        assert_eq!(
+            three.board().unwrap().fen(),
+            "4k3/8/8/8/8/8/3K4/8 b - - 1 1"
+        );
+
+        match games[3] {
+            Err(PGNError::UnexpectedEnd { .. }) => {}
+            ref e => panic!("{:?}", e),
+        }
+
+        let five = games[4].as_ref().unwrap();
+        assert_eq!(five.tags().get("Event"), Some("Five"));
+        assert_eq!(five.result(), GameResult::Draw);
+    }
+
+    #[test]
+    fn reader_filter_skips_games() {
+        let events: Vec<String> = PGNReader::new(DATABASE.as_bytes())
+            .with_filter(|tags| tags.get("White") == Some("Alice"))
+            .map(|game| game.unwrap().tags().get("Event").unwrap().to_string())
+            .collect();
+        assert_eq!(events, vec!["One".to_string(), "Three".to_string()]);
+
+        // The illegal move of game two is never replayed
+        let bob: Vec<_> = PGNReader::new(DATABASE.as_bytes())
+            .with_filter(|tags| tags.get("White") == Some("Bob"))
+            .collect();
+        assert_eq!(bob.len(), 1);
+        assert!(bob[0].is_err());
+    }
+
+    #[test]
+    fn reader_resyncs_after_error() {
+        let input = "[Event \"Bad\"]\n\n1. e4 e5 2. Qxf7 *\n[Site \"Orphan\"]\n\n1. d4 *\n\n\
+                     [Event \"Good\"]\n\n1. e4 *\n";
+        let games: Vec<_> = PGNReader::new(input.as_bytes()).collect();
+        assert_eq!(games.len(), 2);
+        assert!(games[0].is_err());
+        assert_eq!(games[1].as_ref().unwrap().tags().get("Event"), Some("Good"));
+
+        let input = "[FEN \"not a fen\"]\n\n*";
+        match PGNReader::new(input.as_bytes()).next() {
+            Some(Err(PGNError::InvalidFen { line, column, .. })) => {
+                assert_eq!((line, column), (1, 1));
+            }
+            e => panic!("{:?}", e),
+        }
     }
 }
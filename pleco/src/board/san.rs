@@ -0,0 +1,381 @@
+//! Contains functions for reading and writing moves in Standard Algebraic Notation (SAN).
+//!
+//! SAN is the notation used by PGN files and most chess GUIs, e.g. `e4`, `Nbd7`, `exd5`,
+//! `O-O+` or `e8=Q#`. Unlike UCI notation, a SAN move can only be understood in the context
+//! of a position, so both directions go through a [`Board`].
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::Board;
+//!
+//! let mut board = Board::start_pos();
+//! let mov = board.parse_san("Nf3").unwrap();
+//! assert_eq!(board.move_to_san(mov), "Nf3");
+//!
+//! board.apply_move(mov);
+//! assert!(board.parse_san("Nf6").is_ok());
+//! ```
+//!
+//! [`Board`]: ../struct.Board.html
+
+use std::fmt;
+
+use super::Board;
+use core::masks::{FILE_DISPLAYS, RANK_DISPLAYS};
+use core::move_list::CrazyhouseMoveList;
+use core::piece_move::BitMove;
+use core::sq::SQ;
+use core::{File, PieceType, Rank, ALL_FILES, ALL_RANKS};
+
+/// Represents possible errors encountered while parsing a SAN move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SanError {
+    /// The input contained no move.
+    Empty,
+    /// The input could not be read as a SAN move.
+    Malformed { san: String },
+    /// The move is well formed, but no legal move in the position matches it.
+    IllegalMove { san: String },
+    /// More than one legal move in the position matches the input.
+    Ambiguous { san: String },
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SanError::Empty => write!(f, "empty SAN move"),
+            SanError::Malformed { ref san } => write!(f, "malformed SAN move: {}", san),
+            SanError::IllegalMove { ref san } => {
+                write!(f, "no legal move matches SAN move: {}", san)
+            }
+            SanError::Ambiguous { ref san } => write!(f, "ambiguous SAN move: {}", san),
+        }
+    }
+}
+
+impl Board {
+    /// Returns the Standard Algebraic Notation of a move, including any disambiguation and
+    /// check (`+`) or checkmate (`#`) suffix.
+    ///
+    /// # Safety
+    ///
+    /// The move must be legal for the current position, or else a panic may occur.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,BitMove,SQ};
+    ///
+    /// let board = Board::start_pos();
+    /// let mov = BitMove::make_pawn_push(SQ::E2, SQ::E4);
+    /// assert_eq!(board.move_to_san(mov), "e4");
+    /// ```
+    pub fn move_to_san(&self, mov: BitMove) -> String {
+        let mut san = String::with_capacity(8);
+        // Has room for the moves of a Crazyhouse position
+        let legal_moves: CrazyhouseMoveList = self.generate_crazyhouse_moves();
+        debug_assert!(legal_moves.contains(&mov));
+
+        if mov.is_castle() {
+            san.push_str(if mov.is_king_castle() { "O-O" } else { "O-O-O" });
+        } else if mov.is_drop() {
+            // Crazyhouse drops, with pawn drops leaving out the piece
+            if mov.drop_piece() != PieceType::P {
+                san.push(mov.drop_piece().char_upper());
+            }
+            san.push('@');
+            san.push_str(&mov.get_dest().to_string());
+        } else {
+            let src: SQ = mov.get_src();
+            let dst: SQ = mov.get_dest();
+            let piece: PieceType = self.piece_at_sq(src).type_of();
+            let capture: bool = self.is_capture(mov);
+
+            if piece == PieceType::P {
+                if capture {
+                    san.push(FILE_DISPLAYS[src.file() as usize]);
+                }
+            } else {
+                san.push(piece.char_upper());
+
+                // Other pieces of the same type that can also move to the destination
+                let mut ambiguous_file = false;
+                let mut ambiguous_rank = false;
+                let mut ambiguous = false;
+                for other in legal_moves.iter() {
+                    let other_src = other.get_src();
+                    if other_src != src
+                        && other.get_dest() == dst
+                        && !other.is_castle()
+                        && !other.is_drop()
+                        && self.piece_at_sq(other_src).type_of() == piece
+                    {
+                        ambiguous = true;
+                        ambiguous_file |= other_src.file() == src.file();
+                        ambiguous_rank |= other_src.rank() == src.rank();
+                    }
+                }
+
+                if ambiguous {
+                    if !ambiguous_file {
+                        san.push(FILE_DISPLAYS[src.file() as usize]);
+                    } else if !ambiguous_rank {
+                        san.push(RANK_DISPLAYS[src.rank() as usize]);
+                    } else {
+                        san.push_str(&src.to_string());
+                    }
+                }
+            }
+
+            if capture {
+                san.push('x');
+            }
+            san.push_str(&dst.to_string());
+
+            if mov.is_promo() {
+                san.push('=');
+                san.push(mov.promo_piece().char_upper());
+            }
+        }
+
+        if self.gives_check(mov) {
+            let mut after = self.shallow_clone();
+            after.apply_move(mov);
+            san.push(if after.with_legal_moves(|moves| moves.is_empty()) {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    /// Parses a move in Standard Algebraic Notation, returning the matching legal move.
+    ///
+    /// Parsing is lenient: check and checkmate suffixes, annotations such as `!` or `?!`,
+    /// capture markers and the `=` of a promotion are all optional, castling may be written
+    /// with either the letter `O` or the digit `0`, and over-specified moves (like `Ng1f3`)
+    /// are accepted as long as they identify exactly one legal move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{Board,SQ};
+    ///
+    /// let board = Board::start_pos();
+    /// let mov = board.parse_san("Nf3").unwrap();
+    /// assert_eq!(mov.get_src(), SQ::G1);
+    /// assert_eq!(mov.get_dest(), SQ::F3);
+    ///
+    /// assert!(board.parse_san("Nf4").is_err());
+    /// ```
+    pub fn parse_san(&self, san: &str) -> Result<BitMove, SanError> {
+        let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        if trimmed.is_empty() {
+            return Err(SanError::Empty);
+        }
+
+        let malformed = || SanError::Malformed {
+            san: san.to_string(),
+        };
+        // Has room for the moves of a Crazyhouse position
+        let legal_moves: CrazyhouseMoveList = self.generate_crazyhouse_moves();
+
+        // Castling
+        let castle = match trimmed {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castle {
+            return legal_moves
+                .iter()
+                .find(|m| m.is_castle() && m.is_king_castle() == king_side)
+                .cloned()
+                .ok_or_else(|| SanError::IllegalMove {
+                    san: san.to_string(),
+                });
+        }
+
+        // Crazyhouse drops, like `N@f3`, with either `@e4` or `P@e4` for a pawn
+        if let Some(at) = trimmed.find('@') {
+            let piece = match &trimmed[..at] {
+                "" | "P" => PieceType::P,
+                "N" => PieceType::N,
+                "B" => PieceType::B,
+                "R" => PieceType::R,
+                "Q" => PieceType::Q,
+                _ => return Err(malformed()),
+            };
+            let dst: SQ = trimmed[(at + 1)..].parse::<SQ>().map_err(|_| malformed())?;
+            let mov = BitMove::make_drop(piece, dst);
+            if !legal_moves.contains(&mov) {
+                return Err(SanError::IllegalMove {
+                    san: san.to_string(),
+                });
+            }
+            return Ok(mov);
+        }
+
+        let mut chars: Vec<char> = trimmed
+            .chars()
+            .filter(|&c| c != 'x' && c != ':' && c != '-' && c != '=')
+            .collect();
+
+        let piece: PieceType = match chars.first() {
+            Some('N') => PieceType::N,
+            Some('B') => PieceType::B,
+            Some('R') => PieceType::R,
+            Some('Q') => PieceType::Q,
+            Some('K') => PieceType::K,
+            _ => PieceType::P,
+        };
+        if piece != PieceType::P {
+            chars.remove(0);
+        }
+
+        let mut promo: Option<PieceType> = None;
+        if piece == PieceType::P && chars.len() > 2 {
+            promo = match chars[chars.len() - 1] {
+                'N' | 'n' => Some(PieceType::N),
+                'B' | 'b' => Some(PieceType::B),
+                'R' | 'r' => Some(PieceType::R),
+                'Q' | 'q' => Some(PieceType::Q),
+                'K' | 'k' => Some(PieceType::K),
+                _ => None,
+            };
+            if promo.is_some() {
+                chars.pop();
+            }
+        }
+
+        if chars.len() < 2 || chars.len() > 4 {
+            return Err(malformed());
+        }
+
+        let dst_str: String = chars[(chars.len() - 2)..].iter().collect();
+        let dst: SQ = dst_str.parse::<SQ>().map_err(|_| malformed())?;
+
+        // Anything before the destination is disambiguation
+        let mut src_file: Option<File> = None;
+        let mut src_rank: Option<Rank> = None;
+        for &c in chars[..(chars.len() - 2)].iter() {
+            match c {
+                'a'..='h' if src_file.is_none() => {
+                    src_file = Some(ALL_FILES[(c as u8 - b'a') as usize]);
+                }
+                '1'..='8' if src_rank.is_none() => {
+                    src_rank = Some(ALL_RANKS[(c as u8 - b'1') as usize]);
+                }
+                _ => return Err(malformed()),
+            }
+        }
+
+        let mut found: Option<BitMove> = None;
+        for mov in legal_moves.iter() {
+            let src = mov.get_src();
+            if mov.is_castle()
+                || mov.is_drop()
+                || mov.get_dest() != dst
+                || self.piece_at_sq(src).type_of() != piece
+                || src_file.is_some_and(|f| f != src.file())
+                || src_rank.is_some_and(|r| r != src.rank())
+            {
+                continue;
+            }
+
+            let mov_promo = if mov.is_promo() {
+                Some(mov.promo_piece())
+            } else {
+                None
+            };
+            if mov_promo != promo {
+                continue;
+            }
+
+            if found.is_some() {
+                return Err(SanError::Ambiguous {
+                    san: san.to_string(),
+                });
+            }
+            found = Some(*mov);
+        }
+
+        found.ok_or_else(|| SanError::IllegalMove {
+            san: san.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(board: &Board) {
+        for mov in board.generate_moves().iter() {
+            let san = board.move_to_san(*mov);
+            assert_eq!(board.parse_san(&san), Ok(*mov), "{} {}", board.fen(), san);
+        }
+    }
+
+    #[test]
+    fn san_round_trip_all_fens() {
+        for fen in super::super::fen::ALL_FENS.iter() {
+            round_trip(&Board::from_fen(fen).unwrap());
+        }
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1").unwrap();
+        let mov = board.parse_san("Rad1").unwrap();
+        assert_eq!(mov.get_src(), SQ::A1);
+        assert_eq!(board.move_to_san(mov), "Rad1");
+        assert_eq!(
+            board.parse_san("Rd1"),
+            Err(SanError::Ambiguous {
+                san: "Rd1".to_string()
+            })
+        );
+
+        let board = Board::from_fen("4k3/8/8/N7/8/8/8/N3K3 w - - 0 1").unwrap();
+        let mov = board.parse_san("N1b3").unwrap();
+        assert_eq!(mov.get_src(), SQ::A1);
+        assert_eq!(board.move_to_san(mov), "N1b3");
+
+        let board = Board::from_fen("8/7k/8/8/Q5Q1/8/8/Q3K3 w - - 0 1").unwrap();
+        let mov = board.parse_san("Qa4d1").unwrap();
+        assert_eq!(board.move_to_san(mov), "Qa4d1");
+    }
+
+    #[test]
+    fn san_suffixes_and_castling() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let k_castle = board.parse_san("O-O").unwrap();
+        assert!(k_castle.is_king_castle());
+        assert_eq!(board.move_to_san(k_castle), "O-O");
+        assert_eq!(board.parse_san("0-0-0"), board.parse_san("O-O-O+"));
+
+        let board = Board::from_fen("7k/4P3/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        let promo = board.parse_san("e8=Q#").unwrap();
+        assert_eq!(promo.promo_piece(), PieceType::Q);
+        assert_eq!(board.move_to_san(promo), "e8=Q#");
+        assert_eq!(board.parse_san("e8Q"), Ok(promo));
+        assert_eq!(board.move_to_san(board.parse_san("e8=R").unwrap()), "e8=R#");
+        assert_eq!(board.move_to_san(board.parse_san("e8=N").unwrap()), "e8=N");
+    }
+
+    #[test]
+    fn san_pawn_captures() {
+        let board =
+            Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2")
+                .unwrap();
+        let mov = board.parse_san("exd5").unwrap();
+        assert_eq!(board.move_to_san(mov), "exd5");
+        assert_eq!(board.parse_san("ed5"), Ok(mov));
+        assert_eq!(board.parse_san("exd5!?"), Ok(mov));
+        assert_eq!(board.parse_san(""), Err(SanError::Empty));
+        assert!(board.parse_san("Zz9").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! `Serialize` and `Deserialize` implementations for the [`Board`], enabled by the `serde` feature.
+//!
+//! A `Board` is serialized as the FEN of the position it was created from, alongside the moves
+//! played since then. The move history is left out when no moves have been played, and when
+//! deserializing, a missing move history is read as an empty one.
+//!
+//! [`Board`]: ../struct.Board.html
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::variant::Variant;
+use super::Board;
+use core::piece_move::BitMove;
+
+/// The serialized form of a `Board`.
+#[derive(Serialize, Deserialize)]
+struct SerdeBoard {
+    fen: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    moves: Vec<BitMove>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    chess960: bool,
+    #[serde(default, skip_serializing_if = "is_standard")]
+    variant: Variant,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+fn is_standard(variant: &Variant) -> bool {
+    *variant == Variant::Standard
+}
+
+impl Serialize for Board {
+    /// Serializes the `Board` as the FEN of its starting position and the moves played since.
+    ///
+    /// Null moves can't be undone, so if one was played, the starting position is the one
+    /// right after the last null move.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut moves: Vec<BitMove> = self.state.history()[1..]
+            .iter()
+            .rev()
+            .map(|state| state.prev_move)
+            .take_while(|mov| !mov.is_null())
+            .collect();
+        moves.reverse();
+
+        let mut start = self.shallow_clone();
+        start.state = self.state.clone();
+        start.depth = moves.len() as u16;
+        for _ in 0..moves.len() {
+            start.undo_move();
+        }
+
+        SerdeBoard {
+            fen: start.fen(),
+            moves,
+            chess960: self.chess960,
+            variant: self.variant,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        let serde_board = SerdeBoard::deserialize(deserializer)?;
+        let mut board = Board::from_fen_variant(&serde_board.fen, serde_board.variant)
+            .map_err(|_| D::Error::custom(format!("invalid FEN: {}", serde_board.fen)))?;
+        if serde_board.chess960 {
+            board.set_chess960(true);
+        }
+
+        for mov in serde_board.moves {
+            if !board.with_legal_moves(|moves| moves.contains(&mov)) {
+                return Err(D::Error::custom(format!(
+                    "illegal move {} in position {}",
+                    board.move_to_uci(mov),
+                    board.fen()
+                )));
+            }
+            board.apply_move(mov);
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use board::castle_rights::Castling;
+    use core::score::Score;
+    use core::sq::{NO_SQ, SQ};
+    use core::*;
+    use {BitBoard, ScoringMove};
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn serde_core_types() {
+        assert_eq!(serde_json::to_string(&SQ::E4).unwrap(), "28");
+        assert_eq!(round_trip(&NO_SQ), NO_SQ);
+        assert!(serde_json::from_str::<SQ>("65").is_err());
+
+        assert_eq!(round_trip(&BitBoard::RANK_2), BitBoard::RANK_2);
+        assert_eq!(round_trip(&Player::Black), Player::Black);
+        assert_eq!(round_trip(&PieceType::N), PieceType::N);
+        assert_eq!(round_trip(&Piece::WhiteQueen), Piece::WhiteQueen);
+        assert_eq!(round_trip(&File::H), File::H);
+        assert_eq!(round_trip(&Rank::R3), Rank::R3);
+        assert_eq!(round_trip(&Score(12, -40)), Score(12, -40));
+        assert_eq!(round_trip(&Castling::all()), Castling::all());
+        assert!(serde_json::from_str::<Castling>("255").is_err());
+
+        let mov = BitMove::make_quiet(SQ::G1, SQ::F3);
+        assert_eq!(round_trip(&mov), mov);
+        let scoring = ScoringMove::new_score(mov, 35);
+        let scoring_rt = round_trip(&scoring);
+        assert_eq!(scoring_rt.bit_move, mov);
+        assert_eq!(scoring_rt.score, 35);
+    }
+
+    #[test]
+    fn serde_board() {
+        let board = Board::start_pos();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(
+            json,
+            "{\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\"}"
+        );
+
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        for mov in ["e1g1", "e8c8", "a1a7"].iter() {
+            assert!(board.apply_uci_move(mov));
+        }
+        let board_rt: Board = round_trip(&board);
+        assert_eq!(board_rt, board);
+        assert_eq!(board_rt.moves_played(), 3);
+
+        let board = Board::start_pos_chess960(0).unwrap();
+        let board_rt: Board = round_trip(&board);
+        assert!(board_rt.is_chess960());
+        assert_eq!(board_rt.fen(), board.fen());
+
+        let mut board = Board::start_pos_variant(Variant::ThreeCheck);
+        for mov in ["e2e4", "f7f6", "d1h5"].iter() {
+            assert!(board.apply_uci_move(mov));
+        }
+        let board_rt: Board = round_trip(&board);
+        assert_eq!(board_rt.variant(), Variant::ThreeCheck);
+        assert_eq!(board_rt, board);
+        assert_eq!(board_rt.checks_given(Player::White), 1);
+    }
+
+    #[test]
+    fn serde_board_errors() {
+        assert!(serde_json::from_str::<Board>("{\"fen\":\"8/8/8 w - -\"}").is_err());
+        let e2e5 = BitMove::make_quiet(SQ::E2, SQ::E5).get_raw();
+        let json = format!(
+            "{{\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\"moves\":[{}]}}",
+            e2e5
+        );
+        assert!(serde_json::from_str::<Board>(&json).is_err());
+    }
+}
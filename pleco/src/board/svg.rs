@@ -0,0 +1,602 @@
+//! Module for rendering a [`Board`] as a Scalable Vector Graphics (SVG) image.
+//!
+//! A [`BoardSvg`] is created with [`Board::svg`], and configured with its builder methods
+//! before being rendered to a `String`. The board can be drawn from either player's side, with
+//! or without coordinates, and with the last move and a checked king highlighted. Arrows and
+//! marked squares can be drawn over the pieces, and every colour can be changed through a
+//! [`Theme`].
+//!
+//! The piece glyphs are embedded in the image, so it can be displayed without any other files.
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::{Board, Player, SQ, BitMove};
+//!
+//! let board = Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2")
+//!     .unwrap();
+//!
+//! let svg: String = board
+//!     .svg()
+//!     .orientation(Player::Black)
+//!     .arrows(&[BitMove::make_quiet(SQ::G1, SQ::F3)])
+//!     .marks(&[SQ::E5])
+//!     .size(400)
+//!     .render();
+//!
+//! assert!(svg.starts_with("<svg"));
+//! ```
+//!
+//! [`Board`]: ../struct.Board.html
+//! [`Board::svg`]: ../struct.Board.html#method.svg
+//! [`BoardSvg`]: struct.BoardSvg.html
+//! [`Theme`]: struct.Theme.html
+
+use std::fmt::Write;
+
+use super::Board;
+
+use core::bitboard::BitBoard;
+use core::masks::*;
+use core::piece_move::BitMove;
+use core::sq::SQ;
+use core::*;
+
+/// The width of a single square, in the units of the image.
+const SQUARE_SIZE: f32 = 45.0;
+/// The width of the border holding the coordinates, in the units of the image.
+const MARGIN: f32 = 15.0;
+
+/// The colours used to draw a board.
+///
+/// Each colour is an SVG colour, such as `"#f0d9b5"` or `"green"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// The colour of the light squares.
+    pub light_square: String,
+    /// The colour of the dark squares.
+    pub dark_square: String,
+    /// The colour of a light square the last move was made from or to.
+    pub last_move_light: String,
+    /// The colour of a dark square the last move was made from or to.
+    pub last_move_dark: String,
+    /// The colour of the square of a king in check.
+    pub check: String,
+    /// The colour of the arrows.
+    pub arrow: String,
+    /// The colour of the marks around squares.
+    pub mark: String,
+    /// The colour of the border around the board.
+    pub border: String,
+    /// The colour of the coordinates.
+    pub coordinates: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            light_square: "#f0d9b5".to_string(),
+            dark_square: "#b58863".to_string(),
+            last_move_light: "#cdd26a".to_string(),
+            last_move_dark: "#aaa23a".to_string(),
+            check: "#e0413a".to_string(),
+            arrow: "#15781b".to_string(),
+            mark: "#1f5fbf".to_string(),
+            border: "#212121".to_string(),
+            coordinates: "#e5e5e5".to_string(),
+        }
+    }
+}
+
+/// Renders a [`Board`] as an SVG image. Created with [`Board::svg`].
+///
+/// By default, the board is drawn from White's side with coordinates, and the board's last
+/// move and a king in check are highlighted.
+///
+/// [`Board`]: ../struct.Board.html
+/// [`Board::svg`]: ../struct.Board.html#method.svg
+#[derive(Clone)]
+pub struct BoardSvg<'a> {
+    board: &'a Board,
+    orientation: Player,
+    coordinates: bool,
+    last_move: Option<BitMove>,
+    check: bool,
+    arrows: Vec<BitMove>,
+    marks: Vec<SQ>,
+    theme: Theme,
+    size: Option<u32>,
+}
+
+impl<'a> BoardSvg<'a> {
+    /// Creates a `BoardSvg` of a board, with the default options.
+    pub fn new(board: &'a Board) -> Self {
+        BoardSvg {
+            board,
+            orientation: Player::White,
+            coordinates: true,
+            last_move: board.last_move(),
+            check: true,
+            arrows: Vec::new(),
+            marks: Vec::new(),
+            theme: Theme::default(),
+            size: None,
+        }
+    }
+
+    /// Sets the player whose side of the board is drawn at the bottom.
+    pub fn orientation(mut self, player: Player) -> Self {
+        self.orientation = player;
+        self
+    }
+
+    /// Sets whether the files and ranks are drawn around the board.
+    pub fn coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Sets the move to highlight, or `None` to highlight no move. Defaults to the last move
+    /// played on the board.
+    pub fn last_move(mut self, mov: Option<BitMove>) -> Self {
+        self.last_move = mov;
+        self
+    }
+
+    /// Sets whether the king of the player to move is highlighted when in check.
+    pub fn check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// Adds arrows from the source to the destination square of each move. A drop is drawn as
+    /// a mark on its destination square.
+    pub fn arrows(mut self, moves: &[BitMove]) -> Self {
+        self.arrows.extend_from_slice(moves);
+        self
+    }
+
+    /// Adds a mark around each of the squares.
+    pub fn marks(mut self, squares: &[SQ]) -> Self {
+        self.marks.extend_from_slice(squares);
+        self
+    }
+
+    /// Sets the colours to draw the board with.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the width and height of the image, in pixels. By default the image is 360 pixels
+    /// wide, plus the border with the coordinates.
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Renders the image as a `String`.
+    pub fn render(&self) -> String {
+        let margin = if self.coordinates { MARGIN } else { 0.0 };
+        let full = SQUARE_SIZE * 8.0 + margin * 2.0;
+        let size = match self.size {
+            Some(size) => size.to_string(),
+            None => full.to_string(),
+        };
+
+        let mut svg = String::with_capacity(16 * 1024);
+        let _ = write!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+             xmlns:xlink=\"http://www.w3.org/1999/xlink\" version=\"1.1\" \
+             viewBox=\"0 0 {full} {full}\" width=\"{size}\" height=\"{size}\">",
+            full = full,
+            size = size
+        );
+
+        svg.push_str("<defs>");
+        let mut defined = [false; PIECE_CNT];
+        for sq in 0..SQ_CNT as u8 {
+            let piece = self.board.piece_at_sq(SQ(sq));
+            if piece != Piece::None && !defined[piece as usize] {
+                defined[piece as usize] = true;
+                svg.push_str(&glyph(piece));
+            }
+        }
+        svg.push_str("</defs>");
+
+        if self.coordinates {
+            let _ = write!(
+                svg,
+                "<rect x=\"0\" y=\"0\" width=\"{full}\" height=\"{full}\" fill=\"{}\"/>",
+                escape(&self.theme.border),
+                full = full
+            );
+        }
+
+        let highlighted: BitBoard = match self.last_move {
+            Some(mov) if !mov.is_null() && mov.is_drop() => mov.get_dest().to_bb(),
+            Some(mov) if !mov.is_null() => mov.get_src().to_bb() | mov.get_dest().to_bb(),
+            _ => BitBoard(0),
+        };
+        let checked: BitBoard = if self.check && self.board.in_check() {
+            self.board.piece_bb(self.board.turn(), PieceType::K)
+        } else {
+            BitBoard(0)
+        };
+
+        for sq in 0..SQ_CNT as u8 {
+            let sq = SQ(sq);
+            let (x, y) = self.square_origin(sq, margin);
+            let light = (sq.rank() as u8 + sq.file() as u8) % 2 == 1;
+            let fill = if (checked & sq.to_bb()).is_not_empty() {
+                &self.theme.check
+            } else if (highlighted & sq.to_bb()).is_not_empty() {
+                if light {
+                    &self.theme.last_move_light
+                } else {
+                    &self.theme.last_move_dark
+                }
+            } else if light {
+                &self.theme.light_square
+            } else {
+                &self.theme.dark_square
+            };
+            let _ = write!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\"/>",
+                x,
+                y,
+                escape(fill),
+                s = SQUARE_SIZE
+            );
+        }
+
+        if self.coordinates {
+            self.render_coordinates(&mut svg);
+        }
+
+        for sq in 0..SQ_CNT as u8 {
+            let piece = self.board.piece_at_sq(SQ(sq));
+            if piece != Piece::None {
+                let (x, y) = self.square_origin(SQ(sq), margin);
+                let _ = write!(
+                    svg,
+                    "<use xlink:href=\"#{}\" x=\"{}\" y=\"{}\"/>",
+                    glyph_id(piece),
+                    x,
+                    y
+                );
+            }
+        }
+
+        let drops = self
+            .arrows
+            .iter()
+            .filter(|m| m.is_drop())
+            .map(|m| m.get_dest());
+        for sq in self.marks.iter().cloned().chain(drops) {
+            let (x, y) = self.square_center(sq, margin);
+            let _ = write!(
+                svg,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" \
+                 stroke-width=\"{}\" opacity=\"0.8\"/>",
+                x,
+                y,
+                SQUARE_SIZE * 0.45,
+                escape(&self.theme.mark),
+                SQUARE_SIZE * 0.07
+            );
+        }
+
+        for mov in self.arrows.iter().filter(|m| !m.is_null() && !m.is_drop()) {
+            self.render_arrow(&mut svg, *mov, margin);
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Returns the coordinates of the top left corner of a square.
+    fn square_origin(&self, sq: SQ, margin: f32) -> (f32, f32) {
+        let (col, row) = match self.orientation {
+            Player::White => (sq.file() as u8, 7 - sq.rank() as u8),
+            Player::Black => (7 - sq.file() as u8, sq.rank() as u8),
+        };
+        (
+            margin + f32::from(col) * SQUARE_SIZE,
+            margin + f32::from(row) * SQUARE_SIZE,
+        )
+    }
+
+    /// Returns the coordinates of the centre of a square.
+    fn square_center(&self, sq: SQ, margin: f32) -> (f32, f32) {
+        let (x, y) = self.square_origin(sq, margin);
+        (x + SQUARE_SIZE / 2.0, y + SQUARE_SIZE / 2.0)
+    }
+
+    /// Writes the file letters above and below the board, and the rank numbers on either
+    /// side of it.
+    fn render_coordinates(&self, svg: &mut String) {
+        let far = MARGIN + SQUARE_SIZE * 8.0 + MARGIN / 2.0;
+        for i in 0..8u8 {
+            let (file, rank) = match self.orientation {
+                Player::White => (i, 7 - i),
+                Player::Black => (7 - i, i),
+            };
+            let middle = MARGIN + (f32::from(i) + 0.5) * SQUARE_SIZE;
+            let file = (b'a' + file) as char;
+            let rank = (b'1' + rank) as char;
+            for &(x, y, c) in [
+                (middle, MARGIN / 2.0, file),
+                (middle, far, file),
+                (MARGIN / 2.0, middle, rank),
+                (far, middle, rank),
+            ]
+            .iter()
+            {
+                let _ = write!(
+                    svg,
+                    "<text x=\"{}\" y=\"{}\" fill=\"{}\" font-family=\"sans-serif\" \
+                     font-size=\"10\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                    x,
+                    y,
+                    escape(&self.theme.coordinates),
+                    c
+                );
+            }
+        }
+    }
+
+    /// Writes an arrow from the source square of a move to its destination square.
+    fn render_arrow(&self, svg: &mut String, mov: BitMove, margin: f32) {
+        let (x0, y0) = self.square_center(mov.get_src(), margin);
+        let (x1, y1) = self.square_center(mov.get_dest(), margin);
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            return;
+        }
+        // Unit vectors along and across the arrow
+        let (ux, uy) = (dx / length, dy / length);
+        let (px, py) = (-uy, ux);
+
+        let head_length = SQUARE_SIZE * 0.5;
+        let head_width = SQUARE_SIZE * 0.5;
+        let tip = (x1 - ux * SQUARE_SIZE * 0.1, y1 - uy * SQUARE_SIZE * 0.1);
+        let base = (tip.0 - ux * head_length, tip.1 - uy * head_length);
+
+        let color = escape(&self.theme.arrow);
+        let _ = write!(
+            svg,
+            "<g opacity=\"0.8\"><line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" \
+             stroke=\"{}\" stroke-width=\"{}\"/>",
+            x0,
+            y0,
+            base.0,
+            base.1,
+            color,
+            SQUARE_SIZE * 0.2
+        );
+        let _ = write!(
+            svg,
+            "<polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"{}\"/></g>",
+            tip.0,
+            tip.1,
+            base.0 + px * head_width / 2.0,
+            base.1 + py * head_width / 2.0,
+            base.0 - px * head_width / 2.0,
+            base.1 - py * head_width / 2.0,
+            color
+        );
+    }
+}
+
+impl Board {
+    /// Returns a [`BoardSvg`] for rendering the board as an SVG image.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::Board;
+    ///
+    /// let svg: String = Board::start_pos().svg().coordinates(false).render();
+    /// assert!(svg.ends_with("</svg>"));
+    /// ```
+    ///
+    /// [`BoardSvg`]: svg/struct.BoardSvg.html
+    pub fn svg<'a>(&'a self) -> BoardSvg<'a> {
+        BoardSvg::new(self)
+    }
+}
+
+/// Returns the id of a piece's glyph in the image.
+fn glyph_id(piece: Piece) -> &'static str {
+    match piece {
+        Piece::WhitePawn => "white-pawn",
+        Piece::WhiteKnight => "white-knight",
+        Piece::WhiteBishop => "white-bishop",
+        Piece::WhiteRook => "white-rook",
+        Piece::WhiteQueen => "white-queen",
+        Piece::WhiteKing => "white-king",
+        Piece::BlackPawn => "black-pawn",
+        Piece::BlackKnight => "black-knight",
+        Piece::BlackBishop => "black-bishop",
+        Piece::BlackRook => "black-rook",
+        Piece::BlackQueen => "black-queen",
+        Piece::BlackKing => "black-king",
+        Piece::None => "none",
+    }
+}
+
+/// Returns the definition of a piece's glyph, drawn in a square of `SQUARE_SIZE`.
+fn glyph(piece: Piece) -> String {
+    // White pieces are outlined in black, and black pieces have their details drawn in white
+    let (fill, detail) = match piece.player() {
+        Some(Player::White) => ("#fff", "#000"),
+        _ => ("#000", "#fff"),
+    };
+    let body = match piece.type_of() {
+        PieceType::P => "<path d=\"M22.5 9a4 4 0 0 0-3.2 6.4a6 6 0 0 0-0.9 10.6C15.4 27.1 11 \
+                         31.6 11 39.5H34C34 31.6 29.6 27.1 26.6 26a6 6 0 0 0-0.9-10.6A4 4 0 0 0 \
+                         22.5 9z\"/>"
+            .to_string(),
+        PieceType::N => format!(
+            "<path d=\"M22 10C32.5 11 38.5 18 38 39H15C15 30 25 32.5 23 18C22 22 17 25 14 \
+             27C11 29 10 31 8 30C6 29 6 26 7 24L12 14L14 10L13.5 7.5L16.5 10H18.5L21 7z\"/>\
+             <circle cx=\"12\" cy=\"25.5\" r=\"0.8\" fill=\"{d}\" stroke=\"{d}\"/>\
+             <circle cx=\"15\" cy=\"15.5\" r=\"1\" fill=\"{d}\" stroke=\"{d}\"/>",
+            d = detail
+        ),
+        PieceType::B => format!(
+            "<path d=\"M11 39H34V36H11z\"/>\
+             <path d=\"M15 36C15 31 17 29 17 29C13 26 13 19 22.5 11C32 19 32 26 28 29C28 29 30 \
+             31 30 36z\"/><circle cx=\"22.5\" cy=\"8.5\" r=\"2.5\"/>\
+             <path d=\"M22.5 17V25M18.5 21H26.5M17 29H28\" fill=\"none\" stroke=\"{d}\"/>",
+            d = detail
+        ),
+        PieceType::R => format!(
+            "<path d=\"M9 39H36V36H9zM12 36V32H33V36zM11 17V9H15V12H20V9H25V12H30V9H34V17z\"/>\
+             <path d=\"M14 32V17H31V32z\"/>\
+             <path d=\"M14 17H31M14 32H31\" fill=\"none\" stroke=\"{d}\" stroke-width=\"1\"/>",
+            d = detail
+        ),
+        PieceType::Q => format!(
+            "<path d=\"M9 26L6.5 13.5L14 25L14.3 10.9L19.5 24.5L22.5 10L25.5 24.5L30.7 10.9L31 \
+             25L38.5 13.5L36 26z\"/>\
+             <path d=\"M9 26L11.5 30L12 33.5L11 38.5H34L33 33.5L33.5 30L36 26z\"/>\
+             <circle cx=\"6\" cy=\"12\" r=\"2\"/><circle cx=\"14\" cy=\"9\" r=\"2\"/>\
+             <circle cx=\"22.5\" cy=\"8\" r=\"2\"/><circle cx=\"31\" cy=\"9\" r=\"2\"/>\
+             <circle cx=\"39\" cy=\"12\" r=\"2\"/>\
+             <path d=\"M11.5 30H33.5M12 33.5H33\" fill=\"none\" stroke=\"{d}\"/>",
+            d = detail
+        ),
+        PieceType::K => format!(
+            "<path d=\"M22.5 11.6V6M20 8H25\" fill=\"none\"/>\
+             <path d=\"M22.5 25C22.5 25 27 17.5 25.5 14.5C25.5 14.5 24.5 12 22.5 12C20.5 12 19.5 \
+             14.5 19.5 14.5C18 17.5 22.5 25 22.5 25\"/>\
+             <path d=\"M12.5 37C18 40.5 27 40.5 32.5 37V30C32.5 30 41.5 25.5 38.5 19.5C34.5 13 \
+             25 16 22.5 23.5V27V23.5C20 16 10.5 13 6.5 19.5C3.5 25.5 12.5 30 12.5 30z\"/>\
+             <path d=\"M12.5 30C18 27 27 27 32.5 30M12.5 33.5C18 30.5 27 30.5 32.5 33.5\" \
+             fill=\"none\" stroke=\"{d}\"/>",
+            d = detail
+        ),
+        _ => String::new(),
+    };
+    format!(
+        "<g id=\"{}\" fill=\"{}\" stroke=\"#000\" stroke-width=\"1.5\" stroke-linecap=\"round\" \
+         stroke-linejoin=\"round\">{}</g>",
+        glyph_id(piece),
+        fill,
+        body
+    )
+}
+
+/// Escapes a string for use in an attribute value.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(svg: &str, pattern: &str) -> usize {
+        svg.matches(pattern).count()
+    }
+
+    #[test]
+    fn svg_start_pos() {
+        let board = Board::start_pos();
+        let svg = board.svg().render();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(count(&svg, "<use "), 32);
+        assert_eq!(count(&svg, "<g id="), 12);
+        assert_eq!(count(&svg, "<text "), 32);
+        assert_eq!(count(&svg, "width=\"390\""), 2);
+
+        let svg = board.svg().coordinates(false).size(200).render();
+        assert_eq!(count(&svg, "<text "), 0);
+        assert!(svg.contains("viewBox=\"0 0 360 360\" width=\"200\" height=\"200\""));
+    }
+
+    #[test]
+    fn svg_orientation() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let svg = board.svg().coordinates(false).render();
+        assert!(svg.contains("<use xlink:href=\"#white-rook\" x=\"0\" y=\"315\"/>"));
+        assert!(svg.contains("<use xlink:href=\"#black-king\" x=\"180\" y=\"0\"/>"));
+
+        let svg = board
+            .svg()
+            .coordinates(false)
+            .orientation(Player::Black)
+            .render();
+        assert!(svg.contains("<use xlink:href=\"#white-rook\" x=\"315\" y=\"0\"/>"));
+        assert!(svg.contains("<use xlink:href=\"#black-king\" x=\"135\" y=\"315\"/>"));
+    }
+
+    #[test]
+    fn svg_highlights() {
+        let mut board = Board::start_pos();
+        board.apply_uci_move("f2f3");
+        board.apply_uci_move("e7e5");
+        board.apply_uci_move("g2g4");
+        board.apply_uci_move("d8h4");
+        assert!(board.in_check());
+
+        let theme = Theme::default();
+        let svg = board.svg().render();
+        assert_eq!(count(&svg, &format!("fill=\"{}\"", theme.check)), 1);
+        // Both d8 and h4 are dark squares
+        assert_eq!(
+            count(&svg, &format!("fill=\"{}\"", theme.last_move_light)),
+            0
+        );
+        assert_eq!(
+            count(&svg, &format!("fill=\"{}\"", theme.last_move_dark)),
+            2
+        );
+
+        let svg = board.svg().check(false).last_move(None).render();
+        assert_eq!(count(&svg, &format!("fill=\"{}\"", theme.check)), 0);
+        assert_eq!(
+            count(&svg, &format!("fill=\"{}\"", theme.last_move_light)),
+            0
+        );
+        assert_eq!(
+            count(&svg, &format!("fill=\"{}\"", theme.last_move_dark)),
+            0
+        );
+    }
+
+    #[test]
+    fn svg_arrows_marks_theme() {
+        let board = Board::start_pos();
+        let theme = Theme {
+            arrow: "red".to_string(),
+            mark: "blue".to_string(),
+            light_square: "\"white\"".to_string(),
+            ..Theme::default()
+        };
+        let svg = board
+            .svg()
+            .theme(theme)
+            .arrows(&[
+                BitMove::make_quiet(SQ::G1, SQ::F3),
+                BitMove::make_pawn_push(SQ::E2, SQ::E4),
+                BitMove::make_drop(PieceType::N, SQ::D4),
+            ])
+            .marks(&[SQ::E5, SQ::D5])
+            .render();
+        assert_eq!(count(&svg, "<polygon "), 2);
+        assert_eq!(count(&svg, "stroke=\"red\""), 2);
+        assert_eq!(count(&svg, "<circle cx=\"172.5\""), 2);
+        assert_eq!(count(&svg, "<circle cx=\"217.5\""), 1);
+        assert_eq!(count(&svg, "stroke=\"blue\""), 3);
+        assert_eq!(count(&svg, "fill=\"&quot;white&quot;\""), 32);
+    }
+}
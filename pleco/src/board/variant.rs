@@ -0,0 +1,892 @@
+//! Contains the `Variant` of chess a [`Board`] is playing, and the rules each variant changes.
+//!
+//! Every variant is played with the same pieces and the same [`MoveGen`] as standard chess. A
+//! variant may change the starting position, add or forbid some moves, change what a move does
+//! to the board, or end the game before a checkmate. Once a variant's win condition has been
+//! reached, no more legal moves are generated.
+//!
+//! The supported variants are:
+//!
+//! - **Three-check**: A player also wins by giving check three times. The checks given are
+//!   counted in the `BoardState`, and written to the FEN as the checks each player has left,
+//!   e.g. `3+3`.
+//! - **King of the Hill**: A player also wins by moving their king to one of the four centre
+//!   squares.
+//! - **Racing Kings**: Neither player may give check, and the first king to reach the eighth rank
+//!   wins. If White gets there first, Black has one move to reach it as well and draw.
+//! - **Horde**: White has a horde of pawns and no king, and wins by checkmating Black. Black wins
+//!   by capturing every White piece. White pawns on the first rank may double push.
+//! - **Crazyhouse**: A captured piece goes to the capturing player's pocket, and may later be
+//!   dropped onto any empty square instead of moving. Pawns can't be dropped onto the first or
+//!   last rank, and a promoted piece goes back to the pocket as a pawn. The pockets are written
+//!   to the FEN in brackets after the pieces, e.g. `[Qp]`, and promoted pieces are marked with a
+//!   `~`.
+//! - **Atomic**: A capture explodes the capturing and captured pieces, along with every piece
+//!   other than a pawn next to the capture square. Exploding the enemy king wins, so kings can't
+//!   capture, and a king next to the enemy king can't be checked.
+//! - **Antichess**: Also known as Giveaway. Captures are compulsory, the king is an ordinary
+//!   piece that can be captured or promoted to, and there is no castling. A player wins by losing
+//!   every piece, or by having no legal moves.
+//!
+//! # Examples
+//!
+//! ```
+//! use pleco::Board;
+//! use pleco::board::variant::Variant;
+//! use pleco::board::outcome::Termination;
+//! use pleco::Player;
+//!
+//! let mut board = Board::from_fen_variant("4k3/8/8/8/8/3K4/8/8 w - - 0 1", Variant::KingOfTheHill).unwrap();
+//! assert!(board.outcome().is_none());
+//!
+//! board.apply_uci_move("d3d4");
+//! let outcome = board.outcome().unwrap();
+//! assert_eq!(outcome.winner(), Some(Player::White));
+//! assert_eq!(outcome.termination(), Termination::VariantWin);
+//! assert!(board.generate_moves().is_empty());
+//! ```
+//!
+//! [`Board`]: ../struct.Board.html
+//! [`MoveGen`]: ../movegen/struct.MoveGen.html
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::castle_rights::CastlingSquares;
+use super::fen::OPENING_POS_FEN;
+use super::movegen::{MoveGen, PseudoLegal};
+use super::outcome::{Outcome, Termination};
+use super::Board;
+use core::bitboard::BitBoard;
+use core::mono_traits::AllGenType;
+use core::piece_move::BitMove;
+use core::sq::{NO_SQ, SQ};
+use core::{CastleType, Piece, PieceType, Player};
+use helper::prelude::{king_moves, rook_moves};
+
+/// The variant of chess being played.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Variant {
+    /// Standard chess.
+    #[default]
+    Standard,
+    /// Three-check, where giving check three times wins.
+    ThreeCheck,
+    /// King of the Hill, where moving a king to the centre wins.
+    KingOfTheHill,
+    /// Racing Kings, where reaching the eighth rank with a king wins.
+    RacingKings,
+    /// Horde, where White plays with a horde of pawns and no king.
+    Horde,
+    /// Crazyhouse, where captured pieces can be dropped back onto the board.
+    Crazyhouse,
+    /// Atomic, where captures explode the pieces around them.
+    Atomic,
+    /// Antichess, where captures are compulsory and losing every piece wins.
+    Antichess,
+}
+
+/// All the variants of chess a `Board` can play.
+pub const ALL_VARIANTS: [Variant; 8] = [
+    Variant::Standard,
+    Variant::ThreeCheck,
+    Variant::KingOfTheHill,
+    Variant::RacingKings,
+    Variant::Horde,
+    Variant::Crazyhouse,
+    Variant::Atomic,
+    Variant::Antichess,
+];
+
+/// The four centre squares of King of the Hill.
+const CENTRE: BitBoard = BitBoard(0x0000_0018_1800_0000);
+
+impl Variant {
+    /// Returns the name of the variant, as used by the `UCI_Variant` option.
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "chess",
+            Variant::ThreeCheck => "3check",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::RacingKings => "racingkings",
+            Variant::Horde => "horde",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Atomic => "atomic",
+            Variant::Antichess => "antichess",
+        }
+    }
+
+    /// Returns the FEN of the variant's starting position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::board::variant::Variant;
+    ///
+    /// assert_eq!(Variant::RacingKings.start_fen(), "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1");
+    /// ```
+    pub fn start_fen(self) -> &'static str {
+        match self {
+            Variant::Standard | Variant::KingOfTheHill | Variant::Atomic => OPENING_POS_FEN,
+            Variant::ThreeCheck => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1",
+            Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+            Variant::Horde => {
+                "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+            }
+            Variant::Crazyhouse => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            Variant::Antichess => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Variant {
+    type Err = VariantFromStrError;
+
+    /// Parses a `Variant` from its name, ignoring case. A few common aliases, such as
+    /// "standard" or "threecheck", are also accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pleco::board::variant::Variant;
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(Variant::from_str("3check").unwrap(), Variant::ThreeCheck);
+    /// assert_eq!(Variant::from_str("KingOfTheHill").unwrap(), Variant::KingOfTheHill);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "chess" | "standard" => Ok(Variant::Standard),
+            "3check" | "threecheck" => Ok(Variant::ThreeCheck),
+            "kingofthehill" | "koth" => Ok(Variant::KingOfTheHill),
+            "racingkings" => Ok(Variant::RacingKings),
+            "horde" => Ok(Variant::Horde),
+            "crazyhouse" | "zh" => Ok(Variant::Crazyhouse),
+            "atomic" => Ok(Variant::Atomic),
+            "antichess" | "giveaway" => Ok(Variant::Antichess),
+            _ => Err(VariantFromStrError),
+        }
+    }
+}
+
+/// Error type for parsing a `Variant` from a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariantFromStrError;
+
+impl fmt::Display for VariantFromStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown variant")
+    }
+}
+
+impl Board {
+    /// Returns the number of checks a player has given, as counted by Three-check. This is
+    /// always zero for other variants.
+    pub fn checks_given(&self, player: Player) -> u8 {
+        self.state.checks[player as usize]
+    }
+
+    /// Returns the `Outcome` of the game if the variant's own rules have ended it, or `None`
+    /// otherwise. Checkmate and the draws of standard chess are left to [`Board::outcome`].
+    ///
+    /// [`Board::outcome`]: ../struct.Board.html#method.outcome
+    pub fn variant_outcome(&self) -> Option<Outcome> {
+        let win = |winner: Player| {
+            Some(Outcome::Decisive {
+                winner,
+                termination: Termination::VariantWin,
+            })
+        };
+        match self.variant {
+            Variant::Standard | Variant::Crazyhouse => None,
+            Variant::ThreeCheck => {
+                let checks = self.state.checks;
+                if checks[Player::White as usize] >= 3 {
+                    win(Player::White)
+                } else if checks[Player::Black as usize] >= 3 {
+                    win(Player::Black)
+                } else {
+                    None
+                }
+            }
+            Variant::KingOfTheHill => {
+                if (self.piece_bb(Player::White, PieceType::K) & CENTRE).is_not_empty() {
+                    win(Player::White)
+                } else if (self.piece_bb(Player::Black, PieceType::K) & CENTRE).is_not_empty() {
+                    win(Player::Black)
+                } else {
+                    None
+                }
+            }
+            Variant::RacingKings => self.racing_kings_outcome(),
+            Variant::Horde => {
+                if self.get_occupied_player(Player::White).is_empty() {
+                    win(Player::Black)
+                } else {
+                    None
+                }
+            }
+            Variant::Atomic => {
+                if self.piece_bb(Player::White, PieceType::K).is_empty() {
+                    win(Player::Black)
+                } else if self.piece_bb(Player::Black, PieceType::K).is_empty() {
+                    win(Player::White)
+                } else {
+                    None
+                }
+            }
+            Variant::Antichess => {
+                if self.get_occupied_player(self.turn).is_empty() {
+                    win(self.turn)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Returns the number of pieces of a type a player holds in their Crazyhouse pocket. This is
+    /// always zero for other variants.
+    pub fn pocket(&self, player: Player, piece: PieceType) -> u8 {
+        self.state.pockets[player as usize][piece as usize]
+    }
+
+    /// Returns a `BitBoard` of the pieces that were promoted from pawns, as tracked by
+    /// Crazyhouse. This is always empty for other variants.
+    pub fn promoted_pieces(&self) -> BitBoard {
+        self.state.promoted
+    }
+
+    /// Returns the pieces of the other player giving check to a player's king, given the
+    /// occupied squares.
+    ///
+    /// A player without a king, such as White in Horde, can't be checked, and neither can an
+    /// Antichess king, which is an ordinary piece. Kings can't capture in Atomic, so a king next
+    /// to the enemy king is never in check, and a player whose king exploded gives no checks.
+    pub(crate) fn king_attackers(&self, player: Player, occupied: BitBoard) -> BitBoard {
+        let king = self.piece_bb(player, PieceType::K);
+        if king.is_empty() || self.variant == Variant::Antichess {
+            return BitBoard(0);
+        }
+        let ksq = king.to_sq();
+        if self.variant == Variant::Atomic {
+            let their_king = self.piece_bb(!player, PieceType::K);
+            if their_king.is_empty() || (king_moves(ksq) & their_king).is_not_empty() {
+                return BitBoard(0);
+            }
+        }
+        self.attackers_to(ksq, occupied) & self.get_occupied_player(!player) & occupied
+    }
+
+    /// Returns the squares emptied by an Atomic capture: those of the capturing and captured
+    /// pieces, and of every piece other than a pawn next to the capture square.
+    fn atomic_blast(&self, m: BitMove) -> BitBoard {
+        let dst = m.get_dest();
+        let cap_sq = if m.is_en_passant() {
+            SQ((dst.0 as i8).wrapping_sub(self.turn.pawn_push()) as u8)
+        } else {
+            dst
+        };
+        (king_moves(dst) & self.occupied() & !self.piece_bb_both_players(PieceType::P))
+            | m.get_src().to_bb()
+            | dst.to_bb()
+            | cap_sq.to_bb()
+    }
+
+    /// Returns if a pseudo-legal move is legal in Atomic.
+    ///
+    /// A capture may not explode the player's own king, but is legal if it explodes the enemy
+    /// king, even while in check. Otherwise, the player's king may not be left in check by a
+    /// piece that survives the move.
+    pub(crate) fn atomic_legal_move(&self, m: BitMove) -> bool {
+        let us = self.turn;
+        let them = !us;
+        let src = m.get_src();
+        let dst = m.get_dest();
+        let piece = self.piece_at_sq(src);
+        if piece == Piece::None || src == dst {
+            return false;
+        }
+
+        // Castles are checked during move-generation
+        let our_king = self.piece_bb(us, PieceType::K);
+        if our_king.is_empty() || m.is_castle() {
+            return true;
+        }
+
+        let their_king = self.piece_bb(them, PieceType::K);
+        let (ksq, occupied) = if self.is_capture(m) {
+            if piece.type_of() == PieceType::K {
+                return false;
+            }
+            let blast = self.atomic_blast(m);
+            if (blast & our_king).is_not_empty() {
+                return false;
+            }
+            if (blast & their_king).is_not_empty() {
+                return true;
+            }
+            (our_king.to_sq(), self.occupied() & !blast)
+        } else if piece.type_of() == PieceType::K {
+            (dst, self.occupied() ^ src.to_bb() ^ dst.to_bb())
+        } else {
+            (
+                our_king.to_sq(),
+                self.occupied() ^ src.to_bb() ^ dst.to_bb(),
+            )
+        };
+
+        (king_moves(ksq) & their_king).is_not_empty()
+            || (self.attackers_to(ksq, occupied) & self.get_occupied_player(them) & occupied)
+                .is_empty()
+    }
+
+    /// Returns if a pseudo-legal move of a variant is legal. Kept apart from
+    /// `Board::legal_move()`, so that checking the moves of standard chess stays cheap.
+    #[cold]
+    pub(crate) fn variant_legal_move(&self, m: BitMove) -> bool {
+        match self.variant {
+            Variant::Atomic => self.atomic_legal_move(m),
+            Variant::Antichess => self.is_capture(m) || !self.capture_available(),
+            // Racing Kings forbids giving check
+            Variant::RacingKings => self.variant_king_safe_move(m) && !self.gives_check(m),
+            _ => self.variant_king_safe_move(m),
+        }
+    }
+
+    /// Returns if a move of a variant gives check, or `None` if it gives check just as it
+    /// would in standard chess.
+    #[cold]
+    pub(crate) fn variant_gives_check(&self, m: BitMove) -> Option<bool> {
+        // Horde's White player has no king to check, and Antichess kings can't be checked
+        if self.piece_bb(!self.turn, PieceType::K).is_empty() || self.variant == Variant::Antichess
+        {
+            return Some(false);
+        }
+        if self.variant == Variant::Atomic {
+            if let Some(gives_check) = self.atomic_gives_check(m) {
+                return Some(gives_check);
+            }
+        }
+        // A dropped piece can only give check directly
+        if m.is_drop() {
+            let dst_bb = m.get_dest().to_bb();
+            return Some((self.state.check_sqs[m.drop_piece() as usize] & dst_bb).is_not_empty());
+        }
+        None
+    }
+
+    /// Returns if an Atomic move gives check, or `None` if it gives check just as it would in
+    /// standard chess.
+    pub(crate) fn atomic_gives_check(&self, m: BitMove) -> Option<bool> {
+        let us = self.turn;
+        let their_king = self.piece_bb(!us, PieceType::K);
+        let our_king = self.piece_bb(us, PieceType::K);
+        if their_king.is_empty() || our_king.is_empty() {
+            return Some(false);
+        }
+        let ksq = their_king.to_sq();
+
+        if self.is_capture(m) {
+            let blast = self.atomic_blast(m);
+            if (blast & (our_king | their_king)).is_not_empty()
+                || (king_moves(ksq) & our_king).is_not_empty()
+            {
+                return Some(false);
+            }
+            let occupied = self.occupied() & !blast;
+            return Some(
+                (self.attackers_to(ksq, occupied) & self.get_occupied_player(us) & occupied)
+                    .is_not_empty(),
+            );
+        }
+
+        // A king next to the enemy king can't give check
+        let src = m.get_src();
+        let dst = m.get_dest();
+        let castle = if m.is_castle() {
+            let side = if dst > src {
+                CastleType::KingSide
+            } else {
+                CastleType::QueenSide
+            };
+            Some(CastlingSquares::destinations(us, side))
+        } else {
+            None
+        };
+        let our_ksq = match castle {
+            Some((k_dst, _)) => k_dst,
+            None if self.piece_at_sq(src).type_of() == PieceType::K => dst,
+            None => our_king.to_sq(),
+        };
+        if (king_moves(ksq) & our_ksq.to_bb()).is_not_empty() {
+            return Some(false);
+        }
+        if (king_moves(ksq) & our_king).is_empty() {
+            return None;
+        }
+
+        // The king stepped away from the enemy king, so any piece attacking it now gives check
+        let mut occupied = self.occupied() ^ src.to_bb() ^ dst.to_bb();
+        let mut checks = BitBoard(0);
+        if let Some((k_dst, r_dst)) = castle {
+            occupied |= k_dst.to_bb() | r_dst.to_bb();
+            checks = rook_moves(occupied, r_dst) & their_king;
+        }
+        checks |= self.attackers_to(ksq, occupied)
+            & self.get_occupied_player(us)
+            & !src.to_bb()
+            & !dst.to_bb();
+        Some(checks.is_not_empty())
+    }
+
+    /// Returns if the side to move can capture a piece, which is compulsory in Antichess.
+    pub(crate) fn capture_available(&self) -> bool {
+        let us = self.turn;
+        let them_occ = self.get_occupied_player(!us);
+        let mut pieces = self.get_occupied_player(us);
+        while let Some(sq) = pieces.pop_some_lsb() {
+            let piece = self.piece_at_sq(sq).type_of();
+            let mut targets = them_occ;
+            if piece == PieceType::P && self.ep_square() != NO_SQ {
+                targets |= self.ep_square().to_bb();
+            }
+            if (self.attacks_from(piece, sq, us) & targets).is_not_empty() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns if the variant's own rules have ended the game.
+    #[inline]
+    pub fn is_variant_end(&self) -> bool {
+        self.variant != Variant::Standard && self.variant_outcome().is_some()
+    }
+
+    fn racing_kings_outcome(&self) -> Option<Outcome> {
+        let white_home =
+            (self.piece_bb(Player::White, PieceType::K) & BitBoard::RANK_8).is_not_empty();
+        let black_home =
+            (self.piece_bb(Player::Black, PieceType::K) & BitBoard::RANK_8).is_not_empty();
+
+        let winner = match (white_home, black_home) {
+            (false, false) => return None,
+            (true, true) => {
+                return Some(Outcome::Draw {
+                    termination: Termination::VariantDraw,
+                })
+            }
+            (false, true) => Player::Black,
+            (true, false) => {
+                // White got there first, but Black has one move left to catch up
+                if self.turn == Player::Black && self.can_reach_eighth_rank() {
+                    return None;
+                }
+                Player::White
+            }
+        };
+        Some(Outcome::Decisive {
+            winner,
+            termination: Termination::VariantWin,
+        })
+    }
+
+    /// Returns if the king of the side to move has a legal move onto the eighth rank.
+    fn can_reach_eighth_rank(&self) -> bool {
+        let ksq = self.king_sq(self.turn);
+        MoveGen::generate::<PseudoLegal, AllGenType>(self)
+            .iter()
+            .any(|m| {
+                m.get_src() == ksq
+                    && (m.get_dest().to_bb() & BitBoard::RANK_8).is_not_empty()
+                    && self.legal_move(*m)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::fen::OPENING_POS_FEN;
+    use board::perft::{perft, perft_all, perft_divide, perft_parallel};
+
+    fn board(fen: &str, variant: Variant) -> Board {
+        Board::from_fen_variant(fen, variant).unwrap()
+    }
+
+    fn win(winner: Player) -> Option<Outcome> {
+        Some(Outcome::Decisive {
+            winner,
+            termination: Termination::VariantWin,
+        })
+    }
+
+    #[test]
+    fn variant_names() {
+        for variant in ALL_VARIANTS.iter() {
+            assert_eq!(variant.name().parse::<Variant>(), Ok(*variant));
+            let start = Board::start_pos_variant(*variant);
+            assert_eq!(start.variant(), *variant);
+            assert_eq!(start.fen(), variant.start_fen());
+        }
+        assert!("shogi".parse::<Variant>().is_err());
+    }
+
+    #[test]
+    fn variant_perft() {
+        let start = Board::start_pos_variant(Variant::ThreeCheck);
+        assert_eq!(perft(&start, 3), 8_902);
+        let start = Board::start_pos_variant(Variant::KingOfTheHill);
+        assert_eq!(perft(&start, 3), 8_902);
+
+        let start = Board::start_pos_variant(Variant::RacingKings);
+        assert_eq!(perft(&start, 1), 21);
+        assert_eq!(perft(&start, 2), 421);
+        assert_eq!(perft(&start, 3), 11_264);
+
+        let start = Board::start_pos_variant(Variant::Horde);
+        assert_eq!(perft(&start, 1), 8);
+        assert_eq!(perft(&start, 2), 128);
+        assert_eq!(perft(&start, 3), 1_274);
+        assert_eq!(perft(&start, 4), 23_310);
+
+        let kiwipete = board(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 1+1 0 1",
+            Variant::ThreeCheck,
+        );
+        assert_eq!(perft(&kiwipete, 1), 48);
+        assert_eq!(perft(&kiwipete, 2), 2_039);
+        assert_eq!(perft(&kiwipete, 3), 97_848);
+
+        let start = Board::start_pos_variant(Variant::Crazyhouse);
+        assert_eq!(perft(&start, 3), 8_902);
+        let pockets = board(
+            "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1",
+            Variant::Crazyhouse,
+        );
+        assert_eq!(perft(&pockets, 1), 301);
+        assert_eq!(perft(&pockets, 2), 75_353);
+        assert_eq!(perft_all(&pockets, 2).nodes, 75_353);
+        assert_eq!(perft_divide(&pockets, 1).len(), 301);
+        assert_eq!(perft_parallel(&pockets, 2), 75_353);
+        let promoted = board("4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1", Variant::Crazyhouse);
+        assert_eq!(perft(&promoted, 1), 20);
+        assert_eq!(perft(&promoted, 2), 360);
+        assert_eq!(perft(&promoted, 3), 5_445);
+        let middle = board(
+            "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1",
+            Variant::Crazyhouse,
+        );
+        assert_eq!(perft(&middle, 1), 42);
+        assert_eq!(perft(&middle, 2), 1_347);
+        assert_eq!(perft(&middle, 3), 58_057);
+
+        let start = Board::start_pos_variant(Variant::Atomic);
+        assert_eq!(perft(&start, 3), 8_902);
+        for &(fen, counts) in [
+            (
+                "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+                [40, 1_238, 45_237],
+            ),
+            (
+                "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+                [28, 833, 23_353],
+            ),
+            ("8/8/8/8/8/8/2k5/rR4KR w KQ - 0 1", [18, 180, 4_364]),
+            ("r3k1rR/5K2/8/8/8/8/8/8 b kq - 0 1", [25, 282, 6_753]),
+            ("Rr2k1rR/3K4/3p4/8/8/8/7P/8 w kq - 0 1", [21, 465, 10_631]),
+        ]
+        .iter()
+        {
+            let b = board(fen, Variant::Atomic);
+            for (depth, count) in counts.iter().enumerate() {
+                assert_eq!(perft(&b, depth as u16 + 1), *count, "{}", fen);
+            }
+        }
+
+        let start = Board::start_pos_variant(Variant::Antichess);
+        assert_eq!(perft(&start, 1), 20);
+        assert_eq!(perft(&start, 2), 400);
+        assert_eq!(perft(&start, 3), 8_067);
+        let b_pawn = board("8/1p6/8/8/8/8/P7/8 w - - 0 1", Variant::Antichess);
+        let counts: Vec<u64> = (1..7).map(|depth| perft(&b_pawn, depth)).collect();
+        assert_eq!(counts, vec![2, 4, 4, 3, 1, 0]);
+        let c_pawn = board("8/2p5/8/8/8/8/P7/8 w - - 0 1", Variant::Antichess);
+        assert_eq!(perft(&c_pawn, 8), 4);
+        assert_eq!(perft(&c_pawn, 9), 12);
+        assert_eq!(perft(&c_pawn, 10), 36);
+    }
+
+    #[test]
+    fn three_check() {
+        let mut b = Board::start_pos_variant(Variant::ThreeCheck);
+        for mov in ["e2e4", "e7e5", "f1c4", "g8f6", "c4f7"].iter() {
+            assert!(b.apply_uci_move(mov));
+        }
+        assert_eq!(b.checks_given(Player::White), 1);
+        assert_eq!(b.checks_given(Player::Black), 0);
+        assert!(b.fen().contains(" - 2+3 "));
+
+        let from_fen = board(&b.fen(), Variant::ThreeCheck);
+        assert_eq!(from_fen.zobrist(), b.zobrist());
+        assert_ne!(
+            board(OPENING_POS_FEN, Variant::ThreeCheck).zobrist(),
+            board(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +1+0",
+                Variant::ThreeCheck
+            )
+            .zobrist()
+        );
+
+        let b = board("4k3/8/8/8/8/8/8/R3K3 w - - 1+3 0 1", Variant::ThreeCheck);
+        assert!(b.outcome().is_none());
+        let mut b2 = b.shallow_clone();
+        assert!(b2.apply_uci_move("a1a8"));
+        assert_eq!(b2.outcome(), win(Player::White));
+        assert!(b2.generate_moves().is_empty());
+
+        // A king and a knight are enough to give three checks
+        let b = board("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", Variant::ThreeCheck);
+        assert!(!b.insufficient_material());
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        let b = board("8/8/8/3k4/8/8/8/4K3 w - - 0 1", Variant::KingOfTheHill);
+        assert_eq!(b.outcome(), win(Player::Black));
+        let b = board("8/8/8/3k4/8/8/8/4K3 w - - 0 1", Variant::Standard);
+        assert!(b.outcome().is_some());
+        assert_eq!(
+            b.outcome().unwrap().termination(),
+            Termination::InsufficientMaterial
+        );
+    }
+
+    #[test]
+    fn racing_kings() {
+        // Moves giving check are illegal for both sides
+        let b = board(
+            "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+            Variant::RacingKings,
+        );
+        assert!(b.generate_moves().iter().all(|m| !b.gives_check(*m)));
+        let b = board("8/8/8/8/8/8/k6K/7R w - - 0 1", Variant::RacingKings);
+        let moves = b.generate_moves();
+        assert_eq!(moves.len(), 10);
+        assert!(moves.iter().all(|m| m.get_dest() != SQ::A1));
+
+        // White reaches the eighth rank, but Black can follow
+        let mut b = board("8/1k5K/8/8/8/8/8/8 w - - 0 1", Variant::RacingKings);
+        assert!(b.apply_uci_move("h7h8"));
+        assert_eq!(b.outcome(), None);
+        let mut draw = b.shallow_clone();
+        assert!(draw.apply_uci_move("b7b8"));
+        assert_eq!(
+            draw.outcome(),
+            Some(Outcome::Draw {
+                termination: Termination::VariantDraw
+            })
+        );
+        assert!(b.apply_uci_move("b7b6"));
+        assert_eq!(b.outcome(), win(Player::White));
+
+        // Black can't follow
+        let mut b = board("R7/2k4K/8/8/8/8/8/8 w - - 0 1", Variant::RacingKings);
+        assert!(b.apply_uci_move("h7h8"));
+        assert_eq!(b.outcome(), win(Player::White));
+
+        let b = board("1k6/8/8/8/8/8/8/7K w - - 0 1", Variant::RacingKings);
+        assert_eq!(b.outcome(), win(Player::Black));
+    }
+
+    #[test]
+    fn horde() {
+        let b = Board::start_pos_variant(Variant::Horde);
+        assert!(b.is_okay().is_ok());
+        assert!(!b.insufficient_material());
+
+        // Pawns on the first rank may double push, but not en passant
+        let mut b = board("4k3/8/8/8/8/8/8/P7 w - - 0 1", Variant::Horde);
+        assert_eq!(b.generate_moves().len(), 2);
+        assert!(b.apply_uci_move("a1a3"));
+        assert_eq!(b.ep_square(), NO_SQ);
+
+        let mut b = board("4k3/8/8/8/8/8/1p6/P7 b - - 0 1", Variant::Horde);
+        assert!(b.outcome().is_none());
+        assert!(b.apply_uci_move("b2a1q"));
+        assert_eq!(b.outcome(), win(Player::Black));
+        assert!(b.generate_moves().is_empty());
+    }
+
+    #[test]
+    fn crazyhouse() {
+        let fen = "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[QNnpp] b KQkq - 0 1";
+        let b = board(fen, Variant::Crazyhouse);
+        assert_eq!(b.fen(), fen);
+        assert_eq!(b.pocket(Player::White, PieceType::N), 1);
+        assert_eq!(b.pocket(Player::Black, PieceType::P), 2);
+        let ninth_rank = board(
+            "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R/pQnNp b KQkq - 0 1",
+            Variant::Crazyhouse,
+        );
+        assert_eq!(ninth_rank, b);
+        assert!(
+            Board::from_fen_variant("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1", Variant::Crazyhouse)
+                .is_err()
+        );
+
+        // Captured pieces go to the capturer's pocket, and can be dropped back
+        let mut b = board("4k3/8/8/3n4/8/4N3/8/4K3[] w - - 0 1", Variant::Crazyhouse);
+        let zobrist = b.zobrist();
+        assert!(b.apply_uci_move("e3d5"));
+        assert_eq!(b.fen(), "4k3/8/8/3N4/8/8/8/4K3[N] b - - 0 1");
+        assert!(b.apply_uci_move("e8d8"));
+        let drop = BitMove::make_drop(PieceType::N, SQ::F7);
+        assert!(!drop.is_capture());
+        assert!(b.pseudo_legal_move(drop));
+        assert!(b.gives_check(drop));
+        assert_eq!(b.move_to_san(drop), "N@f7+");
+        assert_eq!(b.parse_san("N@f7"), Ok(drop));
+        let key = b.key_after(drop);
+        b.apply_move(drop);
+        assert_eq!(b.zobrist(), key);
+        assert_eq!(b.pocket(Player::White, PieceType::N), 0);
+        assert!(b.in_check());
+        b.undo_move();
+        b.undo_move();
+        b.undo_move();
+        assert_eq!(b.zobrist(), zobrist);
+
+        // Drops can block a check, but pawns can't be dropped on the back ranks
+        let b = board("4k3/8/8/8/8/8/8/r3K3[NP] w - - 0 1", Variant::Crazyhouse);
+        let mut drops: Vec<BitMove> = b
+            .generate_moves()
+            .iter()
+            .filter(|m| m.is_drop())
+            .cloned()
+            .collect();
+        drops.sort_by_key(|m| m.get_dest_u8());
+        assert_eq!(
+            drops,
+            vec![
+                BitMove::make_drop(PieceType::N, SQ::B1),
+                BitMove::make_drop(PieceType::N, SQ::C1),
+                BitMove::make_drop(PieceType::N, SQ::D1),
+            ]
+        );
+        assert!(!b.pseudo_legal_move(BitMove::make_drop(PieceType::N, SQ::E4)));
+        assert!(!b.pseudo_legal_move(BitMove::make_drop(PieceType::Q, SQ::D1)));
+
+        // Promoted pieces return to the pocket as pawns
+        let mut b = board("4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1", Variant::Crazyhouse);
+        assert_eq!(b.promoted_pieces(), SQ::B7.to_bb());
+        assert!(b.apply_uci_move("e4b7"));
+        assert_eq!(b.pocket(Player::Black, PieceType::P), 1);
+        assert_eq!(b.pocket(Player::Black, PieceType::Q), 0);
+        assert!(b.promoted_pieces().is_empty());
+        assert!(b.apply_uci_move("a2b2"));
+        assert!(b.apply_uci_move("c2c1q"));
+        assert_eq!(b.promoted_pieces(), SQ::C1.to_bb());
+        assert_eq!(b.fen(), "4k3/1b6/8/8/8/8/1K6/2q~5[Pp] w - - 0 3");
+        assert!(b.apply_uci_move("b2c1"));
+        assert_eq!(b.pocket(Player::White, PieceType::P), 2);
+        assert!(!b.insufficient_material());
+    }
+
+    #[test]
+    fn atomic() {
+        // A capture explodes the pieces around it, except for pawns
+        let fen = "r3k2r/8/8/8/8/2p1n3/3Bn3/R3K2R w KQkq - 0 1";
+        let mut b = board(fen, Variant::Atomic);
+        let zobrist = b.zobrist();
+        assert!(b.apply_uci_move("d2e3"));
+        assert_eq!(b.fen(), "r3k2r/8/8/8/8/2p5/8/R3K2R b KQkq - 0 1");
+        assert_eq!(b.piece_captured_last_turn(), PieceType::N);
+        b.undo_move();
+        assert_eq!(b.zobrist(), zobrist);
+        assert_eq!(b.fen(), fen);
+
+        // Exploding a rook loses its castling right, and exploding the king wins
+        let mut b = board("r3k2r/8/8/8/8/8/6p1/R3K1NR b KQkq - 0 1", Variant::Atomic);
+        assert!(b.apply_uci_move("g2h1q"));
+        assert_eq!(b.fen(), "r3k2r/8/8/8/8/8/8/R3K3 w Qkq - 0 2");
+        let mut b = board("4kr2/8/8/8/8/8/8/4KN1R b K - 0 1", Variant::Atomic);
+        assert!(b.outcome().is_none());
+        assert!(b.apply_uci_move("f8f1"));
+        assert_eq!(b.fen(), "4k3/8/8/8/8/8/8/7R w - - 0 2");
+        assert_eq!(b.outcome(), win(Player::Black));
+        assert!(b.generate_moves().is_empty());
+
+        // Kings can't capture, and can't be checked while next to each other
+        let b = board("8/8/8/8/8/3k4/3qK3/8 w - - 0 1", Variant::Atomic);
+        assert!(!b.in_check());
+        let moves = b.generate_moves();
+        assert_eq!(moves.len(), 3);
+        assert!(moves.iter().all(|m| !b.is_capture(*m)));
+        assert!(moves.contains(&BitMove::make_quiet(SQ::E2, SQ::E3)));
+
+        // Exploding the enemy king is legal, even while in check
+        let b = board("4k3/3n4/8/1B6/8/8/8/r3K3 w - - 0 1", Variant::Atomic);
+        assert!(b.in_check());
+        let explode = b.parse_san("Bxd7").unwrap();
+        assert!(b.generate_moves().contains(&explode));
+        let mut after = b.shallow_clone();
+        after.apply_move(explode);
+        assert!(!after.in_check());
+        assert_eq!(after.outcome(), win(Player::White));
+    }
+
+    #[test]
+    fn antichess() {
+        // No castling, and captures are compulsory
+        let b = board(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Variant::Antichess,
+        );
+        assert_eq!(b.fen(), Variant::Antichess.start_fen());
+        let mut b = board("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", Variant::Antichess);
+        assert_eq!(b.generate_moves().len(), 1);
+        assert!(!b.legal_move(BitMove::make_quiet(SQ::E1, SQ::E2)));
+        assert!(b.apply_uci_move("e4d5"));
+        assert!(!b.in_check());
+
+        // Pawns may promote to a king, which is an ordinary piece
+        let mut b = board("8/P7/8/8/8/8/8/k7 w - - 0 1", Variant::Antichess);
+        assert_eq!(b.generate_moves().len(), 5);
+        let king_promo: BitMove = "a7a8k".parse().unwrap();
+        assert_eq!(king_promo.promo_piece(), PieceType::K);
+        assert!(!king_promo.is_capture());
+        assert!(b.generate_moves().contains(&king_promo));
+        assert!(b.apply_uci_move("a7a8k"));
+        assert_eq!(b.count_piece(Player::White, PieceType::K), 1);
+        assert!(b.is_okay().is_ok());
+
+        // A King promotion captures when it changes file
+        let b = board("1n6/P7/8/8/8/8/8/k7 w - - 0 1", Variant::Antichess);
+        let moves = b.generate_moves();
+        assert_eq!(moves.len(), 5);
+        assert!(moves.iter().all(|m| m.is_capture() && m.is_promo()));
+        assert!(moves.contains(&BitMove::make_king_promotion(SQ::A7, SQ::B8)));
+
+        // Losing every piece wins, as does having no legal moves
+        let mut b = board("8/8/8/8/8/8/1p6/R7 b - - 0 1", Variant::Antichess);
+        assert!(b.apply_uci_move("b2a1n"));
+        assert_eq!(b.outcome(), win(Player::White));
+        let b = board("8/8/8/8/8/p7/P7/8 w - - 0 1", Variant::Antichess);
+        assert_eq!(b.outcome(), win(Player::White));
+
+        // Bishops on squares of different colors can never meet
+        let b = board("8/8/8/8/8/1b6/8/B7 w - - 0 1", Variant::Antichess);
+        assert!(b.insufficient_material());
+        let b = board("8/8/8/8/8/2b5/8/B7 w - - 0 1", Variant::Antichess);
+        assert!(!b.insufficient_material());
+    }
+}
@@ -1,5 +1,6 @@
 //! The alpha-beta algorithm.
 use super::*;
+use board::movegen::StagedMoves;
 use board::*;
 
 use super::{eval_board, ScoringMove};
@@ -11,27 +12,29 @@ pub fn alpha_beta_search(board: &mut Board, mut alpha: i16, beta: i16, depth: u1
         return eval_board(board);
     }
 
-    let mut moves = board.generate_scoring_moves();
-
-    if moves.is_empty() {
-        if board.in_check() {
-            return ScoringMove::blank(-MATE_V);
-        } else {
-            return ScoringMove::blank(DRAW_V);
-        }
-    }
+    let mut moves = StagedMoves::new(board, BitMove::null());
+    let mut any_moves = false;
 
     let mut best_move = ScoringMove::blank(alpha);
-    for mov in moves.iter_mut() {
-        board.apply_move(mov.bit_move);
-        mov.score = -alpha_beta_search(board, -beta, -alpha, depth - 1).score;
+    while let Some(bit_move) = moves.next_move(board) {
+        any_moves = true;
+        board.apply_move(bit_move);
+        let score = -alpha_beta_search(board, -beta, -alpha, depth - 1).score;
         board.undo_move();
-        if mov.score > alpha {
-            alpha = mov.score;
+        if score > alpha {
+            alpha = score;
             if alpha >= beta {
-                return *mov;
+                return ScoringMove::new_score(bit_move, score);
             }
-            best_move = *mov;
+            best_move = ScoringMove::new_score(bit_move, score);
+        }
+    }
+
+    if !any_moves {
+        if board.in_check() {
+            return ScoringMove::blank(-MATE_V);
+        } else {
+            return ScoringMove::blank(DRAW_V);
         }
     }
 
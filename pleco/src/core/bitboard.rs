@@ -40,6 +40,7 @@ use std::ops::*;
 /// bit maps to a specific square. Used for mapping occupancy, where '1' represents
 /// a piece being at that index's square, and a '0' represents a lack of a piece.
 #[derive(Copy, Clone, Default, Hash, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 #[repr(transparent)]
 pub struct BitBoard(pub u64);
 
@@ -241,6 +242,25 @@ impl BitBoard {
         }
     }
 
+    /// Flips the ranks of every square on the `BitBoard`, so `SQ::A1` -> `SQ::A8`.
+    #[inline]
+    pub fn flip(self) -> BitBoard {
+        BitBoard(self.0.swap_bytes())
+    }
+
+    /// Mirrors the files of every square on the `BitBoard`, so `SQ::A1` -> `SQ::H1`.
+    #[inline]
+    pub fn mirror_horizontal(self) -> BitBoard {
+        const K1: u64 = 0x5555_5555_5555_5555;
+        const K2: u64 = 0x3333_3333_3333_3333;
+        const K4: u64 = 0x0f0f_0f0f_0f0f_0f0f;
+        let mut x = self.0;
+        x = ((x >> 1) & K1) | ((x & K1) << 1);
+        x = ((x >> 2) & K2) | ((x & K2) << 2);
+        x = ((x >> 4) & K4) | ((x & K4) << 4);
+        BitBoard(x)
+    }
+
     /// Returns a clone of a `[[BitBoard; 6]; 2]`. Used to duplicate occupancy `BitBoard`s of each
     /// piece for each player.
     #[inline(always)]
@@ -456,4 +476,24 @@ mod tests {
             assert_eq!(bbs_1.pop(), bbs_2.pop());
         }
     }
+
+    #[test]
+    fn bb_flip_mirror() {
+        let bbs = RandBitBoard::default()
+            .pseudo_random(7_001_330_245)
+            .avg(10)
+            .many(100);
+        for bb in bbs {
+            let mut flipped = BitBoard(0);
+            let mut mirrored = BitBoard(0);
+            for sq in bb {
+                flipped |= sq.flip().to_bb();
+                mirrored |= sq.mirror_horizontal().to_bb();
+            }
+            assert_eq!(bb.flip(), flipped);
+            assert_eq!(bb.mirror_horizontal(), mirrored);
+            assert_eq!(bb.flip().flip(), bb);
+            assert_eq!(bb.mirror_horizontal().mirror_horizontal(), bb);
+        }
+    }
 }
@@ -61,6 +61,7 @@ pub static ALL_RANKS: [Rank; RANK_CNT] = [
 
 /// Enum to represent the Players White & Black.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u8)]
 pub enum Player {
     White = 0,
@@ -202,6 +203,7 @@ pub enum GenTypes {
 /// [`Piece`]: ./enum.Piece
 #[repr(u8)]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PieceType {
     None = 0,
     P = 1,
@@ -300,6 +302,7 @@ impl fmt::Display for PieceType {
 /// [`Piece`]: ./enum.PieceType
 #[repr(u8)]
 #[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Piece {
     None = 0b0000,
     WhitePawn = 0b0001,
@@ -604,6 +607,7 @@ impl fmt::Debug for Piece {
 /// Enum for the Files of a Chessboard.
 #[repr(u8)]
 #[derive(Copy, Clone, PartialEq, Debug, Ord, PartialOrd, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum File {
     A = 0, // eg a specific column
     B = 1,
@@ -698,6 +702,7 @@ impl Not for File {
 /// Enum for the Ranks of a Chessboard.
 #[repr(u8)]
 #[derive(Copy, Clone, PartialEq, Debug, Eq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Rank {
     // eg a specific row
     R1 = 0,
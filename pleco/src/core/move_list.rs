@@ -1,7 +1,7 @@
 //! Contains the `MoveList` & `ScoreMoveList` structures, akin to a `Vec<BitMove>` but faster for
 //! our purposes.
 //!
-//! A [`MoveList`] structure is guaranteed to be exactly 512 bytes long, containing a maximum of 256
+//! A [`MoveList`] structure keeps its moves on the Stack, containing a maximum of `MAX_MOVES` (252)
 //! moves. This number was chosen as no possible chess position has been found to contain more than
 //! 232 possible moves.
 //!
@@ -12,8 +12,15 @@
 //! The [`ScoreMoveList`] is practically the same as the [`MoveList`], but it allows for each move to
 //! have a score attached to it as well.
 //!
+//! Drops can give a Crazyhouse position more moves than that, so the capacity of both lists is a
+//! const generic parameter, defaulting to `MAX_MOVES`. The moves of a Crazyhouse position are kept
+//! in a [`CrazyhouseMoveList`] or [`CrazyhouseScoringMoveList`] instead, with room for
+//! `MAX_CRAZYHOUSE_MOVES` moves, so the lists of every other variant stay as small as before.
+//!
 //! [`MoveList`]: struct.MoveList.html
 //! [`ScoreMoveList`]: struct.MoveList.html
+//! [`CrazyhouseMoveList`]: type.CrazyhouseMoveList.html
+//! [`CrazyhouseScoringMoveList`]: type.CrazyhouseScoringMoveList.html
 
 use std::iter::{ExactSizeIterator, FromIterator, FusedIterator, IntoIterator, Iterator};
 use std::ops::{Deref, DerefMut, Index, IndexMut};
@@ -72,47 +79,106 @@ pub const MAX_MOVES: usize = 254;
 #[cfg(any(target_pointer_width = "16", target_pointer_width = "8",))]
 pub const MAX_MOVES: usize = 255;
 
+/// The maximum number of moves a `CrazyhouseMoveList` or `CrazyhouseScoringMoveList` may
+/// contain, as drops can give a Crazyhouse position well over 256 moves.
+pub const MAX_CRAZYHOUSE_MOVES: usize = 2 * MAX_MOVES;
+
 /// This is the list of possible moves for a current position. Think of it alike a faster
 /// version of `Vec<BitMove>`, as all the data is stored in the Stack rather than the Heap.
-pub struct MoveList {
-    inner: [BitMove; MAX_MOVES],
+///
+/// The list has room for `N` moves, which is `MAX_MOVES` unless it's a [`CrazyhouseMoveList`].
+///
+/// [`CrazyhouseMoveList`]: type.CrazyhouseMoveList.html
+pub struct MoveList<const N: usize = MAX_MOVES> {
+    inner: [BitMove; N],
     len: usize,
 }
 
+/// A `MoveList` with room for the moves of a Crazyhouse position, `MAX_CRAZYHOUSE_MOVES`.
+pub type CrazyhouseMoveList = MoveList<MAX_CRAZYHOUSE_MOVES>;
+
 impl Default for MoveList {
     #[inline]
     fn default() -> Self {
-        MoveList {
-            inner: [BitMove::null(); MAX_MOVES],
-            len: 0,
-        }
+        MoveList::empty()
     }
 }
 
-impl From<Vec<BitMove>> for MoveList {
+/// Panics if `len` moves don't fit into a list with room for `N` moves, rather than letting
+/// the list drop the moves past its capacity.
+#[inline]
+fn assert_fits<const N: usize>(len: usize) {
+    assert!(
+        len <= N,
+        "{} moves don't fit into a move list with room for {}",
+        len,
+        N
+    );
+}
+
+/// # Panics
+///
+/// Panics if the vector holds more than `N` moves.
+impl<const N: usize> From<Vec<BitMove>> for MoveList<N> {
     fn from(vec: Vec<BitMove>) -> Self {
-        let mut list = MoveList::default();
+        assert_fits::<N>(vec.len());
+        let mut list = MoveList::empty();
         vec.iter().for_each(|m| list.push(*m));
         list
     }
 }
 
-impl From<ScoringMoveList> for MoveList {
-    fn from(sc_list: ScoringMoveList) -> Self {
-        let mut mv_list = MoveList::default();
+impl<const N: usize> From<ScoringMoveList<N>> for MoveList<N> {
+    fn from(sc_list: ScoringMoveList<N>) -> Self {
+        let mut mv_list = MoveList::empty();
         sc_list.iter().for_each(|m| mv_list.push(m.bitmove()));
         mv_list
     }
 }
 
-impl Into<Vec<BitMove>> for MoveList {
+impl<const N: usize> Into<Vec<BitMove>> for MoveList<N> {
     #[inline]
     fn into(self) -> Vec<BitMove> {
         self.vec()
     }
 }
 
-impl MoveList {
+impl CrazyhouseMoveList {
+    /// Creates an empty `CrazyhouseMoveList`, with room for the `MAX_CRAZYHOUSE_MOVES` moves a
+    /// Crazyhouse position may have.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pleco::{BitMove,MoveList};
+    /// use pleco::core::move_list::MAX_MOVES;
+    ///
+    /// let mut list = MoveList::with_crazyhouse_capacity();
+    /// for _ in 0..(MAX_MOVES + 1) {
+    ///     list.push(BitMove::null());
+    /// }
+    /// assert_eq!(list.len(), MAX_MOVES + 1);
+    /// ```
+    pub fn with_crazyhouse_capacity() -> CrazyhouseMoveList {
+        MoveList::empty()
+    }
+}
+
+impl<const N: usize> MoveList<N> {
+    #[inline(always)]
+    pub(crate) fn empty() -> MoveList<N> {
+        MoveList {
+            inner: [BitMove::null(); N],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of moves the list has room for.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
     /// Adds a `BitMove` to the end of the list.
     ///
     /// # Safety
@@ -201,7 +267,7 @@ impl MoveList {
     }
 }
 
-impl Deref for MoveList {
+impl<const N: usize> Deref for MoveList<N> {
     type Target = [BitMove];
 
     #[inline]
@@ -213,7 +279,7 @@ impl Deref for MoveList {
     }
 }
 
-impl DerefMut for MoveList {
+impl<const N: usize> DerefMut for MoveList<N> {
     #[inline]
     fn deref_mut(&mut self) -> &mut [BitMove] {
         unsafe {
@@ -223,7 +289,7 @@ impl DerefMut for MoveList {
     }
 }
 
-impl Index<usize> for MoveList {
+impl<const N: usize> Index<usize> for MoveList<N> {
     type Output = BitMove;
 
     #[inline(always)]
@@ -232,17 +298,17 @@ impl Index<usize> for MoveList {
     }
 }
 
-impl IndexMut<usize> for MoveList {
+impl<const N: usize> IndexMut<usize> for MoveList<N> {
     #[inline(always)]
     fn index_mut(&mut self, index: usize) -> &mut BitMove {
         &mut (**self)[index]
     }
 }
 
-impl MVPushable for MoveList {
+impl<const N: usize> MVPushable for MoveList<N> {
     #[inline(always)]
     fn push_mv(&mut self, mv: BitMove) {
-        if self.len() < MAX_MOVES {
+        if self.len() < N {
             unsafe { self.unchecked_push_mv(mv) }
         }
     }
@@ -270,12 +336,12 @@ impl MVPushable for MoveList {
     }
 }
 
-pub struct MoveIter<'a> {
-    movelist: &'a MoveList,
+pub struct MoveIter<'a, const N: usize = MAX_MOVES> {
+    movelist: &'a MoveList<N>,
     idx: usize,
 }
 
-impl<'a> Iterator for MoveIter<'a> {
+impl<'a, const N: usize> Iterator for MoveIter<'a, N> {
     type Item = BitMove;
 
     #[inline]
@@ -300,9 +366,9 @@ impl<'a> Iterator for MoveIter<'a> {
     }
 }
 
-impl<'a> IntoIterator for &'a MoveList {
+impl<'a, const N: usize> IntoIterator for &'a MoveList<N> {
     type Item = BitMove;
-    type IntoIter = MoveIter<'a>;
+    type IntoIter = MoveIter<'a, N>;
 
     #[inline(always)]
     fn into_iter(self) -> Self::IntoIter {
@@ -313,17 +379,17 @@ impl<'a> IntoIterator for &'a MoveList {
     }
 }
 
-impl<'a> ExactSizeIterator for MoveIter<'a> {}
+impl<'a, const N: usize> ExactSizeIterator for MoveIter<'a, N> {}
 
-impl<'a> FusedIterator for MoveIter<'a> {}
+impl<'a, const N: usize> FusedIterator for MoveIter<'a, N> {}
 
 // Iterator for the `MoveList`.
-pub struct MoveIntoIter {
-    movelist: MoveList,
+pub struct MoveIntoIter<const N: usize = MAX_MOVES> {
+    movelist: MoveList<N>,
     idx: usize,
 }
 
-impl Iterator for MoveIntoIter {
+impl<const N: usize> Iterator for MoveIntoIter<N> {
     type Item = BitMove;
 
     #[inline]
@@ -348,9 +414,9 @@ impl Iterator for MoveIntoIter {
     }
 }
 
-impl IntoIterator for MoveList {
+impl<const N: usize> IntoIterator for MoveList<N> {
     type Item = BitMove;
-    type IntoIter = MoveIntoIter;
+    type IntoIter = MoveIntoIter<N>;
 
     #[inline(always)]
     fn into_iter(self) -> Self::IntoIter {
@@ -361,60 +427,95 @@ impl IntoIterator for MoveList {
     }
 }
 
-impl FromIterator<BitMove> for MoveList {
+/// # Panics
+///
+/// Panics if the iterator yields more than `N` moves.
+impl<const N: usize> FromIterator<BitMove> for MoveList<N> {
     fn from_iter<T: IntoIterator<Item = BitMove>>(iter: T) -> Self {
-        let mut list = MoveList::default();
+        let mut list = MoveList::empty();
         for i in iter {
+            assert_fits::<N>(list.len() + 1);
             list.push(i);
         }
         list
     }
 }
 
-impl ExactSizeIterator for MoveIntoIter {}
+impl<const N: usize> ExactSizeIterator for MoveIntoIter<N> {}
 
-impl FusedIterator for MoveIntoIter {}
+impl<const N: usize> FusedIterator for MoveIntoIter<N> {}
 
 /// This is similar to a `MoveList`, but also keeps the scores for each move as well.
-pub struct ScoringMoveList {
-    inner: [ScoringMove; MAX_MOVES],
+///
+/// The list has room for `N` moves, which is `MAX_MOVES` unless it's a
+/// [`CrazyhouseScoringMoveList`].
+///
+/// [`CrazyhouseScoringMoveList`]: type.CrazyhouseScoringMoveList.html
+pub struct ScoringMoveList<const N: usize = MAX_MOVES> {
+    inner: [ScoringMove; N],
     len: usize,
 }
 
+/// A `ScoringMoveList` with room for the moves of a Crazyhouse position, `MAX_CRAZYHOUSE_MOVES`.
+pub type CrazyhouseScoringMoveList = ScoringMoveList<MAX_CRAZYHOUSE_MOVES>;
+
 impl Default for ScoringMoveList {
     #[inline]
     fn default() -> Self {
-        ScoringMoveList {
-            inner: [ScoringMove::default(); MAX_MOVES],
-            len: 0,
-        }
+        ScoringMoveList::empty()
     }
 }
 
-impl From<Vec<BitMove>> for ScoringMoveList {
+/// # Panics
+///
+/// Panics if the vector holds more than `N` moves.
+impl<const N: usize> From<Vec<BitMove>> for ScoringMoveList<N> {
     fn from(vec: Vec<BitMove>) -> Self {
-        let mut list = ScoringMoveList::default();
+        assert_fits::<N>(vec.len());
+        let mut list = ScoringMoveList::empty();
         vec.iter().for_each(|m| list.push(*m));
         list
     }
 }
 
-impl From<MoveList> for ScoringMoveList {
-    fn from(mv_list: MoveList) -> Self {
-        let mut sc_list = ScoringMoveList::default();
+impl<const N: usize> From<MoveList<N>> for ScoringMoveList<N> {
+    fn from(mv_list: MoveList<N>) -> Self {
+        let mut sc_list = ScoringMoveList::empty();
         mv_list.iter().for_each(|m| sc_list.push(*m));
         sc_list
     }
 }
 
-impl Into<Vec<ScoringMove>> for ScoringMoveList {
+impl<const N: usize> Into<Vec<ScoringMove>> for ScoringMoveList<N> {
     #[inline]
     fn into(self) -> Vec<ScoringMove> {
         self.vec()
     }
 }
 
-impl ScoringMoveList {
+impl CrazyhouseScoringMoveList {
+    /// Creates an empty `CrazyhouseScoringMoveList`, with room for the `MAX_CRAZYHOUSE_MOVES`
+    /// moves a Crazyhouse position may have.
+    pub fn with_crazyhouse_capacity() -> CrazyhouseScoringMoveList {
+        ScoringMoveList::empty()
+    }
+}
+
+impl<const N: usize> ScoringMoveList<N> {
+    #[inline(always)]
+    pub(crate) fn empty() -> ScoringMoveList<N> {
+        ScoringMoveList {
+            inner: [ScoringMove::default(); N],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of moves the list has room for.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
     /// Adds a `BitMove` to the end of the list.
     ///
     /// # Safety
@@ -446,7 +547,7 @@ impl ScoringMoveList {
     /// Unlike a normal score, which is a `i32`, the score pushed is of a type `i16`.
     #[inline(always)]
     pub fn push_score(&mut self, mov: BitMove, score: i16) {
-        if self.len < MAX_MOVES {
+        if self.len < N {
             unsafe {
                 self.push_score_unchecked(mov, score);
             }
@@ -479,7 +580,7 @@ impl ScoringMoveList {
     }
 }
 
-impl Deref for ScoringMoveList {
+impl<const N: usize> Deref for ScoringMoveList<N> {
     type Target = [ScoringMove];
 
     #[inline]
@@ -491,7 +592,7 @@ impl Deref for ScoringMoveList {
     }
 }
 
-impl DerefMut for ScoringMoveList {
+impl<const N: usize> DerefMut for ScoringMoveList<N> {
     #[inline]
     fn deref_mut(&mut self) -> &mut [ScoringMove] {
         unsafe {
@@ -501,7 +602,7 @@ impl DerefMut for ScoringMoveList {
     }
 }
 
-impl Index<usize> for ScoringMoveList {
+impl<const N: usize> Index<usize> for ScoringMoveList<N> {
     type Output = ScoringMove;
 
     #[inline(always)]
@@ -510,17 +611,17 @@ impl Index<usize> for ScoringMoveList {
     }
 }
 
-impl IndexMut<usize> for ScoringMoveList {
+impl<const N: usize> IndexMut<usize> for ScoringMoveList<N> {
     #[inline(always)]
     fn index_mut(&mut self, index: usize) -> &mut ScoringMove {
         &mut (**self)[index]
     }
 }
 
-impl MVPushable for ScoringMoveList {
+impl<const N: usize> MVPushable for ScoringMoveList<N> {
     #[inline(always)]
     fn push_mv(&mut self, mv: BitMove) {
-        if self.len() < MAX_MOVES {
+        if self.len() < N {
             unsafe { self.unchecked_push_mv(mv) }
         }
     }
@@ -548,12 +649,12 @@ impl MVPushable for ScoringMoveList {
     }
 }
 
-pub struct ScoreMoveIter<'a> {
-    movelist: &'a ScoringMoveList,
+pub struct ScoreMoveIter<'a, const N: usize = MAX_MOVES> {
+    movelist: &'a ScoringMoveList<N>,
     idx: usize,
 }
 
-impl<'a> Iterator for ScoreMoveIter<'a> {
+impl<'a, const N: usize> Iterator for ScoreMoveIter<'a, N> {
     type Item = ScoringMove;
 
     #[inline]
@@ -578,9 +679,9 @@ impl<'a> Iterator for ScoreMoveIter<'a> {
     }
 }
 
-impl<'a> IntoIterator for &'a ScoringMoveList {
+impl<'a, const N: usize> IntoIterator for &'a ScoringMoveList<N> {
     type Item = ScoringMove;
-    type IntoIter = ScoreMoveIter<'a>;
+    type IntoIter = ScoreMoveIter<'a, N>;
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
@@ -591,17 +692,17 @@ impl<'a> IntoIterator for &'a ScoringMoveList {
     }
 }
 
-impl<'a> ExactSizeIterator for ScoreMoveIter<'a> {}
+impl<'a, const N: usize> ExactSizeIterator for ScoreMoveIter<'a, N> {}
 
-impl<'a> FusedIterator for ScoreMoveIter<'a> {}
+impl<'a, const N: usize> FusedIterator for ScoreMoveIter<'a, N> {}
 
 // Iterator for the `ScoringMoveList`.
-pub struct ScoreMoveIntoIter {
-    movelist: ScoringMoveList,
+pub struct ScoreMoveIntoIter<const N: usize = MAX_MOVES> {
+    movelist: ScoringMoveList<N>,
     idx: usize,
 }
 
-impl Iterator for ScoreMoveIntoIter {
+impl<const N: usize> Iterator for ScoreMoveIntoIter<N> {
     type Item = ScoringMove;
 
     #[inline]
@@ -626,9 +727,9 @@ impl Iterator for ScoreMoveIntoIter {
     }
 }
 
-impl IntoIterator for ScoringMoveList {
+impl<const N: usize> IntoIterator for ScoringMoveList<N> {
     type Item = ScoringMove;
-    type IntoIter = ScoreMoveIntoIter;
+    type IntoIter = ScoreMoveIntoIter<N>;
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
@@ -639,16 +740,73 @@ impl IntoIterator for ScoringMoveList {
     }
 }
 
-impl FromIterator<BitMove> for ScoringMoveList {
+/// # Panics
+///
+/// Panics if the iterator yields more than `N` moves.
+impl<const N: usize> FromIterator<BitMove> for ScoringMoveList<N> {
     fn from_iter<T: IntoIterator<Item = BitMove>>(iter: T) -> Self {
-        let mut list = ScoringMoveList::default();
+        let mut list = ScoringMoveList::empty();
         for i in iter {
+            assert_fits::<N>(list.len() + 1);
             list.push(i);
         }
         list
     }
 }
 
-impl ExactSizeIterator for ScoreMoveIntoIter {}
+impl<const N: usize> ExactSizeIterator for ScoreMoveIntoIter<N> {}
+
+impl<const N: usize> FusedIterator for ScoreMoveIntoIter<N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+
+    fn many_moves() -> Vec<BitMove> {
+        (0..(MAX_MOVES as u16 + 40)).map(BitMove::new).collect()
+    }
+
+    #[test]
+    fn conversions_keep_every_move() {
+        let moves = many_moves();
 
-impl FusedIterator for ScoreMoveIntoIter {}
+        let list = CrazyhouseMoveList::from(moves.clone());
+        assert_eq!(list.vec(), moves);
+        let sc_list = CrazyhouseScoringMoveList::from(list);
+        assert_eq!(sc_list.len(), moves.len());
+        let list = CrazyhouseMoveList::from(sc_list);
+        assert_eq!(list.vec(), moves);
+
+        let sc_list = CrazyhouseScoringMoveList::from(moves.clone());
+        assert!(sc_list
+            .iter()
+            .map(|m| m.bitmove())
+            .eq(moves.iter().cloned()));
+
+        let list: CrazyhouseMoveList = moves.iter().cloned().collect();
+        assert_eq!(list.vec(), moves);
+        let sc_list: CrazyhouseScoringMoveList = list.into_iter().collect();
+        assert!(sc_list
+            .into_iter()
+            .map(|m| m.bitmove())
+            .eq(moves.into_iter()));
+    }
+
+    #[test]
+    fn conversions_panic_when_too_small() {
+        assert!(panic::catch_unwind(|| MoveList::<MAX_MOVES>::from(many_moves())).is_err());
+        assert!(panic::catch_unwind(|| ScoringMoveList::<MAX_MOVES>::from(many_moves())).is_err());
+        assert!(panic::catch_unwind(|| many_moves().into_iter().collect::<MoveList>()).is_err());
+        assert!(
+            panic::catch_unwind(|| many_moves().into_iter().collect::<ScoringMoveList>()).is_err()
+        );
+
+        let moves: Vec<BitMove> = many_moves().into_iter().take(MAX_MOVES).collect();
+        assert_eq!(MoveList::<MAX_MOVES>::from(moves.clone()).len(), MAX_MOVES);
+        assert_eq!(
+            moves.into_iter().collect::<ScoringMoveList>().len(),
+            MAX_MOVES
+        );
+    }
+}
@@ -21,7 +21,15 @@
 //! square of the king, while the destination square will be the square of the rook to
 //! castle with.
 //!
-//! Lastly, the En-passant flag is only set if the move is a pawn double-push.
+//! The En-passant flag is only set if the move is a pawn double-push.
+//!
+//! Lastly, a drop places a piece from a player's pocket onto the board, as in Crazyhouse. A drop
+//! has no origin square, so the origin bits hold the `PieceType` being dropped instead. This
+//! means the origin and destination of a drop can be equal.
+//!
+//! Antichess also allows promoting to a King. There is no room left for both a quiet and a
+//! capturing King promotion, so a single flag is used for both. A pawn only captures by moving
+//! diagonally, so a King promotion captures if its origin and destination are on different files.
 //!
 //! # Bit Flags for a `BitMove`
 //!
@@ -42,8 +50,8 @@
 //! 0011  ===> Queen Castle
 //! 0100  ===> Capture
 //! 0101  ===> EP Capture
-//! 0110  ===>
-//! 0111  ===>
+//! 0110  ===> Drop
+//! 0111  ===> King Promotion, capturing if it changes file
 //! 1000  ===> Knight Promotion
 //! 1001  ===> Bishop Promo
 //! 1010  ===> Rook   Promo
@@ -85,6 +93,7 @@ const SP_MASK: u16 = 0b0011_000000_000000;
 /// A `BitMove` should never be created directly, but rather instigated with a `PreMoveInfo`. This is because
 /// the bits are in a special order, and manually creating moves risks creating an invalid move.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 #[repr(transparent)]
 pub struct BitMove {
     data: u16,
@@ -126,10 +135,32 @@ pub enum MoveType {
     Castle = 1, //0b001x
     /// The move is an en-passant capture.
     EnPassant = 5, // 0b0101
+    /// The move is a drop of a piece from a player's pocket.
+    Drop = 6, // 0b0110
     /// The move is a promotion.
     Promotion = 8, //0b1xxx
 }
 
+/// The `MoveType` of each flag of a `BitMove`.
+const MOVE_TYPES: [MoveType; 16] = [
+    MoveType::Normal,
+    MoveType::Normal,
+    MoveType::Castle,
+    MoveType::Castle,
+    MoveType::Normal,
+    MoveType::EnPassant,
+    MoveType::Drop,
+    MoveType::Promotion,
+    MoveType::Promotion,
+    MoveType::Promotion,
+    MoveType::Promotion,
+    MoveType::Promotion,
+    MoveType::Promotion,
+    MoveType::Promotion,
+    MoveType::Promotion,
+    MoveType::Promotion,
+];
+
 /// Useful pre-encoding of a move's information before it is compressed into a `BitMove` struct.
 #[derive(Copy, Clone, PartialEq)]
 pub struct PreMoveInfo {
@@ -150,10 +181,11 @@ impl fmt::Display for BitMove {
 impl FromStr for BitMove {
     type Err = BitMoveFromStrError;
 
-    /// Parses a `BitMove` from a UCI move string (e.g., "e2e4", "a7a8q").
+    /// Parses a `BitMove` from a UCI move string (e.g., "e2e4", "a7a8q", "N@f3").
     ///
     /// The format is: source square, destination square, and an optional promotion piece
-    /// character ('n', 'b', 'r', 'q').
+    /// character ('n', 'b', 'r', 'q', or 'k' in Antichess). Drops are written as the uppercase
+    /// piece dropped, an '@', and the destination square.
     ///
     /// Note: Without board context, only the source, destination, and promotion information
     /// can be determined. Flags such as capture, en passant, castle, or double pawn push
@@ -170,9 +202,24 @@ impl FromStr for BitMove {
     ///
     /// let promo = BitMove::from_str("a7a8q").unwrap();
     /// assert!(promo.is_promo());
+    ///
+    /// let drop = BitMove::from_str("N@f3").unwrap();
+    /// assert!(drop.is_drop());
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let len = s.len();
+        if len == 4 && s.as_bytes()[1] == b'@' {
+            let piece = match s.as_bytes()[0] {
+                b'P' => PieceType::P,
+                b'N' => PieceType::N,
+                b'B' => PieceType::B,
+                b'R' => PieceType::R,
+                b'Q' => PieceType::Q,
+                _ => return Err(BitMoveFromStrError),
+            };
+            let dst = s[2..4].parse::<SQ>().map_err(|_| BitMoveFromStrError)?;
+            return Ok(BitMove::make_drop(piece, dst));
+        }
         if len < 4 || len > 5 {
             return Err(BitMoveFromStrError);
         }
@@ -186,6 +233,7 @@ impl FromStr for BitMove {
                 b'b' => PieceType::B,
                 b'r' => PieceType::R,
                 b'q' => PieceType::Q,
+                b'k' => return Ok(BitMove::make_king_promotion(src, dst)),
                 _ => return Err(BitMoveFromStrError),
             };
             // Capture status cannot be determined without board context
@@ -213,7 +261,7 @@ impl fmt::Display for BitMoveFromStrError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "invalid move string, expected format like 'e2e4' or 'a7a8q'"
+            "invalid move string, expected format like 'e2e4', 'a7a8q' or 'N@f3'"
         )
     }
 }
@@ -226,7 +274,11 @@ impl BitMove {
     pub const FLAG_QUEEN_CASTLE: u16 = 0b0011;
     pub const FLAG_CAPTURE: u16 = 0b0100;
     pub const FLAG_EP: u16 = 0b0101;
+    pub const FLAG_DROP: u16 = 0b0110;
+    pub const FLAG_PROMO_K: u16 = 0b0111;
+    #[deprecated(note = "this flag now marks drops, use `BitMove::FLAG_DROP`")]
     pub const ILLEGAL_FLAG_1: u16 = 0b0110;
+    #[deprecated(note = "this flag now marks King promotions, use `BitMove::FLAG_PROMO_K`")]
     pub const ILLEGAL_FLAG_2: u16 = 0b0111;
     pub const FLAG_PROMO_N: u16 = 0b1000;
     pub const FLAG_PROMO_B: u16 = 0b1001;
@@ -272,6 +324,32 @@ impl BitMove {
         BitMove::make(BitMove::FLAG_EP, src, dst)
     }
 
+    /// Makes a castling `BitMove` from the square of the king and the square of the rook it
+    /// castles with. Castles are encoded as the king capturing its own rook, which works for
+    /// both standard chess and Chess960.
+    #[inline(always)]
+    pub const fn make_castle(king: SQ, rook: SQ) -> BitMove {
+        let flag = if king.0 < rook.0 {
+            BitMove::FLAG_KING_CASTLE
+        } else {
+            BitMove::FLAG_QUEEN_CASTLE
+        };
+        BitMove::make(flag, king, rook)
+    }
+
+    /// Makes a `BitMove` dropping a piece from the pocket of the player to move onto a square.
+    #[inline(always)]
+    pub const fn make_drop(piece: PieceType, dst: SQ) -> BitMove {
+        BitMove::make(BitMove::FLAG_DROP, SQ(piece as u8), dst)
+    }
+
+    /// Makes a `BitMove` promoting a pawn to a King, as allowed in Antichess. The promotion is
+    /// a capture if the source and destination squares are on different files.
+    #[inline(always)]
+    pub const fn make_king_promotion(src: SQ, dst: SQ) -> BitMove {
+        BitMove::make(BitMove::FLAG_PROMO_K, src, dst)
+    }
+
     /// Creates a `BitMove` from a source and destination square, as well as the current
     /// flag.
     #[inline(always)]
@@ -345,10 +423,46 @@ impl BitMove {
         self.data == 0
     }
 
+    /// Returns the `BitMove` with its source and destination squares flipped, for use on a
+    /// `Board` with its colours swapped by `Board::flip()`. A null move stays null.
+    #[inline]
+    pub fn flip(self) -> BitMove {
+        self.map_squares(SQ::flip)
+    }
+
+    /// Returns the `BitMove` with its source and destination squares mirrored, for use on a
+    /// `Board` mirrored by `Board::mirror_horizontal()`. A null move stays null.
+    #[inline]
+    pub fn mirror_horizontal(self) -> BitMove {
+        self.map_squares(SQ::mirror_horizontal)
+    }
+
+    // Maps the squares of the move, keeping the flags and the piece of a drop.
+    #[inline]
+    fn map_squares(self, map: fn(SQ) -> SQ) -> BitMove {
+        if self.is_null() {
+            return self;
+        }
+        let src = if self.is_drop() {
+            self.get_src()
+        } else {
+            map(self.get_src())
+        };
+        BitMove {
+            data: (self.data & FLAG_MASK) | src.0 as u16 | ((map(self.get_dest()).0 as u16) << 6),
+        }
+    }
+
     /// Returns if a `BitMove` captures an opponent's piece.
     #[inline(always)]
     pub const fn is_capture(self) -> bool {
-        ((self.data & CP_MASK) >> 14) == 1
+        let flag = self.flag();
+        // Drops and King promotions share the capture bit
+        if flag & 0b1110 == BitMove::FLAG_DROP {
+            return flag == BitMove::FLAG_PROMO_K
+                && (self.get_src_u8() ^ self.get_dest_u8()) & 0b111 != 0;
+        }
+        (self.data & CP_MASK) != 0
     }
 
     /// Returns if a `BitMove` is a Quiet Move, meaning it is not any of the following: a capture, promotion, castle, or double pawn push.
@@ -360,7 +474,7 @@ impl BitMove {
     /// Returns if a `BitMove` is a promotion.
     #[inline(always)]
     pub const fn is_promo(self) -> bool {
-        (self.data & PR_MASK) != 0
+        (self.data & PR_MASK) != 0 || self.flag() == BitMove::FLAG_PROMO_K
     }
 
     /// Returns the destination of a `BitMove`.
@@ -405,6 +519,30 @@ impl BitMove {
         self.flag() == BitMove::FLAG_QUEEN_CASTLE
     }
 
+    /// Returns if a `BitMove` is a drop of a piece from a player's pocket.
+    #[inline(always)]
+    pub const fn is_drop(self) -> bool {
+        self.flag() == BitMove::FLAG_DROP
+    }
+
+    /// Returns the `PieceType` dropped by a [BitMove].
+    ///
+    /// # Safety
+    ///
+    /// Method should only be used if the [BitMove] is a drop. Otherwise, the piece returned is
+    /// meaningless.
+    #[inline(always)]
+    pub fn drop_piece(self) -> PieceType {
+        match self.get_src_u8() {
+            1 => PieceType::P,
+            2 => PieceType::N,
+            3 => PieceType::B,
+            4 => PieceType::R,
+            5 => PieceType::Q,
+            _ => PieceType::None,
+        }
+    }
+
     /// Returns if a `BitMove` is an enpassant capture.
     #[inline(always)]
     pub const fn is_en_passant(self) -> bool {
@@ -455,6 +593,9 @@ impl BitMove {
     /// Method should only be used if the [BitMove] is a promotion. Otherwise, Undefined Behavior may result.
     #[inline(always)]
     pub fn promo_piece(self) -> PieceType {
+        if self.flag() == BitMove::FLAG_PROMO_K {
+            return PieceType::K;
+        }
         match (self.flag()) & 0b0011 {
             0 => PieceType::N,
             1 => PieceType::B,
@@ -467,24 +608,19 @@ impl BitMove {
     /// Returns the `MoveType` of a `BitMove`.
     #[inline(always)]
     pub fn move_type(self) -> MoveType {
-        if self.is_castle() {
-            return MoveType::Castle;
-        }
-        if self.is_promo() {
-            return MoveType::Promotion;
-        }
-        if self.is_en_passant() {
-            return MoveType::EnPassant;
-        }
-        MoveType::Normal
+        MOVE_TYPES[self.flag() as usize]
     }
 
     /// Returns a String representation of a `BitMove`.
     ///
     /// Format goes "Source Square, Destination Square, (Promo Piece)". Moving a Queen from A1 to B8
     /// will stringify to "a1b8". If there is a pawn promotion involved, the piece promoted to will be
-    /// appended to the end of the string, alike "a7a8q" in the case of a queen promotion.
+    /// appended to the end of the string, alike "a7a8q" in the case of a queen promotion. Drops
+    /// are written as the piece dropped and its square, alike "N@f3".
     pub fn stringify(self) -> String {
+        if self.is_drop() && self.drop_piece() != PieceType::None {
+            return format!("{}@{}", self.drop_piece().char_upper(), self.get_dest());
+        }
         let src = self.get_src().to_string();
         let dst_sq = self.get_dest();
 
@@ -507,6 +643,21 @@ impl BitMove {
         s
     }
 
+    /// Returns a String representation of a `BitMove`, using the UCI notation for Chess960.
+    ///
+    /// This is the same as [`BitMove::stringify`], except castles are always written as the
+    /// king capturing its own rook. For example, a white king-side castle in the standard
+    /// starting position is written as "e1h1".
+    ///
+    /// [`BitMove::stringify`]: #method.stringify
+    pub fn stringify_chess960(self) -> String {
+        if self.is_castle() {
+            format!("{}{}", self.get_src(), self.get_dest())
+        } else {
+            self.stringify()
+        }
+    }
+
     /// Returns the raw number representation of the move.
     #[inline(always)]
     pub const fn get_raw(self) -> u16 {
@@ -516,7 +667,7 @@ impl BitMove {
     /// Returns if the move has an incorrect flag inside, and therefore is invalid.
     #[inline(always)]
     pub fn incorrect_flag(self) -> bool {
-        ((self.flag()) & 0b1110) == 0b0110
+        self.is_drop() && self.drop_piece() == PieceType::None
     }
 
     /// Returns the 4 bit flag of the `BitMove`.
@@ -526,10 +677,10 @@ impl BitMove {
     }
 
     /// Returns if the move is within bounds, ala the to and from squares
-    /// are not equal.
+    /// are not equal, or the move is a drop.
     #[inline(always)]
     pub const fn is_okay(self) -> bool {
-        self.get_dest_u8() != self.get_src_u8()
+        self.get_dest_u8() != self.get_src_u8() || self.is_drop()
     }
 
     /// Returns only from "from" and "to" squares of the move.
@@ -543,6 +694,7 @@ impl BitMove {
 ///
 /// This is useful for tracking a list of moves alongside each of their scores.
 #[derive(Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
 pub struct ScoringMove {
     pub bit_move: BitMove,
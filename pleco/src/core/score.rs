@@ -45,6 +45,7 @@ pub const MATED_IN_MAX_PLY: Value = -MATE + 2 * 128;
 
 /// Struct to define the value of a mid-game / end-game evaluation.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Score(pub Value, pub Value);
 
 impl Score {
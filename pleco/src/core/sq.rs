@@ -246,6 +246,12 @@ impl SQ {
         SQ(self.0 ^ 0b111000)
     }
 
+    /// Mirrors the square's file, so `SQ::A1` -> `SQ::H1`.
+    #[inline(always)]
+    pub fn mirror_horizontal(self) -> SQ {
+        SQ(self.0 ^ 0b000111)
+    }
+
     /// Determines if two squares are on opposite colors.
     #[inline(always)]
     pub fn opposite_colors(self, other: SQ) -> bool {
@@ -424,6 +430,29 @@ impl FromStr for SQ {
     }
 }
 
+/// Serializes as the index of the square, with `NO_SQ` as 64.
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for SQ {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for SQ {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<SQ, D::Error> {
+        let sq = SQ(u8::deserialize(deserializer)?);
+        if sq.is_okay() || sq == NO_SQ {
+            Ok(sq)
+        } else {
+            Err(::serde::de::Error::custom(format!(
+                "invalid square: {}",
+                sq.0
+            )))
+        }
+    }
+}
+
 /// Error type for parsing a `SQ` from a string.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SQFromStrError;
@@ -16,7 +16,7 @@ mod boards;
 mod magic;
 pub mod prelude;
 mod psqt;
-mod zobrist;
+pub(crate) mod zobrist;
 
 use core::score::{Score, Value};
 use {BitBoard, File, Piece, Player, Rank, SQ};
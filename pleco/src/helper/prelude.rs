@@ -16,6 +16,7 @@ use super::magic;
 use super::psqt;
 use super::zobrist;
 
+use core::piece_move::BitMove;
 use core::score::{Score, Value};
 use {BitBoard, File, Piece, PieceType, Player, Rank, SQ};
 
@@ -38,6 +39,7 @@ pub fn init_statics() {
         magic::init_magics();
         compiler_fence(Ordering::SeqCst);
         boards::init_boards();
+        zobrist::init_cuckoo();
         fence(Ordering::SeqCst);
         compiler_fence(Ordering::SeqCst);
     });
@@ -213,6 +215,31 @@ pub fn z_no_pawns() -> u64 {
     zobrist::z_no_pawns()
 }
 
+/// Returns the Zobrist Hash for a player having given a number of checks.
+///
+/// Undefined behavior will occur if the number of checks is greater than 3.
+#[inline(always)]
+pub fn z_checks(player: Player, checks: u8) -> u64 {
+    zobrist::z_checks(player, checks)
+}
+
+/// Returns the Zobrist Hash for a player holding a number of pieces of a type in their
+/// Crazyhouse pocket.
+///
+/// Undefined behavior will occur if the count is greater than 16.
+#[inline(always)]
+pub fn z_pocket(player: Player, piece: PieceType, count: u8) -> u64 {
+    zobrist::z_pocket(player, piece, count)
+}
+
+/// Returns the reversible move, by a non-pawn piece between two squares, that changes the
+/// Zobrist hash of a position by the given key, if there is one. The move is always from the
+/// lower of the two squares to the higher.
+#[inline(always)]
+pub fn cuckoo_move(key: u64) -> Option<BitMove> {
+    zobrist::cuckoo_move(key)
+}
+
 // PSQT FUNCTIONS
 
 /// Returns the score for a player's piece being at a particular square.
@@ -1,6 +1,11 @@
+use std::mem;
+
+use super::prelude::{bishop_moves, king_moves, knight_moves, rook_moves};
 use core::masks::*;
+use core::piece_move::BitMove;
+use core::{ALL_PIECE_TYPES, ALL_PLAYERS};
 use tools::prng::PRNG;
-use {BitBoard, Piece, SQ};
+use {BitBoard, Piece, PieceType, Player, SQ};
 
 /// Seed for the Zobrist's pseudo-random number generator.
 const ZOBRIST_SEED: u64 = 23_081;
@@ -20,6 +25,33 @@ static mut ZOBRIST_SIDE: u64 = 0;
 /// Zobrist key for having no pawns;
 static mut ZOBRIST_NO_PAWNS: u64 = 0;
 
+/// Zobrist key for the number of checks each player has given, as counted by Three-check.
+/// Giving no checks has a key of zero.
+static mut ZOBRIST_CHECKS: [[u64; 4]; PLAYER_CNT] = [[0; 4]; PLAYER_CNT];
+
+/// The most pieces of a single type a Crazyhouse pocket can hold.
+pub(crate) const MAX_POCKET: usize = 16;
+
+/// Zobrist key for the number of each piece type in each player's Crazyhouse pocket. An empty
+/// pocket has a key of zero.
+static mut ZOBRIST_POCKET: [[[u64; MAX_POCKET + 1]; PIECE_TYPE_CNT]; PLAYER_CNT] =
+    [[[0; MAX_POCKET + 1]; PIECE_TYPE_CNT]; PLAYER_CNT];
+
+/// Number of slots in each cuckoo table.
+const CUCKOO_SIZE: usize = 8192;
+
+/// The number of reversible moves, those by a non-pawn piece between two squares, stored in the
+/// cuckoo tables.
+const CUCKOO_MOVES: usize = 3668;
+
+/// For each reversible move, the zobrist key of the piece moving between the two squares xor'd
+/// with the key for the side to move. Looked up with the two hash functions `cuckoo_h1` and
+/// `cuckoo_h2`, with empty slots set to zero.
+static mut CUCKOO: [u64; CUCKOO_SIZE] = [0; CUCKOO_SIZE];
+
+/// The move for each key of `CUCKOO`, from the lower of the two squares to the higher.
+static mut CUCKOO_MOVE: [BitMove; CUCKOO_SIZE] = [BitMove::null(); CUCKOO_SIZE];
+
 /// initialize the zobrist hash
 #[cold]
 pub fn init_zobrist() {
@@ -27,7 +59,7 @@ pub fn init_zobrist() {
 
     unsafe {
         for i in 0..SQ_CNT {
-            for j in (Piece::WhitePawn as usize)..(Piece::BlackKing as usize) {
+            for j in (Piece::WhitePawn as usize)..=(Piece::BlackKing as usize) {
                 ZOBRIST_PIECE_SQUARE[i][j] = rng.rand();
                 ZOBRIST_PIECE_SQUARE[i][j] = rng.rand();
             }
@@ -53,7 +85,101 @@ pub fn init_zobrist() {
         }
         ZOBRIST_SIDE = rng.rand();
         ZOBRIST_NO_PAWNS = rng.rand();
+
+        let mut checks = [[0; 4]; PLAYER_CNT];
+        for player_checks in checks.iter_mut() {
+            for key in player_checks[1..].iter_mut() {
+                *key = rng.rand();
+            }
+        }
+        ZOBRIST_CHECKS = checks;
+
+        let mut pocket = [[[0; MAX_POCKET + 1]; PIECE_TYPE_CNT]; PLAYER_CNT];
+        for player_pocket in pocket.iter_mut() {
+            for piece_counts in player_pocket.iter_mut() {
+                for key in piece_counts[1..].iter_mut() {
+                    *key = rng.rand();
+                }
+            }
+        }
+        ZOBRIST_POCKET = pocket;
+    }
+}
+
+/// Initializes the cuckoo tables of reversible moves. Must be called after the zobrist keys and
+/// the knight, king and sliding piece attacks are initialized.
+#[cold]
+pub fn init_cuckoo() {
+    let mut cuckoo = [0; CUCKOO_SIZE];
+    let mut cuckoo_move = [BitMove::null(); CUCKOO_SIZE];
+    let mut count = 0;
+    for &player in ALL_PLAYERS.iter() {
+        for &piece_type in ALL_PIECE_TYPES.iter().filter(|&&p| p != PieceType::P) {
+            let piece = Piece::make_lossy(player, piece_type);
+            for s1 in 0..SQ_CNT as u8 {
+                let s1 = SQ(s1);
+                let attacks = match piece_type {
+                    PieceType::N => knight_moves(s1),
+                    PieceType::B => bishop_moves(BitBoard(0), s1),
+                    PieceType::R => rook_moves(BitBoard(0), s1),
+                    PieceType::Q => bishop_moves(BitBoard(0), s1) | rook_moves(BitBoard(0), s1),
+                    _ => king_moves(s1),
+                };
+                for s2 in attacks.filter(|&s2| s2 > s1) {
+                    let mut mov = BitMove::make_quiet(s1, s2);
+                    let mut key = z_square(s1, piece) ^ z_square(s2, piece) ^ z_side();
+                    let mut i = cuckoo_h1(key);
+                    // Insert the key, pushing out any key already in its slot to that key's
+                    // other slot, until an empty slot is reached
+                    loop {
+                        mem::swap(&mut cuckoo[i], &mut key);
+                        mem::swap(&mut cuckoo_move[i], &mut mov);
+                        if mov.is_null() {
+                            break;
+                        }
+                        i = if i == cuckoo_h1(key) {
+                            cuckoo_h2(key)
+                        } else {
+                            cuckoo_h1(key)
+                        };
+                    }
+                    count += 1;
+                }
+            }
+        }
     }
+    debug_assert_eq!(count, CUCKOO_MOVES);
+
+    unsafe {
+        CUCKOO = cuckoo;
+        CUCKOO_MOVE = cuckoo_move;
+    }
+}
+
+#[inline(always)]
+fn cuckoo_h1(key: u64) -> usize {
+    (key & 0x1fff) as usize
+}
+
+#[inline(always)]
+fn cuckoo_h2(key: u64) -> usize {
+    ((key >> 16) & 0x1fff) as usize
+}
+
+/// Returns the reversible move whose cuckoo key is the given key, if there is one.
+#[inline(always)]
+pub fn cuckoo_move(key: u64) -> Option<BitMove> {
+    unsafe {
+        let i = cuckoo_h1(key);
+        if CUCKOO[i] == key {
+            return Some(CUCKOO_MOVE[i]);
+        }
+        let i = cuckoo_h2(key);
+        if CUCKOO[i] == key {
+            return Some(CUCKOO_MOVE[i]);
+        }
+    }
+    None
 }
 
 #[inline(always)]
@@ -83,3 +209,15 @@ pub fn z_side() -> u64 {
 pub fn z_no_pawns() -> u64 {
     unsafe { ZOBRIST_NO_PAWNS }
 }
+
+#[inline(always)]
+pub fn z_checks(player: Player, checks: u8) -> u64 {
+    debug_assert!(checks < 4);
+    unsafe { ZOBRIST_CHECKS[player as usize][checks as usize] }
+}
+
+#[inline(always)]
+pub fn z_pocket(player: Player, piece: PieceType, count: u8) -> u64 {
+    debug_assert!(count as usize <= MAX_POCKET);
+    unsafe { ZOBRIST_POCKET[player as usize][piece as usize][count as usize] }
+}
@@ -78,6 +78,9 @@ extern crate mucow;
 extern crate num_cpus;
 extern crate rand;
 extern crate rayon;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 pub mod board;
 pub mod bots;
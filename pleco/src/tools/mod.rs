@@ -5,6 +5,7 @@
 
 pub mod eval;
 pub mod pleco_arc;
+pub mod polyglot;
 pub mod prng;
 pub mod tt;
 
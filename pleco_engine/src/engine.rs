@@ -1,15 +1,19 @@
 //! The main searching structure.
 
-use std::io;
+use std::fs::File;
+use std::io::{self, BufReader};
 use std::sync::atomic::Ordering;
+use std::time::Instant;
 
+use pleco::board::perft::{perft_divide, perft_parallel};
+use pleco::tools::polyglot::PolyglotBook;
 use pleco::BitMove;
 use pleco::Board;
 
 use consts::*;
 use threadpool::threadpool;
 use time::uci_timer::PreLimits;
-use uci::options::{OptionWork, OptionsMap};
+use uci::options::{OptionWork, OptionsMap, NO_BOOK_FILE};
 use uci::parse;
 
 use search::eval::Evaluation;
@@ -31,6 +35,9 @@ pub struct PlecoSearcher {
     options: OptionsMap,
     search_mode: SearchType,
     board: Board,
+    chess960: bool,
+    own_book: bool,
+    book: Option<PolyglotBook<BufReader<File>>>,
 }
 
 impl PlecoSearcher {
@@ -41,6 +48,9 @@ impl PlecoSearcher {
             options: OptionsMap::new(),
             search_mode: SearchType::None,
             board: Board::start_pos(),
+            chess960: false,
+            own_book: false,
+            book: None,
         }
     }
 
@@ -60,14 +70,14 @@ impl PlecoSearcher {
                 "ucinewgame" => self.clear_search(),
                 "isready" => println!("readyok"),
                 "position" => {
-                    if let Some(b) = parse::position_parse_board(&args[1..]) {
+                    if let Some(b) = parse::position_parse_board(&args[1..], self.chess960) {
                         self.board = b;
                     } else {
                         println!("unable to parse board");
                     }
                 }
                 "setboard" => {
-                    if let Some(b) = parse::setboard_parse_board(&args[1..]) {
+                    if let Some(b) = parse::setboard_parse_board(&args[1..], self.chess960) {
                         self.board = b;
                     } else {
                         println!("unable to parse board");
@@ -80,6 +90,8 @@ impl PlecoSearcher {
                 }
                 "stop" => self.halt(),
                 "eval" => Evaluation::trace(&self.board),
+                "perft" => self.perft(&args[1..]),
+                "divide" => self.divide(&args[1..]),
                 _ => print!("Unknown Command: {}", full_command),
             }
             self.apply_all_options();
@@ -93,9 +105,66 @@ impl PlecoSearcher {
 
     fn uci_go(&mut self, args: &[&str]) {
         let limit = parse::parse_time(&args);
+        if !limit.infinite && !limit.ponder && limit.search_moves.is_empty() {
+            if let Some(mov) = self.book_move() {
+                println!("bestmove {}", self.board.move_to_uci(mov));
+                return;
+            }
+        }
         threadpool().uci_search(&self.board, &limit.create())
     }
 
+    /// Returns a move from the opening book for the current position, if `OwnBook` is
+    /// enabled and the book has one.
+    fn book_move(&mut self) -> Option<BitMove> {
+        if !self.own_book {
+            return None;
+        }
+        let board = &self.board;
+        self.book
+            .as_mut()
+            .and_then(|book| book.weighted_move(board).unwrap_or(None))
+    }
+
+    fn set_book_file(&mut self, path: &str) {
+        self.book = None;
+        if path.is_empty() || path == NO_BOOK_FILE {
+            return;
+        }
+        match PolyglotBook::open(path) {
+            Ok(book) => self.book = Some(book),
+            Err(e) => println!("info string unable to open book '{}': {}", path, e),
+        }
+    }
+
+    fn perft(&self, args: &[&str]) {
+        if let Some(depth) = args.first().and_then(|d| d.parse::<u16>().ok()) {
+            let start = Instant::now();
+            let nodes = perft_parallel(&self.board, depth);
+            let millis = start.elapsed().as_millis().max(1);
+            println!("Nodes searched: {}", nodes);
+            println!("Time: {} ms, {} nps", millis, nodes as u128 * 1000 / millis);
+        } else {
+            println!("perft [depth]");
+        }
+    }
+
+    fn divide(&self, args: &[&str]) {
+        if let Some(depth) = args.first().and_then(|d| d.parse::<u16>().ok()) {
+            let divide = perft_divide(&self.board, depth);
+            for &(mov, nodes) in divide.iter() {
+                println!("{}: {}", self.board.move_to_uci(mov), nodes);
+            }
+            println!();
+            println!(
+                "Nodes searched: {}",
+                divide.iter().map(|&(_, n)| n).sum::<u64>()
+            );
+        } else {
+            println!("divide [depth]");
+        }
+    }
+
     fn apply_option(&mut self, full_command: &str) {
         let mut args = full_command.split_whitespace();
         args.next().unwrap(); // setoption
@@ -153,6 +222,12 @@ impl PlecoSearcher {
                     OptionWork::ClearTT => self.clear_tt(),
                     OptionWork::ResizeTT(mb) => self.resize_tt(mb),
                     OptionWork::Threads(num) => threadpool().set_thread_count(num),
+                    OptionWork::Chess960(chess960) => {
+                        self.chess960 = chess960;
+                        self.board.set_chess960(chess960);
+                    }
+                    OptionWork::OwnBook(own_book) => self.own_book = own_book,
+                    OptionWork::BookFile(path) => self.set_book_file(&path),
                 }
             }
         }
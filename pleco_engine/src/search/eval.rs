@@ -608,10 +608,11 @@ impl<'a, 'b, T: Tracing> EvaluationInner<'a, 'b, T> {
                 self.king_ring[us as usize] |= P::shift_up(b);
             }
 
+            // Widen the ring towards the centre, which is the same direction for both players
             if ksq_us.file() == File::H {
-                self.king_ring[us as usize] |= P::shift_left(b);
+                self.king_ring[us as usize] |= (b & !BitBoard::FILE_A) >> 1;
             } else if ksq_us.file() == File::A {
-                self.king_ring[us as usize] |= P::shift_right(b);
+                self.king_ring[us as usize] |= (b & !BitBoard::FILE_H) << 1;
             }
 
             self.king_attackers_count[them as usize] =
@@ -992,7 +993,7 @@ impl<'a, 'b, T: Tracing> EvaluationInner<'a, 'b, T> {
                         & rook_moves(occ_all, s));
 
                 score += SLIDER_ON_QUEEN
-                    * (b * safe_threats & self.attacked_by2[us as usize]).count_bits();
+                    * (b & safe_threats & self.attacked_by2[us as usize]).count_bits();
             }
         }
 
@@ -1245,6 +1246,7 @@ fn king_proximity(king_sq: SQ, sq: SQ) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pleco::board::fen::ALL_FENS;
 
     //    #[test]
     //    fn eval_stuff() {
@@ -1263,6 +1265,46 @@ mod tests {
     //
     //    }
 
+    // Asserts that a position evaluates the same as its colour-flipped twin, and as its
+    // mirror image when it has no castling rights.
+    fn assert_symmetric(board: &Board) {
+        let mut pawn_table = PawnTable::new();
+        let mut material = Material::new();
+        let value = Evaluation::evaluate(board, &mut pawn_table, &mut material);
+
+        let flipped = board.flip().unwrap();
+        let flipped_value = Evaluation::evaluate(&flipped, &mut pawn_table, &mut material);
+        assert_eq!(
+            value,
+            flipped_value,
+            "fen: {}, flipped: {}",
+            board.fen(),
+            flipped.fen()
+        );
+
+        if let Some(mirrored) = board.mirror_horizontal() {
+            let mirrored_value = Evaluation::evaluate(&mirrored, &mut pawn_table, &mut material);
+            assert_eq!(
+                value,
+                mirrored_value,
+                "fen: {}, mirrored: {}",
+                board.fen(),
+                mirrored.fen()
+            );
+        }
+    }
+
+    #[test]
+    fn eval_symmetry() {
+        ::consts::init_globals();
+        for fen in ALL_FENS.iter() {
+            assert_symmetric(&Board::from_fen(fen).unwrap());
+        }
+        for board in Board::random().pseudo_random(81_726_354).many(200).iter() {
+            assert_symmetric(board);
+        }
+    }
+
     #[test]
     fn bad_board() {
         let board =
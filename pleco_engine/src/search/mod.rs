@@ -293,7 +293,7 @@ impl Searcher {
         self.best_move = best_move;
 
         if self.use_stdout() {
-            println!("bestmove {}", best_move.to_string());
+            println!("bestmove {}", self.board.move_to_uci(best_move));
         }
     }
 
@@ -495,6 +495,14 @@ impl Searcher {
             return DRAW as i32;
         }
 
+        // If a move drawing by repetition is available, the position is worth at least a draw.
+        if ss.ply > 0 && alpha < DRAW && self.board.has_game_cycle(ss.ply) {
+            alpha = DRAW;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
         if depth < 1 {
             return self.qsearch::<N>(alpha, beta, ss, 0);
         }
@@ -1078,6 +1086,13 @@ impl Searcher {
             return DRAW as i32;
         }
 
+        if alpha < DRAW && self.board.has_game_cycle(ss.ply) {
+            alpha = DRAW;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
         let is_pv: bool = N::is_pv();
 
         assert!(alpha >= NEG_INFINITE);
@@ -1455,7 +1470,10 @@ impl Searcher {
             s.push_str(&format!(" hashfull {:.2}", tt().hash_percent()));
         }
         s.push_str(&format!(" time {}", elapsed));
-        s.push_str(&format!(" pv {}", root_move.bit_move.to_string()));
+        s.push_str(&format!(
+            " pv {}",
+            self.board.move_to_uci(root_move.bit_move)
+        ));
         println!("{}", s);
     }
 }
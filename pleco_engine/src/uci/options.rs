@@ -7,11 +7,17 @@ use std::option::Option;
 
 use num_cpus;
 
+/// The value of the `BookFile` option when no book is set.
+pub const NO_BOOK_FILE: &str = "<empty>";
+
 /// A List of work for the Searcher to do following the application of options
 pub enum OptionWork {
     ClearTT,
     ResizeTT(usize),
     Threads(usize),
+    Chess960(bool),
+    OwnBook(bool),
+    BookFile(String),
 }
 
 impl OptionWork {
@@ -20,6 +26,9 @@ impl OptionWork {
             OptionWork::ClearTT => false,
             OptionWork::ResizeTT(_) => false,
             OptionWork::Threads(_) => false,
+            OptionWork::Chess960(_) => true,
+            OptionWork::OwnBook(_) => true,
+            OptionWork::BookFile(_) => true,
         }
     }
 }
@@ -38,6 +47,9 @@ impl OptionsMap {
         map.push(OptionsMap::clear_hash());
         map.push(OptionsMap::resize_hash());
         map.push(OptionsMap::threads());
+        map.push(OptionsMap::chess960());
+        map.push(OptionsMap::own_book());
+        map.push(OptionsMap::book_file());
         map.sort_by(|a, b| a.option_name().cmp(b.option_name()));
 
         OptionsMap { map, work }
@@ -99,6 +111,34 @@ impl OptionsMap {
             mutator,
         })
     }
+
+    fn chess960() -> Box<dyn UCIOption> {
+        let mutator: fn(bool) -> Option<OptionWork> = |x: bool| Some(OptionWork::Chess960(x));
+        Box::new(UCICheck {
+            option_name: "UCI_Chess960",
+            default: false,
+            mutator,
+        })
+    }
+
+    fn own_book() -> Box<dyn UCIOption> {
+        let mutator: fn(bool) -> Option<OptionWork> = |x: bool| Some(OptionWork::OwnBook(x));
+        Box::new(UCICheck {
+            option_name: "OwnBook",
+            default: false,
+            mutator,
+        })
+    }
+
+    fn book_file() -> Box<dyn UCIOption> {
+        let mutator: fn(&str) -> Option<OptionWork> =
+            |x: &str| Some(OptionWork::BookFile(x.to_string()));
+        Box::new(UCIText {
+            option_name: "BookFile",
+            default: NO_BOOK_FILE,
+            mutator,
+        })
+    }
 }
 
 // "option name Nullmove type check default true\n"
@@ -291,4 +331,22 @@ mod tests {
         let all = OptionsMap::new();
         all.display_all();
     }
+
+    #[test]
+    fn book_options() {
+        let mut all = OptionsMap::new();
+        assert!(all.apply_option("OwnBook", "true"));
+        assert!(all.apply_option("BookFile", "books/performance.bin"));
+        assert!(!all.apply_option("OwnBook", "maybe"));
+
+        match all.work() {
+            Some(OptionWork::OwnBook(true)) => {}
+            _ => panic!("expected OwnBook work"),
+        }
+        match all.work() {
+            Some(OptionWork::BookFile(ref path)) => assert_eq!(path, "books/performance.bin"),
+            _ => panic!("expected BookFile work"),
+        }
+        assert!(all.work().is_none());
+    }
 }
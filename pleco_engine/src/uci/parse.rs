@@ -135,7 +135,7 @@ fn valid_move(board: &mut Board, mov: &str) -> bool {
     let all_moves = board
         .generate_moves()
         .iter()
-        .map(|m| m.stringify())
+        .map(|m| board.move_to_uci(*m))
         .collect::<Vec<String>>();
 
     if all_moves.contains(&mov.to_string()) {
@@ -149,17 +149,24 @@ fn valid_move(board: &mut Board, mov: &str) -> bool {
     false
 }
 
-pub fn setboard_parse_board(args: &[&str]) -> Option<Board> {
+/// Parses a board from a FEN string. If `chess960` is set, the board uses Chess960 castling
+/// notation.
+pub fn setboard_parse_board(args: &[&str], chess960: bool) -> Option<Board> {
     let fen_string: String = args
         .iter()
         .take_while(|p: &&&str| **p != "moves")
         .map(|p| (*p).to_string())
         .collect::<Vec<String>>()
         .join(" ");
-    Board::from_fen(&fen_string).ok()
+    Board::from_fen(&fen_string).ok().map(|mut board| {
+        board.set_chess960(chess960);
+        board
+    })
 }
 
-pub fn position_parse_board(args: &[&str]) -> Option<Board> {
+/// Parses the arguments of a UCI `position` command. If `chess960` is set, the board uses
+/// Chess960 castling notation, and castling moves are read as the king capturing its own rook.
+pub fn position_parse_board(args: &[&str], chess960: bool) -> Option<Board> {
     let start: &str = args[0];
     let mut board = if start == "startpos" {
         Some(Board::start_pos())
@@ -174,6 +181,9 @@ pub fn position_parse_board(args: &[&str]) -> Option<Board> {
     } else {
         None
     };
+    if let Some(ref mut op_board) = board {
+        op_board.set_chess960(chess960);
+    }
 
     let mut moves_start: Option<usize> = None;
     for (i, mov) in args.iter().enumerate() {
@@ -199,7 +209,7 @@ pub fn position_parse_board(args: &[&str]) -> Option<Board> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pleco::Player;
+    use pleco::{Piece, Player, SQ};
 
     // TODO: More testing
 
@@ -207,13 +217,13 @@ mod tests {
     fn board_parse() {
         let b_str = "position startpos moves e2e4 e7e5";
         let args: Vec<&str> = b_str.split_whitespace().collect();
-        let board = position_parse_board(&args[1..]).unwrap();
+        let board = position_parse_board(&args[1..], false).unwrap();
         assert_eq!(board.moves_played(), 2);
         assert_eq!(board.turn(), Player::White);
 
         let b_str = "position startpos";
         let args: Vec<&str> = b_str.split_whitespace().collect();
-        let board = position_parse_board(&args[1..]).unwrap();
+        let board = position_parse_board(&args[1..], false).unwrap();
         assert_eq!(board.moves_played(), 0);
     }
 
@@ -231,6 +241,23 @@ mod tests {
         let old_str = "position startpos moves e2e4 d7d5 e4d5 d8d5 g1f3 d5e4 f1e2 c7c6 e1g1";
         // e8c8
         let args: Vec<&str> = old_str.split_whitespace().collect();
-        position_parse_board(&args[1..]).unwrap();
+        position_parse_board(&args[1..], false).unwrap();
+    }
+
+    #[test]
+    fn board_parse_chess960() {
+        let b_str = "position fen 4k3/8/8/8/8/8/8/1R3KR1 w GB - 0 1 moves f1g1";
+        let args: Vec<&str> = b_str.split_whitespace().collect();
+        let board = position_parse_board(&args[1..], true).unwrap();
+        assert_eq!(board.moves_played(), 1);
+        assert_eq!(board.piece_at_sq(SQ::G1), Piece::WhiteKing);
+        assert_eq!(board.piece_at_sq(SQ::F1), Piece::WhiteRook);
+
+        let b_str = "position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1h1";
+        let args: Vec<&str> = b_str.split_whitespace().collect();
+        let board = position_parse_board(&args[1..], true).unwrap();
+        assert_eq!(board.moves_played(), 7);
+        let board = position_parse_board(&args[1..], false).unwrap();
+        assert_eq!(board.moves_played(), 6);
     }
 }